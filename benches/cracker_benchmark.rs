@@ -72,45 +72,160 @@ fn bench_wordlist_preprocessing(c: &mut Criterion) {
 }
 
 /// Benchmark: Batch preparation overhead
+///
+/// Compares padding every candidate into MD5 blocks on the host, as batches
+/// used to be uploaded, with packing the raw bytes and offsets the kernels
+/// now pad themselves
 fn bench_batch_preparation(c: &mut Criterion) {
     let mut group = c.benchmark_group("Batch Preparation");
 
-    let wordlist = generate_wordlist(BATCH_SIZE, "batch");
-    let wordlist_refs: Vec<&str> = wordlist.iter().map(|s| s.as_str()).collect();
-
-    group.bench_function("prepare_single_batch", |b| {
-        b.iter(|| {
-            // Simulate the data preparation that happens in process_batch
-            let mut message_data_bytes = Vec::new();
-            let mut message_lengths = Vec::new();
-            let mut message_offsets = Vec::new();
-            let mut current_offset = 0u32;
-
-            for msg in black_box(&wordlist_refs) {
-                let msg_bytes = msg.as_bytes();
-                message_offsets.push(current_offset);
-                message_lengths.push(msg_bytes.len() as u32);
-                message_data_bytes.extend_from_slice(msg_bytes);
-                current_offset += msg_bytes.len() as u32;
-            }
-
-            // Convert to u32 array
-            let mut message_data_u32 = Vec::new();
-            for chunk in message_data_bytes.chunks(4) {
-                let mut word = 0u32;
-                for (i, &byte) in chunk.iter().enumerate() {
-                    word |= (byte as u32) << (i * 8);
+    for (name, wordlist) in [
+        ("short", generate_wordlist(BATCH_SIZE, "b")),
+        ("varied", generate_varied_length_wordlist(BATCH_SIZE)),
+    ] {
+        let wordlist_refs: Vec<&str> = wordlist.iter().map(|s| s.as_str()).collect();
+        group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+
+        let mut blocks = Vec::new();
+        let mut block_offsets = Vec::new();
+        group.bench_function(format!("padded_md5_blocks_{name}"), |b| {
+            b.iter(|| {
+                blocks.clear();
+                block_offsets.clear();
+                block_offsets.push(0u32);
+                for msg in black_box(&wordlist_refs) {
+                    append_padded_blocks(msg.as_bytes(), &mut blocks);
+                    block_offsets.push((blocks.len() / 16) as u32);
+                }
+                black_box((blocks.len(), block_offsets.len()))
+            })
+        });
+
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::new();
+        group.bench_function(format!("packed_bytes_{name}"), |b| {
+            b.iter(|| {
+                bytes.clear();
+                offsets.clear();
+                offsets.push(0u32);
+                for msg in black_box(&wordlist_refs) {
+                    bytes.extend_from_slice(msg.as_bytes());
+                    offsets.push(bytes.len() as u32);
                 }
-                message_data_u32.push(word);
-            }
+                bytes.resize(bytes.len().next_multiple_of(4) + 4, 0);
+                black_box((bytes.len(), offsets.len()))
+            })
+        });
+    }
 
-            black_box((message_data_u32, message_lengths, message_offsets))
-        })
-    });
+    group.finish();
+}
+
+/// Benchmark: GPU throughput of padded and packed batches
+///
+/// Times copying each batch to a GPU buffer in both layouts, with the
+/// bytes moved as the throughput, then the packed batch going through the
+/// MD5 kernel, which now pads candidates itself
+fn bench_batch_upload_gpu(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Batch Upload");
+    group.sample_size(10);
+
+    let (device, queue) = open_device();
+    let mut cracker = pollster::block_on(GpuCracker::new()).expect("Failed to initialize GPU");
+    let target_hash = md5_hash("not in the wordlist");
+
+    for (name, wordlist) in [
+        ("short", generate_wordlist(BATCH_SIZE, "b")),
+        ("varied", generate_varied_length_wordlist(BATCH_SIZE)),
+    ] {
+        let mut padded = Vec::new();
+        let mut padded_offsets = vec![0u32];
+        let mut packed = Vec::new();
+        let mut packed_offsets = vec![0u32];
+        for word in &wordlist {
+            append_padded_blocks(word.as_bytes(), &mut padded);
+            padded_offsets.push((padded.len() / 16) as u32);
+            packed.extend_from_slice(word.as_bytes());
+            packed_offsets.push(packed.len() as u32);
+        }
+        packed.resize(packed.len().next_multiple_of(4) + 4, 0);
+        let uploads: [(&str, &[u8], &[u32]); 2] = [
+            ("padded", bytemuck::cast_slice(&padded), &padded_offsets),
+            ("packed", &packed, &packed_offsets),
+        ];
+
+        let size = uploads
+            .iter()
+            .map(|(_, bytes, offsets)| bytes.len() + offsets.len() * 4)
+            .max()
+            .unwrap();
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Upload Benchmark Buffer"),
+            size: size.next_multiple_of(4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        for (layout, bytes, offsets) in uploads {
+            let offsets: &[u8] = bytemuck::cast_slice(offsets);
+            group.throughput(Throughput::Bytes((bytes.len() + offsets.len()) as u64));
+            group.bench_function(format!("{layout}_{name}_upload"), |b| {
+                b.iter(|| {
+                    queue.write_buffer(&buffer, 0, bytes);
+                    queue.write_buffer(&buffer, bytes.len() as u64, offsets);
+                    queue.submit([]);
+                    device
+                        .poll(wgpu::PollType::Wait {
+                            submission_index: None,
+                            timeout: None,
+                        })
+                        .unwrap()
+                })
+            });
+        }
+
+        let wordlist_refs: Vec<&str> = wordlist.iter().map(String::as_str).collect();
+        group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+        group.bench_function(format!("packed_{name}_md5"), |b| {
+            b.iter(|| {
+                cracker
+                    .process_batch(black_box(&wordlist_refs), black_box(&target_hash))
+                    .unwrap()
+            })
+        });
+    }
 
     group.finish();
 }
 
+/// A device of its own for the raw upload benchmarks
+fn open_device() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::VULKAN,
+        ..Default::default()
+    });
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        ..Default::default()
+    }))
+    .expect("Failed to find a GPU");
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+        .expect("Failed to open the GPU")
+}
+
+/// Host-side MD5 padding of one message into 16-word blocks
+fn append_padded_blocks(msg: &[u8], out: &mut Vec<u32>) {
+    let mut data = msg.to_vec();
+    data.push(0x80);
+    while data.len() % 64 != 56 {
+        data.push(0);
+    }
+    data.extend_from_slice(&(msg.len() as u64 * 8).to_le_bytes());
+    out.extend(
+        data.chunks_exact(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])),
+    );
+}
+
 /// Benchmark: GPU cracking throughput (end-to-end with small batches)
 fn bench_gpu_throughput(c: &mut Criterion) {
     let mut group = c.benchmark_group("GPU Throughput");
@@ -136,7 +251,11 @@ fn bench_gpu_throughput(c: &mut Criterion) {
             BenchmarkId::from_parameter(format!("{batch_size}_hashes")),
             batch_size,
             |b, _| {
-                b.iter(|| cracker.process_batch(black_box(&wordlist_refs), black_box(&target_hash)))
+                b.iter(|| {
+                    cracker
+                        .process_batch(black_box(&wordlist_refs), black_box(&target_hash))
+                        .unwrap()
+                })
             },
         );
     }
@@ -158,7 +277,12 @@ fn bench_end_to_end_cracking(c: &mut Criterion) {
     let wordlist_start_refs: Vec<&str> = wordlist_start.iter().map(|s| s.as_str()).collect();
 
     group.bench_function("password_at_start_50k", |b| {
-        b.iter(|| cracker.crack(black_box(&hash_start), black_box(&wordlist_start_refs)))
+        b.iter(|| {
+            cracker
+                .crack(black_box(&hash_start), black_box(&wordlist_start_refs))
+                .unwrap()
+                .found
+        })
     });
 
     // Scenario 2: Password in the middle
@@ -168,7 +292,12 @@ fn bench_end_to_end_cracking(c: &mut Criterion) {
     let wordlist_middle_refs: Vec<&str> = wordlist_middle.iter().map(|s| s.as_str()).collect();
 
     group.bench_function("password_in_middle_50k", |b| {
-        b.iter(|| cracker.crack(black_box(&hash_middle), black_box(&wordlist_middle_refs)))
+        b.iter(|| {
+            cracker
+                .crack(black_box(&hash_middle), black_box(&wordlist_middle_refs))
+                .unwrap()
+                .found
+        })
     });
 
     // Scenario 3: Password at the end
@@ -178,7 +307,12 @@ fn bench_end_to_end_cracking(c: &mut Criterion) {
     let wordlist_end_refs: Vec<&str> = wordlist_end.iter().map(|s| s.as_str()).collect();
 
     group.bench_function("password_at_end_50k", |b| {
-        b.iter(|| cracker.crack(black_box(&hash_end), black_box(&wordlist_end_refs)))
+        b.iter(|| {
+            cracker
+                .crack(black_box(&hash_end), black_box(&wordlist_end_refs))
+                .unwrap()
+                .found
+        })
     });
 
     // Scenario 4: Password not found (worst case)
@@ -189,10 +323,13 @@ fn bench_end_to_end_cracking(c: &mut Criterion) {
 
     group.bench_function("password_not_found_10k", |b| {
         b.iter(|| {
-            cracker.crack(
-                black_box(&hash_not_found),
-                black_box(&wordlist_not_found_refs),
-            )
+            cracker
+                .crack(
+                    black_box(&hash_not_found),
+                    black_box(&wordlist_not_found_refs),
+                )
+                .unwrap()
+                .found
         })
     });
 
@@ -212,7 +349,11 @@ fn bench_variable_password_lengths(c: &mut Criterion) {
     let target_hash = md5_hash("pwd999");
 
     group.bench_function("short_passwords_4-7_chars", |b| {
-        b.iter(|| cracker.process_batch(black_box(&short_refs), black_box(&target_hash)))
+        b.iter(|| {
+            cracker
+                .process_batch(black_box(&short_refs), black_box(&target_hash))
+                .unwrap()
+        })
     });
 
     // Test with uniform long passwords
@@ -222,7 +363,11 @@ fn bench_variable_password_lengths(c: &mut Criterion) {
     let long_refs: Vec<&str> = long_wordlist.iter().map(|s| s.as_str()).collect();
 
     group.bench_function("long_passwords_40-50_chars", |b| {
-        b.iter(|| cracker.process_batch(black_box(&long_refs), black_box(&target_hash)))
+        b.iter(|| {
+            cracker
+                .process_batch(black_box(&long_refs), black_box(&target_hash))
+                .unwrap()
+        })
     });
 
     // Test with varied lengths
@@ -230,7 +375,11 @@ fn bench_variable_password_lengths(c: &mut Criterion) {
     let varied_refs: Vec<&str> = varied_wordlist.iter().map(|s| s.as_str()).collect();
 
     group.bench_function("varied_passwords_4-64_chars", |b| {
-        b.iter(|| cracker.process_batch(black_box(&varied_refs), black_box(&target_hash)))
+        b.iter(|| {
+            cracker
+                .process_batch(black_box(&varied_refs), black_box(&target_hash))
+                .unwrap()
+        })
     });
 
     group.finish();
@@ -242,6 +391,7 @@ criterion_group!(
     bench_wordlist_loading,
     bench_wordlist_preprocessing,
     bench_batch_preparation,
+    bench_batch_upload_gpu,
     bench_gpu_throughput,
     bench_end_to_end_cracking,
     bench_variable_password_lengths,
@@ -28,7 +28,8 @@ fn bench_pure_gpu_timing(c: &mut Criterion) {
     group.bench_function("full_batch_gpu_only", |b| {
         b.iter(|| {
             let (_result, gpu_time) = cracker
-                .process_batch_with_timing(black_box(&wordlist_refs), black_box(&target_hash));
+                .process_batch_with_timing(black_box(&wordlist_refs), black_box(&target_hash))
+                .unwrap();
 
             if let Some(time_ns) = gpu_time {
                 // Calculate hashes per second
@@ -61,7 +62,9 @@ fn bench_gpu_hashing_rate(_c: &mut Criterion) {
 
     // Warm-up run
     for _ in 0..3 {
-        cracker.process_batch_with_timing(&wordlist_refs, &target_hash);
+        cracker
+            .process_batch_with_timing(&wordlist_refs, &target_hash)
+            .unwrap();
     }
 
     // Measure multiple runs
@@ -69,7 +72,9 @@ fn bench_gpu_hashing_rate(_c: &mut Criterion) {
     let num_runs = 10;
 
     for _ in 0..num_runs {
-        let (_result, gpu_time) = cracker.process_batch_with_timing(&wordlist_refs, &target_hash);
+        let (_result, gpu_time) = cracker
+            .process_batch_with_timing(&wordlist_refs, &target_hash)
+            .unwrap();
         if let Some(time_ns) = gpu_time {
             total_time_ns += time_ns;
         }
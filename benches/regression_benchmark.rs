@@ -0,0 +1,338 @@
+//! Regression gate for hash rates
+//!
+//! Runs the built-in benchmark of every hash mode (or of each `--mode`),
+//! plus the GPU time of one full MD5 batch from `process_batch_with_timing`,
+//! writes the results as JSON and compares them with the baseline stored
+//! for this device. A rate that dropped, or a batch time that grew, by more
+//! than the tolerance fails the run with a table of what changed, and so
+//! does a missing baseline unless `--write-baseline` records one.
+//!
+//! The JSON is the object `rustcracker benchmark --json` prints, with the
+//! MD5 batch time added as `batch_gpu_ns`, so the command's output can serve
+//! as a baseline too.
+//!
+//! ```text
+//! cargo bench --bench regression_benchmark -- [--mode <mode>]... [--runtime <s>]
+//!     [--tolerance [<mode>=]<percent>]... [--baseline-file <file>]
+//!     [--output <file>] [--write-baseline]
+//! ```
+//!
+//! The flags are named apart from criterion's `--baseline` and
+//! `--save-baseline`, and arguments the harness does not know, such as the
+//! filters and flags `cargo bench` passes to every target, are ignored.
+
+use rustcracker::{benchmark_json, GpuCracker, BATCH_SIZE, BENCHMARK_MODES};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs, process};
+
+mod benchmark_utils;
+use benchmark_utils::*;
+
+/// Time each mode is measured for unless set with `--runtime`
+const DEFAULT_RUNTIME: Duration = Duration::from_secs(2);
+/// How much worse than the baseline a result may be, in percent, unless set
+/// with `--tolerance`
+const DEFAULT_TOLERANCE: f64 = 10.0;
+/// Timed runs of the MD5 batch, of which the fastest counts
+const BATCH_RUNS: usize = 5;
+
+struct Options {
+    modes: Vec<String>,
+    runtime: Duration,
+    tolerance: f64,
+    mode_tolerances: HashMap<String, f64>,
+    baseline: Option<PathBuf>,
+    output: PathBuf,
+    write_baseline: bool,
+}
+
+/// Whether a larger value of a metric is an improvement or a regression
+#[derive(Clone, Copy, PartialEq)]
+enum Better {
+    Higher,
+    Lower,
+}
+
+/// Metrics compared against the baseline, by field name
+const METRICS: [(&str, Better); 3] = [
+    ("hashes_per_sec", Better::Higher),
+    ("gpu_hashes_per_sec", Better::Higher),
+    ("batch_gpu_ns", Better::Lower),
+];
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: cargo bench --bench regression_benchmark -- [--mode <mode>]... [--runtime <s>]"
+    );
+    eprintln!("           [--tolerance [<mode>=]<percent>]... [--baseline-file <file>]");
+    eprintln!("           [--output <file>] [--write-baseline]");
+    eprintln!();
+    eprintln!("Modes: {}", BENCHMARK_MODES.join(", "));
+    eprintln!("The baseline defaults to benches/baselines/<device>.json and the results");
+    eprintln!("to target/regression/results.json; tolerances default to {DEFAULT_TOLERANCE}%.");
+    process::exit(2);
+}
+
+fn parse_options() -> Options {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut options = Options {
+        modes: Vec::new(),
+        runtime: DEFAULT_RUNTIME,
+        tolerance: DEFAULT_TOLERANCE,
+        mode_tolerances: HashMap::new(),
+        baseline: None,
+        output: root.join("target/regression/results.json"),
+        write_baseline: false,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mode" => match args.next() {
+                Some(mode) if BENCHMARK_MODES.contains(&mode.as_str()) => options.modes.push(mode),
+                _ => usage(),
+            },
+            "--runtime" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(seconds) => options.runtime = Duration::from_secs_f64(seconds),
+                None => usage(),
+            },
+            "--tolerance" => {
+                let Some(value) = args.next() else { usage() };
+                let (mode, percent) = match value.split_once('=') {
+                    Some((mode, percent)) => (Some(mode.to_string()), percent),
+                    None => (None, value.as_str()),
+                };
+                let Ok(percent) = percent.parse::<f64>() else {
+                    usage()
+                };
+                match mode {
+                    Some(mode) => {
+                        options.mode_tolerances.insert(mode, percent);
+                    }
+                    None => options.tolerance = percent,
+                }
+            }
+            "--baseline-file" => match args.next() {
+                Some(path) => options.baseline = Some(path.into()),
+                None => usage(),
+            },
+            "--output" => match args.next() {
+                Some(path) => options.output = path.into(),
+                None => usage(),
+            },
+            "--write-baseline" => options.write_baseline = true,
+            // `--bench` and filters from `cargo bench`, or arguments meant
+            // for the criterion benches run alongside
+            _ => {}
+        }
+    }
+    if options.modes.is_empty() {
+        options.modes = BENCHMARK_MODES
+            .iter()
+            .map(|mode| mode.to_string())
+            .collect();
+    }
+    options
+}
+
+/// File name a device's baseline is kept under, such as
+/// `nvidia-geforce-rtx-4090-vulkan`
+fn device_slug(adapter: &wgpu::AdapterInfo) -> String {
+    format!("{} {:?}", adapter.name, adapter.backend)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Benchmark every mode in `options` and collect the results
+fn run(cracker: &mut GpuCracker, options: &Options) -> Value {
+    let mut results = Vec::new();
+    for mode in &options.modes {
+        eprintln!("Benchmarking {mode}...");
+        let result = cracker
+            .benchmark(mode, options.runtime)
+            .unwrap_or_else(|e| panic!("{mode} benchmark failed: {e}"));
+        results.push(result);
+    }
+    let batch_gpu_ns = options
+        .modes
+        .iter()
+        .any(|mode| mode == "md5")
+        .then(|| md5_batch_gpu_ns(cracker));
+
+    let mut current: Value =
+        serde_json::from_str(&benchmark_json(Some(cracker.adapter_info()), &results))
+            .expect("benchmark JSON parses");
+    if let Some(batch_gpu_ns) = batch_gpu_ns {
+        let md5 = current["results"]
+            .as_array_mut()
+            .and_then(|results| results.iter_mut().find(|result| result["mode"] == "md5"))
+            .expect("md5 result");
+        md5["batch_gpu_ns"] = json!(batch_gpu_ns);
+    }
+    current
+}
+
+/// GPU time of one full batch of MD5 candidates, fastest of a few runs,
+/// when the device has timestamp queries
+fn md5_batch_gpu_ns(cracker: &mut GpuCracker) -> Option<u64> {
+    let batch_size = cracker.batch_size().min(BATCH_SIZE);
+    let wordlist = generate_wordlist(batch_size, "regression");
+    let wordlist_refs: Vec<&str> = wordlist.iter().map(String::as_str).collect();
+    let target_hash = md5_hash("not in the wordlist");
+
+    // The first run warms up the pipeline
+    let mut fastest = None;
+    for run in 0..=BATCH_RUNS {
+        let (_, gpu_time_ns) = cracker
+            .process_batch_with_timing(&wordlist_refs, &target_hash)
+            .expect("MD5 batch failed");
+        if run > 0 {
+            fastest = gpu_time_ns.map(|ns| fastest.map_or(ns, |fastest: u64| fastest.min(ns)));
+        }
+    }
+    fastest
+}
+
+/// One metric of one mode against the baseline
+struct Change {
+    mode: String,
+    metric: &'static str,
+    baseline: f64,
+    current: f64,
+    regressed: bool,
+}
+
+/// Compare every metric both results have, mode by mode
+fn compare(baseline: &Value, current: &Value, options: &Options) -> Vec<Change> {
+    let empty = Vec::new();
+    let baseline_results = baseline["results"].as_array().unwrap_or(&empty);
+    let mut changes = Vec::new();
+    for result in current["results"].as_array().unwrap_or(&empty) {
+        let mode = result["mode"].as_str().unwrap_or_default();
+        let Some(before) = baseline_results
+            .iter()
+            .find(|before| before["mode"].as_str() == Some(mode))
+        else {
+            eprintln!("note: {mode} is not in the baseline");
+            continue;
+        };
+        if before["batch_size"] != result["batch_size"] {
+            eprintln!(
+                "note: {mode} ran with batch size {} against {} in the baseline",
+                result["batch_size"], before["batch_size"]
+            );
+        }
+
+        let tolerance = options
+            .mode_tolerances
+            .get(mode)
+            .copied()
+            .unwrap_or(options.tolerance)
+            / 100.0;
+        for (metric, better) in METRICS {
+            let (Some(baseline), Some(current)) =
+                (before[metric].as_f64(), result[metric].as_f64())
+            else {
+                continue;
+            };
+            let ratio = current / baseline;
+            let regressed = match better {
+                Better::Higher => ratio < 1.0 - tolerance,
+                Better::Lower => ratio > 1.0 + tolerance,
+            };
+            changes.push(Change {
+                mode: mode.to_string(),
+                metric,
+                baseline,
+                current,
+                regressed,
+            });
+        }
+    }
+    changes
+}
+
+fn print_changes(changes: &[Change]) {
+    println!(
+        "{:<10} {:<20} {:>16} {:>16} {:>9}",
+        "mode", "metric", "baseline", "current", "change"
+    );
+    for change in changes {
+        println!(
+            "{:<10} {:<20} {:>16.0} {:>16.0} {:>+8.1}%{}",
+            change.mode,
+            change.metric,
+            change.baseline,
+            change.current,
+            (change.current / change.baseline - 1.0) * 100.0,
+            if change.regressed { "  REGRESSION" } else { "" }
+        );
+    }
+}
+
+fn write_json(path: &Path, value: &Value) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(|e| panic!("cannot create {}: {e}", dir.display()));
+    }
+    let text = serde_json::to_string_pretty(value).expect("results serialize") + "\n";
+    fs::write(path, text).unwrap_or_else(|e| panic!("cannot write {}: {e}", path.display()));
+}
+
+fn main() {
+    let options = parse_options();
+    let mut cracker = pollster::block_on(GpuCracker::new()).expect("Failed to initialize GPU");
+    let baseline_path = options.baseline.clone().unwrap_or_else(|| {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("benches/baselines")
+            .join(format!("{}.json", device_slug(cracker.adapter_info())))
+    });
+
+    let current = run(&mut cracker, &options);
+    write_json(&options.output, &current);
+    println!("Results written to {}", options.output.display());
+
+    if options.write_baseline {
+        write_json(&baseline_path, &current);
+        println!("Baseline saved to {}", baseline_path.display());
+        return;
+    }
+
+    let baseline: Value = match fs::read_to_string(&baseline_path) {
+        Ok(text) => serde_json::from_str(&text)
+            .unwrap_or_else(|e| panic!("{} is not valid JSON: {e}", baseline_path.display())),
+        Err(e) => {
+            eprintln!(
+                "No baseline at {} ({e}); record one with --write-baseline",
+                baseline_path.display()
+            );
+            process::exit(2);
+        }
+    };
+    if baseline["device"]["name"] != current["device"]["name"] {
+        eprintln!(
+            "Baseline {} was recorded on {}, not {}",
+            baseline_path.display(),
+            baseline["device"]["name"],
+            current["device"]["name"]
+        );
+        process::exit(2);
+    }
+
+    let changes = compare(&baseline, &current, &options);
+    print_changes(&changes);
+    let regressions = changes.iter().filter(|change| change.regressed).count();
+    if regressions > 0 {
+        eprintln!(
+            "{regressions} metric(s) regressed against {}",
+            baseline_path.display()
+        );
+        process::exit(1);
+    }
+    println!("No regressions against {}", baseline_path.display());
+}
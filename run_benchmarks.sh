@@ -78,6 +78,9 @@ OPTIONS:
     -a, --all           Run all benchmarks
     -q, --quick         Run quick benchmarks (fewer samples)
     -t, --timing        Run GPU timing benchmarks only
+    -r, --regression    Compare hash rates with this device's stored baseline
+                        and fail on a regression
+    --update-baseline   Store this run's hash rates as the device's baseline
     -f, --full          Run full comprehensive benchmarks
     -h, --help          Show this help message
     --baseline NAME     Save results as baseline for comparison
@@ -93,6 +96,7 @@ Examples:
     $0 --quick --all                  # Quick run of all benchmarks
     $0 --baseline v1.0                # Run benchmarks and save as v1.0 baseline
     $0 --compare v1.0                 # Compare with v1.0 baseline
+    $0 --regression                   # Fail if a hash mode got slower
     $0 "GPU Throughput"               # Run only GPU Throughput benchmarks
     $0 --check-env                    # Check if environment is optimal
 
@@ -114,6 +118,8 @@ run_benchmark() {
 main() {
     local run_all=false
     local run_timing=false
+    local run_regression=false
+    local regression_args=""
     local run_full=false
     local quick_mode=""
     local baseline=""
@@ -136,6 +142,15 @@ main() {
                 run_timing=true
                 shift
                 ;;
+            -r|--regression)
+                run_regression=true
+                shift
+                ;;
+            --update-baseline)
+                run_regression=true
+                regression_args="--write-baseline"
+                shift
+                ;;
             -f|--full)
                 run_full=true
                 shift
@@ -170,6 +185,12 @@ main() {
         exit 0
     fi
     
+    if [ "$run_regression" = true ]; then
+        print_header "Running: regression_benchmark"
+        cargo bench --bench regression_benchmark -- $regression_args
+        exit $?
+    fi
+
     # Build extra arguments
     local extra_args=""
     if [ -n "$baseline" ]; then
@@ -1,6 +1,8 @@
 #![cfg_attr(target_arch = "spirv", no_std)]
 
-use spirv_std::glam::UVec3;
+use spirv_std::arch::atomic_i_add;
+use spirv_std::glam::{UVec3, UVec4};
+use spirv_std::memory::{Scope, Semantics};
 use spirv_std::spirv;
 
 // MD5 constants
@@ -28,102 +30,1831 @@ const K_TABLE: [u32; 64] = [
     0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
 ];
 
+// Number of hits a kernel that lists them can record, after the count in
+// the first word of the result buffer; must match the host
+const RESULT_SLOTS: usize = 1024;
+
+// Every kernel reads its dispatch from the uniform at binding 3: x is the
+// number of candidates in the batch and w the first one this dispatch
+// covers, since the host may split a batch over several dispatches to keep
+// each one short. Iterated kernels also take a window of iterations in y-z.
+
+// SHA1 initial state
+const SHA1_H0: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+// SHA256 initial state and round constants
+const SHA256_H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// MD4 per-round shift amounts and message word order
+const MD4_SHIFTS: [u32; 12] = [3, 7, 11, 19, 3, 5, 9, 13, 3, 9, 11, 15];
+const MD4_ORDER: [usize; 48] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3,
+    7, 11, 15, 0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15,
+];
+
+// DES key schedule tables (1-based bit positions, FIPS 46-3)
+const DES_PC1: [u32; 56] = [
+    57, 49, 41, 33, 25, 17, 9, 1, 58, 50, 42, 34, 26, 18, 10, 2, 59, 51, 43, 35, 27, 19, 11, 3, 60,
+    52, 44, 36, 63, 55, 47, 39, 31, 23, 15, 7, 62, 54, 46, 38, 30, 22, 14, 6, 61, 53, 45, 37, 29,
+    21, 13, 5, 28, 20, 12, 4,
+];
+const DES_PC2: [u32; 48] = [
+    14, 17, 11, 24, 1, 5, 3, 28, 15, 6, 21, 10, 23, 19, 12, 4, 26, 8, 16, 7, 27, 20, 13, 2, 41, 52,
+    31, 37, 47, 55, 30, 40, 51, 45, 33, 48, 44, 49, 39, 56, 34, 53, 46, 42, 50, 36, 29, 32,
+];
+const DES_KEY_SHIFTS: [u32; 16] = [1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1];
+const DES_P: [u32; 32] = [
+    16, 7, 20, 21, 29, 12, 28, 17, 1, 15, 23, 26, 5, 18, 31, 10, 2, 8, 24, 14, 32, 27, 3, 9, 19,
+    13, 30, 6, 22, 11, 4, 25,
+];
+const DES_SBOX: [[u32; 64]; 8] = [
+    [
+        14, 4, 13, 1, 2, 15, 11, 8, 3, 10, 6, 12, 5, 9, 0, 7, 0, 15, 7, 4, 14, 2, 13, 1, 10, 6, 12,
+        11, 9, 5, 3, 8, 4, 1, 14, 8, 13, 6, 2, 11, 15, 12, 9, 7, 3, 10, 5, 0, 15, 12, 8, 2, 4, 9, 1,
+        7, 5, 11, 3, 14, 10, 0, 6, 13,
+    ],
+    [
+        15, 1, 8, 14, 6, 11, 3, 4, 9, 7, 2, 13, 12, 0, 5, 10, 3, 13, 4, 7, 15, 2, 8, 14, 12, 0, 1,
+        10, 6, 9, 11, 5, 0, 14, 7, 11, 10, 4, 13, 1, 5, 8, 12, 6, 9, 3, 2, 15, 13, 8, 10, 1, 3, 15,
+        4, 2, 11, 6, 7, 12, 0, 5, 14, 9,
+    ],
+    [
+        10, 0, 9, 14, 6, 3, 15, 5, 1, 13, 12, 7, 11, 4, 2, 8, 13, 7, 0, 9, 3, 4, 6, 10, 2, 8, 5, 14,
+        12, 11, 15, 1, 13, 6, 4, 9, 8, 15, 3, 0, 11, 1, 2, 12, 5, 10, 14, 7, 1, 10, 13, 0, 6, 9, 8,
+        7, 4, 15, 14, 3, 11, 5, 2, 12,
+    ],
+    [
+        7, 13, 14, 3, 0, 6, 9, 10, 1, 2, 8, 5, 11, 12, 4, 15, 13, 8, 11, 5, 6, 15, 0, 3, 4, 7, 2, 12,
+        1, 10, 14, 9, 10, 6, 9, 0, 12, 11, 7, 13, 15, 1, 3, 14, 5, 2, 8, 4, 3, 15, 0, 6, 10, 1, 13,
+        8, 9, 4, 5, 11, 12, 7, 2, 14,
+    ],
+    [
+        2, 12, 4, 1, 7, 10, 11, 6, 8, 5, 3, 15, 13, 0, 14, 9, 14, 11, 2, 12, 4, 7, 13, 1, 5, 0, 15,
+        10, 3, 9, 8, 6, 4, 2, 1, 11, 10, 13, 7, 8, 15, 9, 12, 5, 6, 3, 0, 14, 11, 8, 12, 7, 1, 14, 2,
+        13, 6, 15, 0, 9, 10, 4, 5, 3,
+    ],
+    [
+        12, 1, 10, 15, 9, 2, 6, 8, 0, 13, 3, 4, 14, 7, 5, 11, 10, 15, 4, 2, 7, 12, 9, 5, 6, 1, 13,
+        14, 0, 11, 3, 8, 9, 14, 15, 5, 2, 8, 12, 3, 7, 0, 4, 10, 1, 13, 11, 6, 4, 3, 2, 12, 9, 5, 15,
+        10, 11, 14, 1, 7, 6, 0, 8, 13,
+    ],
+    [
+        4, 11, 2, 14, 15, 0, 8, 13, 3, 12, 9, 7, 5, 10, 6, 1, 13, 0, 11, 7, 4, 9, 1, 10, 14, 3, 5,
+        12, 2, 15, 8, 6, 1, 4, 11, 13, 12, 3, 7, 14, 10, 15, 6, 8, 0, 5, 9, 2, 6, 11, 13, 8, 1, 4,
+        10, 7, 9, 5, 0, 15, 14, 2, 3, 12,
+    ],
+    [
+        13, 2, 8, 4, 6, 15, 11, 1, 10, 9, 3, 14, 5, 0, 12, 7, 1, 15, 13, 8, 10, 3, 7, 4, 12, 5, 6,
+        11, 0, 14, 9, 2, 7, 11, 4, 1, 9, 12, 14, 2, 0, 6, 10, 13, 15, 3, 5, 8, 2, 1, 14, 7, 4, 10, 8,
+        13, 15, 12, 9, 0, 3, 5, 6, 11,
+    ],
+];
+
+/// Combined S-box and P permutation lookup, indexed by the raw 6-bit
+/// S-box input
+const DES_SP: [[u32; 64]; 8] = des_sp_table();
+
+const fn des_sp_table() -> [[u32; 64]; 8] {
+    let mut sp = [[0u32; 64]; 8];
+    let mut i = 0;
+    while i < 8 {
+        let mut v = 0;
+        while v < 64 {
+            let row = ((v >> 4) & 2) | (v & 1);
+            let col = (v >> 1) & 15;
+            let pre = DES_SBOX[i][row * 16 + col] << (28 - 4 * i);
+            let mut out = 0u32;
+            let mut j = 0;
+            while j < 32 {
+                if (pre >> (32 - DES_P[j])) & 1 == 1 {
+                    out |= 1 << (31 - j);
+                }
+                j += 1;
+            }
+            sp[i][v] = out;
+            v += 1;
+        }
+        i += 1;
+    }
+    sp
+}
+
+/// CRC-32 (IEEE, reflected) lookup table
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
 #[inline]
 fn leftrotate(x: u32, amt: u32) -> u32 {
     (x << (amt % 32)) | (x >> (32 - (amt % 32)))
 }
 
+#[inline]
+fn rightrotate(x: u32, amt: u32) -> u32 {
+    (x >> amt) | (x << (32 - amt))
+}
+
+#[inline]
+fn bswap(x: u32) -> u32 {
+    (x << 24) | ((x << 8) & 0x00ff_0000) | ((x >> 8) & 0x0000_ff00) | (x >> 24)
+}
+
+/// Run the MD5 compression function over one 16-word block
+#[inline]
+fn md5_compress(h: [u32; 4], m: &[u32; 16]) -> [u32; 4] {
+    let mut a = h[0];
+    let mut b = h[1];
+    let mut c = h[2];
+    let mut d = h[3];
+
+    // 64 rounds
+    let mut i = 0;
+    while i < 64 {
+        let mut f;
+        let g;
+
+        if i < 16 {
+            f = (b & c) | ((!b) & d);
+            g = i;
+        } else if i < 32 {
+            f = (d & b) | ((!d) & c);
+            g = (5 * i + 1) % 16;
+        } else if i < 48 {
+            f = b ^ c ^ d;
+            g = (3 * i + 5) % 16;
+        } else {
+            f = c ^ (b | (!d));
+            g = (7 * i) % 16;
+        }
+
+        f = f
+            .wrapping_add(a)
+            .wrapping_add(K_TABLE[i])
+            .wrapping_add(m[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(leftrotate(f, SHIFT_AMTS[i]));
+
+        i += 1;
+    }
+
+    [
+        h[0].wrapping_add(a),
+        h[1].wrapping_add(b),
+        h[2].wrapping_add(c),
+        h[3].wrapping_add(d),
+    ]
+}
+
+/// Run the MD4 compression function over one 16-word block
+fn md4_compress(h: [u32; 4], m: &[u32; 16]) -> [u32; 4] {
+    let mut a = h[0];
+    let mut b = h[1];
+    let mut c = h[2];
+    let mut d = h[3];
+
+    let mut i = 0;
+    while i < 48 {
+        let (f, k) = if i < 16 {
+            ((b & c) | ((!b) & d), 0)
+        } else if i < 32 {
+            ((b & c) | (b & d) | (c & d), 0x5a827999)
+        } else {
+            (b ^ c ^ d, 0x6ed9eba1)
+        };
+
+        let t = a
+            .wrapping_add(f)
+            .wrapping_add(m[MD4_ORDER[i]])
+            .wrapping_add(k);
+        a = d;
+        d = c;
+        c = b;
+        b = leftrotate(t, MD4_SHIFTS[(i / 16) * 4 + i % 4]);
+
+        i += 1;
+    }
+
+    [
+        h[0].wrapping_add(a),
+        h[1].wrapping_add(b),
+        h[2].wrapping_add(c),
+        h[3].wrapping_add(d),
+    ]
+}
+
+/// Byte `i` of the packed messages buffer
+#[inline]
+fn message_byte(messages: &[u32], i: usize) -> u32 {
+    (messages[i / 4] >> ((i % 4) * 8)) & 0xff
+}
+
+/// Word `w` of the `len`-byte message at byte `start`, followed by the
+/// 0x80 byte and zeros of MD5 padding (the length words are not included)
+///
+/// Messages are packed back to back, so a word can straddle two words of
+/// the buffer; the host leaves a spare zero word after the last message.
+#[inline]
+fn padded_word(messages: &[u32], start: usize, len: usize, w: usize) -> u32 {
+    let byte = w * 4;
+    if byte > len {
+        return 0;
+    }
+    if byte == len {
+        return 0x80;
+    }
+
+    let at = start + byte;
+    let shift = (at % 4) as u32 * 8;
+    let mut word = messages[at / 4] >> shift;
+    if shift != 0 {
+        word |= messages[at / 4 + 1] << (32 - shift);
+    }
+
+    let remaining = (len - byte) as u32;
+    if remaining < 4 {
+        word = (word & ((1u32 << (remaining * 8)) - 1)) | (0x80 << (remaining * 8));
+    }
+    word
+}
+
+/// Number of 64-byte blocks a `len`-byte message pads out to
+#[inline]
+fn md5_block_count(len: usize) -> usize {
+    (len + 9).div_ceil(64)
+}
+
+/// Block `block` of a packed message with MD5 padding and length words
+/// (MD4 pads the same way)
+fn md5_block(messages: &[u32], start: usize, len: usize, block: usize) -> [u32; 16] {
+    let mut m = [0u32; 16];
+    let mut i = 0;
+    while i < 16 {
+        m[i] = padded_word(messages, start, len, block * 16 + i);
+        i += 1;
+    }
+    if block + 1 == md5_block_count(len) {
+        m[14] = (len * 8) as u32;
+        m[15] = 0;
+    }
+    m
+}
+
+/// MD4 of a packed message
+fn md4_blocks(messages: &[u32], start: usize, len: usize) -> [u32; 4] {
+    let mut h = [A0, B0, C0, D0];
+
+    let block_count = md5_block_count(len);
+    let mut block = 0;
+    while block < block_count {
+        let m = md5_block(messages, start, len, block);
+        h = md4_compress(h, &m);
+        block += 1;
+    }
+
+    h
+}
+
+/// Expand a 56-bit DES key (two big-endian words, the second holding 24
+/// bits) into 16 round keys of eight 6-bit chunks each
+fn des_key_schedule(k0: u32, k1: u32, subkeys: &mut [u32; 128]) {
+    // PC1 positions skip the parity bits, so map them onto the raw 56 bits
+    let mut c = 0u32;
+    let mut d = 0u32;
+    let mut i = 0;
+    while i < 56 {
+        let q = DES_PC1[i] - DES_PC1[i] / 8 - 1;
+        let bit = if q < 32 {
+            (k0 >> (31 - q)) & 1
+        } else {
+            (k1 >> (63 - q)) & 1
+        };
+        if i < 28 {
+            c |= bit << (27 - i);
+        } else {
+            d |= bit << (55 - i);
+        }
+        i += 1;
+    }
+
+    let mut round = 0;
+    while round < 16 {
+        let shift = DES_KEY_SHIFTS[round];
+        c = ((c << shift) | (c >> (28 - shift))) & 0x0fff_ffff;
+        d = ((d << shift) | (d >> (28 - shift))) & 0x0fff_ffff;
+
+        let mut chunk = 0;
+        while chunk < 8 {
+            let mut v = 0u32;
+            let mut j = 0;
+            while j < 6 {
+                let p = DES_PC2[chunk * 6 + j];
+                let bit = if p <= 28 {
+                    (c >> (28 - p)) & 1
+                } else {
+                    (d >> (56 - p)) & 1
+                };
+                v = (v << 1) | bit;
+                j += 1;
+            }
+            subkeys[round * 8 + chunk] = v;
+            chunk += 1;
+        }
+        round += 1;
+    }
+}
+
+/// 16 DES rounds on an already initial-permuted block. Returns the
+/// pre-output block (R16, L16), i.e. the ciphertext before the final
+/// permutation.
+fn des_rounds(l0: u32, r0: u32, subkeys: &[u32; 128]) -> (u32, u32) {
+    let mut l = l0;
+    let mut r = r0;
+    let mut round = 0;
+    while round < 16 {
+        let mut f = 0u32;
+        let mut chunk = 0;
+        while chunk < 8 {
+            // E expansion: chunk i covers bits 4i..4i+5 of R (1-based, wrapping)
+            let e = (leftrotate(r, (4 * chunk as u32 + 31) % 32) >> 26) & 0x3f;
+            f ^= DES_SP[chunk][(e ^ subkeys[round * 8 + chunk]) as usize];
+            chunk += 1;
+        }
+        let t = l ^ f;
+        l = r;
+        r = t;
+        round += 1;
+    }
+    (r, l)
+}
+
+/// MD5 of a packed message, padded block by block as it is hashed
+fn md5_blocks(messages: &[u32], start: usize, len: usize) -> [u32; 4] {
+    let mut h = [A0, B0, C0, D0];
+
+    let block_count = md5_block_count(len);
+    let mut block = 0;
+    while block < block_count {
+        let m = md5_block(messages, start, len, block);
+        h = md5_compress(h, &m);
+        block += 1;
+    }
+
+    h
+}
+
+/// Run the SHA1 compression function over one 16-word big-endian block
+fn sha1_compress(h: [u32; 5], block: &[u32; 16]) -> [u32; 5] {
+    let mut w = *block;
+    let mut a = h[0];
+    let mut b = h[1];
+    let mut c = h[2];
+    let mut d = h[3];
+    let mut e = h[4];
+
+    let mut i = 0;
+    while i < 80 {
+        if i >= 16 {
+            let t = w[(i + 13) & 15] ^ w[(i + 8) & 15] ^ w[(i + 2) & 15] ^ w[i & 15];
+            w[i & 15] = leftrotate(t, 1);
+        }
+
+        let (f, k) = if i < 20 {
+            ((b & c) | ((!b) & d), 0x5a827999)
+        } else if i < 40 {
+            (b ^ c ^ d, 0x6ed9eba1)
+        } else if i < 60 {
+            ((b & c) | (b & d) | (c & d), 0x8f1bbcdc)
+        } else {
+            (b ^ c ^ d, 0xca62c1d6)
+        };
+
+        let temp = leftrotate(a, 5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(w[i & 15]);
+        e = d;
+        d = c;
+        c = leftrotate(b, 30);
+        b = a;
+        a = temp;
+
+        i += 1;
+    }
+
+    [
+        h[0].wrapping_add(a),
+        h[1].wrapping_add(b),
+        h[2].wrapping_add(c),
+        h[3].wrapping_add(d),
+        h[4].wrapping_add(e),
+    ]
+}
+
+/// Run the SHA256 compression function over one 16-word big-endian block
+fn sha256_compress(h: [u32; 8], block: &[u32; 16]) -> [u32; 8] {
+    let mut w = *block;
+    let mut a = h[0];
+    let mut b = h[1];
+    let mut c = h[2];
+    let mut d = h[3];
+    let mut e = h[4];
+    let mut f = h[5];
+    let mut g = h[6];
+    let mut hh = h[7];
+
+    let mut i = 0;
+    while i < 64 {
+        if i >= 16 {
+            let w15 = w[(i + 1) & 15];
+            let w2 = w[(i + 14) & 15];
+            let s0 = rightrotate(w15, 7) ^ rightrotate(w15, 18) ^ (w15 >> 3);
+            let s1 = rightrotate(w2, 17) ^ rightrotate(w2, 19) ^ (w2 >> 10);
+            w[i & 15] = w[i & 15]
+                .wrapping_add(s0)
+                .wrapping_add(w[(i + 9) & 15])
+                .wrapping_add(s1);
+        }
+
+        let s1 = rightrotate(e, 6) ^ rightrotate(e, 11) ^ rightrotate(e, 25);
+        let ch = (e & f) ^ ((!e) & g);
+        let temp1 = hh
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(SHA256_K[i])
+            .wrapping_add(w[i & 15]);
+        let s0 = rightrotate(a, 2) ^ rightrotate(a, 13) ^ rightrotate(a, 22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        hh = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+
+        i += 1;
+    }
+
+    [
+        h[0].wrapping_add(a),
+        h[1].wrapping_add(b),
+        h[2].wrapping_add(c),
+        h[3].wrapping_add(d),
+        h[4].wrapping_add(e),
+        h[5].wrapping_add(f),
+        h[6].wrapping_add(g),
+        h[7].wrapping_add(hh),
+    ]
+}
+
+/// SHA1 of a packed message
+///
+/// The padding is the same as MD5's; only the word byte order and the
+/// order of the two length words differ.
+fn sha1_blocks(messages: &[u32], start: usize, len: usize) -> [u32; 5] {
+    let mut h = SHA1_H0;
+
+    let block_count = md5_block_count(len);
+    let mut block = 0;
+    while block < block_count {
+        let m = md5_block(messages, start, len, block);
+        let mut w = [0u32; 16];
+        let mut i = 0;
+        while i < 16 {
+            w[i] = bswap(m[i]);
+            i += 1;
+        }
+        if block + 1 == block_count {
+            w[14] = m[15];
+            w[15] = m[14];
+        }
+
+        h = sha1_compress(h, &w);
+        block += 1;
+    }
+
+    h
+}
+
+/// Extract byte `i` from a little-endian packed word array
+#[inline]
+fn byte_at<const N: usize>(words: &[u32; N], i: usize) -> u32 {
+    (words[i / 4] >> ((i % 4) * 8)) & 0xff
+}
+
 /// Main compute shader entry point
 /// Processes a batch of messages and checks them against a target hash
+///
+/// Target layout: words 0-3 hold the digest and words 4-7 a mask of the
+/// bits to compare (all ones for a full hash). A truncated or partial
+/// digest can match several candidates, so every match is recorded.
 #[spirv(compute(threads(64)))]
 pub fn md5_crack(
     #[spirv(global_invocation_id)] global_id: UVec3,
     #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
-    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target_hash: &[u32; 4],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target_hash: &[u32; 8],
     #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
-    #[spirv(uniform, descriptor_set = 0, binding = 3)] message_count: &u32,
-    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] block_offsets: &[u32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
 ) {
-    let idx = global_id.x as usize;
+    md5_crack_check(
+        (dispatch.w + global_id.x) as usize,
+        messages,
+        target_hash,
+        result_buffer,
+        dispatch.x as usize,
+        offsets,
+    );
+}
 
+/// Body of `md5_crack` and its workgroup size variants
+fn md5_crack_check(
+    idx: usize,
+    messages: &[u32],
+    target_hash: &[u32; 8],
+    result_buffer: &mut [i32],
+    message_count: usize,
+    offsets: &[u32],
+) {
     // Bounds checking
-    if idx >= *message_count as usize {
+    if idx >= message_count {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let h = md5_blocks(messages, start, len);
+
+    // Compare the masked bits with the target
+    if (h[0] ^ target_hash[0]) & target_hash[4] == 0
+        && (h[1] ^ target_hash[1]) & target_hash[5] == 0
+        && (h[2] ^ target_hash[2]) & target_hash[6] == 0
+        && (h[3] ^ target_hash[3]) & target_hash[7] == 0
+    {
+        record_hit(result_buffer, idx as i32);
+    }
+}
+
+/// MD5 round functions for the unrolled kernel
+#[inline]
+fn md5_f(b: u32, c: u32, d: u32) -> u32 {
+    d ^ (b & (c ^ d))
+}
+
+#[inline]
+fn md5_g(b: u32, c: u32, d: u32) -> u32 {
+    c ^ (d & (b ^ c))
+}
+
+#[inline]
+fn md5_h(b: u32, c: u32, d: u32) -> u32 {
+    b ^ c ^ d
+}
+
+#[inline]
+fn md5_i(b: u32, c: u32, d: u32) -> u32 {
+    c ^ (b | (!d))
+}
+
+/// One MD5 step with its message word, constant and shift spelled out
+macro_rules! md5_step {
+    ($f:ident, $a:ident, $b:ident, $c:ident, $d:ident, $m:expr, $k:expr, $s:expr) => {
+        $a = $b.wrapping_add(leftrotate(
+            $a.wrapping_add($f($b, $c, $d))
+                .wrapping_add($m)
+                .wrapping_add($k),
+            $s,
+        ));
+    };
+}
+
+/// Fast path of `md5_crack` for batches whose candidates all fit one
+/// block (55 bytes or less), against a full digest
+///
+/// The 64 steps are unrolled with their constants inlined. Words 8-11 of
+/// the target hold the digest minus the initial state, i.e. the registers
+/// after the last step, which the host works out once per target. The
+/// last four steps each produce one of those registers, so most
+/// candidates are rejected after step 60 without finishing the hash.
+#[spirv(compute(threads(64)))]
+pub fn md5_crack_short(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 12],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    md5_crack_short_check(
+        (dispatch.w + global_id.x) as usize,
+        messages,
+        target,
+        result_buffer,
+        dispatch.x as usize,
+        offsets,
+    );
+}
+
+/// Body of `md5_crack_short` and its workgroup size variants
+fn md5_crack_short_check(
+    idx: usize,
+    messages: &[u32],
+    target: &[u32; 12],
+    result_buffer: &mut [i32],
+    message_count: usize,
+    offsets: &[u32],
+) {
+    if idx >= message_count {
         return;
     }
 
-    let block_start = block_offsets[idx] as usize;
-    let block_end = block_offsets[idx + 1] as usize;
-    let num_blocks = block_end - block_start;
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let m = md5_block(messages, start, len, 0);
+
+    let mut a = A0;
+    let mut b = B0;
+    let mut c = C0;
+    let mut d = D0;
+
+    md5_step!(md5_f, a, b, c, d, m[0], 0xd76aa478, 7);
+    md5_step!(md5_f, d, a, b, c, m[1], 0xe8c7b756, 12);
+    md5_step!(md5_f, c, d, a, b, m[2], 0x242070db, 17);
+    md5_step!(md5_f, b, c, d, a, m[3], 0xc1bdceee, 22);
+    md5_step!(md5_f, a, b, c, d, m[4], 0xf57c0faf, 7);
+    md5_step!(md5_f, d, a, b, c, m[5], 0x4787c62a, 12);
+    md5_step!(md5_f, c, d, a, b, m[6], 0xa8304613, 17);
+    md5_step!(md5_f, b, c, d, a, m[7], 0xfd469501, 22);
+    md5_step!(md5_f, a, b, c, d, m[8], 0x698098d8, 7);
+    md5_step!(md5_f, d, a, b, c, m[9], 0x8b44f7af, 12);
+    md5_step!(md5_f, c, d, a, b, m[10], 0xffff5bb1, 17);
+    md5_step!(md5_f, b, c, d, a, m[11], 0x895cd7be, 22);
+    md5_step!(md5_f, a, b, c, d, m[12], 0x6b901122, 7);
+    md5_step!(md5_f, d, a, b, c, m[13], 0xfd987193, 12);
+    md5_step!(md5_f, c, d, a, b, m[14], 0xa679438e, 17);
+    md5_step!(md5_f, b, c, d, a, m[15], 0x49b40821, 22);
 
-    if num_blocks == 0 {
+    md5_step!(md5_g, a, b, c, d, m[1], 0xf61e2562, 5);
+    md5_step!(md5_g, d, a, b, c, m[6], 0xc040b340, 9);
+    md5_step!(md5_g, c, d, a, b, m[11], 0x265e5a51, 14);
+    md5_step!(md5_g, b, c, d, a, m[0], 0xe9b6c7aa, 20);
+    md5_step!(md5_g, a, b, c, d, m[5], 0xd62f105d, 5);
+    md5_step!(md5_g, d, a, b, c, m[10], 0x02441453, 9);
+    md5_step!(md5_g, c, d, a, b, m[15], 0xd8a1e681, 14);
+    md5_step!(md5_g, b, c, d, a, m[4], 0xe7d3fbc8, 20);
+    md5_step!(md5_g, a, b, c, d, m[9], 0x21e1cde6, 5);
+    md5_step!(md5_g, d, a, b, c, m[14], 0xc33707d6, 9);
+    md5_step!(md5_g, c, d, a, b, m[3], 0xf4d50d87, 14);
+    md5_step!(md5_g, b, c, d, a, m[8], 0x455a14ed, 20);
+    md5_step!(md5_g, a, b, c, d, m[13], 0xa9e3e905, 5);
+    md5_step!(md5_g, d, a, b, c, m[2], 0xfcefa3f8, 9);
+    md5_step!(md5_g, c, d, a, b, m[7], 0x676f02d9, 14);
+    md5_step!(md5_g, b, c, d, a, m[12], 0x8d2a4c8a, 20);
+
+    md5_step!(md5_h, a, b, c, d, m[5], 0xfffa3942, 4);
+    md5_step!(md5_h, d, a, b, c, m[8], 0x8771f681, 11);
+    md5_step!(md5_h, c, d, a, b, m[11], 0x6d9d6122, 16);
+    md5_step!(md5_h, b, c, d, a, m[14], 0xfde5380c, 23);
+    md5_step!(md5_h, a, b, c, d, m[1], 0xa4beea44, 4);
+    md5_step!(md5_h, d, a, b, c, m[4], 0x4bdecfa9, 11);
+    md5_step!(md5_h, c, d, a, b, m[7], 0xf6bb4b60, 16);
+    md5_step!(md5_h, b, c, d, a, m[10], 0xbebfbc70, 23);
+    md5_step!(md5_h, a, b, c, d, m[13], 0x289b7ec6, 4);
+    md5_step!(md5_h, d, a, b, c, m[0], 0xeaa127fa, 11);
+    md5_step!(md5_h, c, d, a, b, m[3], 0xd4ef3085, 16);
+    md5_step!(md5_h, b, c, d, a, m[6], 0x04881d05, 23);
+    md5_step!(md5_h, a, b, c, d, m[9], 0xd9d4d039, 4);
+    md5_step!(md5_h, d, a, b, c, m[12], 0xe6db99e5, 11);
+    md5_step!(md5_h, c, d, a, b, m[15], 0x1fa27cf8, 16);
+    md5_step!(md5_h, b, c, d, a, m[2], 0xc4ac5665, 23);
+
+    md5_step!(md5_i, a, b, c, d, m[0], 0xf4292244, 6);
+    md5_step!(md5_i, d, a, b, c, m[7], 0x432aff97, 10);
+    md5_step!(md5_i, c, d, a, b, m[14], 0xab9423a7, 15);
+    md5_step!(md5_i, b, c, d, a, m[5], 0xfc93a039, 21);
+    md5_step!(md5_i, a, b, c, d, m[12], 0x655b59c3, 6);
+    md5_step!(md5_i, d, a, b, c, m[3], 0x8f0ccc92, 10);
+    md5_step!(md5_i, c, d, a, b, m[10], 0xffeff47d, 15);
+    md5_step!(md5_i, b, c, d, a, m[1], 0x85845dd1, 21);
+    md5_step!(md5_i, a, b, c, d, m[8], 0x6fa87e4f, 6);
+    md5_step!(md5_i, d, a, b, c, m[15], 0xfe2ce6e0, 10);
+    md5_step!(md5_i, c, d, a, b, m[6], 0xa3014314, 15);
+    md5_step!(md5_i, b, c, d, a, m[13], 0x4e0811a1, 21);
+    md5_step!(md5_i, a, b, c, d, m[4], 0xf7537e82, 6);
+    if a != target[8] {
+        return;
+    }
+    md5_step!(md5_i, d, a, b, c, m[11], 0xbd3af235, 10);
+    if d != target[11] {
+        return;
+    }
+    md5_step!(md5_i, c, d, a, b, m[2], 0x2ad7d2bb, 15);
+    if c != target[10] {
+        return;
+    }
+    md5_step!(md5_i, b, c, d, a, m[9], 0xeb86d391, 21);
+    if b != target[9] {
         return;
     }
 
-    let mut h = [A0, B0, C0, D0];
+    record_hit(result_buffer, idx as i32);
+}
+
+/// Entry points running the body of a wordlist kernel with other
+/// workgroup sizes, for the host to pick between when autotuning
+macro_rules! workgroup_variants {
+    ($check:ident, $target_words:tt, $($name:ident: $threads:tt),+) => {$(
+        #[spirv(compute(threads($threads)))]
+        pub fn $name(
+            #[spirv(global_invocation_id)] global_id: UVec3,
+            #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+            #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; $target_words],
+            #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+            #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+            #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+        ) {
+            $check(
+                (dispatch.w + global_id.x) as usize,
+                messages,
+                target,
+                result_buffer,
+                dispatch.x as usize,
+                offsets,
+            );
+        }
+    )+};
+}
+
+workgroup_variants!(md5_crack_check, 8, md5_crack_wg128: 128, md5_crack_wg256: 256);
+workgroup_variants!(
+    md5_crack_short_check,
+    12,
+    md5_crack_short_wg128: 128,
+    md5_crack_short_wg256: 256
+);
+
+/// Initialize an RC4 state from a 128-bit key
+fn rc4_init(key: &[u32; 4], s: &mut [u32; 256]) {
+    let mut i = 0;
+    while i < 256 {
+        s[i] = i as u32;
+        i += 1;
+    }
+
+    let mut j = 0u32;
+    let mut i = 0;
+    while i < 256 {
+        j = (j + s[i] + byte_at(key, i % 16)) & 0xff;
+        let tmp = s[i];
+        s[i] = s[j as usize];
+        s[j as usize] = tmp;
+        i += 1;
+    }
+}
+
+/// Decrypt one little-endian word with the RC4 keystream, advancing the state
+fn rc4_xor_word(s: &mut [u32; 256], i: &mut u32, j: &mut u32, word: u32) -> u32 {
+    let mut out = 0u32;
+    let mut k = 0;
+    while k < 4 {
+        *i = (*i + 1) & 0xff;
+        *j = (*j + s[*i as usize]) & 0xff;
+        let tmp = s[*i as usize];
+        s[*i as usize] = s[*j as usize];
+        s[*j as usize] = tmp;
+        let ks = s[((s[*i as usize] + s[*j as usize]) & 0xff) as usize];
+        out |= (((word >> (k * 8)) & 0xff) ^ ks) << (k * 8);
+        k += 1;
+    }
+    out
+}
 
-    for block_idx in 0..num_blocks {
-        // Load preprocessed MD5 block (16 u32 words)
-        let base = (block_start + block_idx) * 16;
+/// MS Office 97-2003 RC4 (MD5 key derivation) verifier check
+///
+/// Messages are the UTF-16LE passwords. The target
+/// buffer holds the salt, encrypted verifier and encrypted verifier hash
+/// (4 words each).
+#[spirv(compute(threads(64)))]
+pub fn office_rc4_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 12],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let h0 = md5_blocks(messages, start, len);
+
+    // Intermediate hash over 16 repetitions of (first 5 bytes of h0 || salt),
+    // 336 bytes in total which pads out to 6 blocks
+    let mut h1 = [A0, B0, C0, D0];
+    let mut block = 0;
+    while block < 6 {
         let mut m = [0u32; 16];
-        let mut i = 0;
-        while i < 16 {
-            m[i] = messages[base + i];
-            i += 1;
+        let mut p = 0;
+        while p < 64 {
+            let pos = block * 64 + p;
+            let byte = if pos < 336 {
+                let r = pos % 21;
+                if r < 5 {
+                    byte_at(&h0, r)
+                } else {
+                    byte_at(target, r - 5)
+                }
+            } else if pos == 336 {
+                0x80
+            } else {
+                0
+            };
+            m[p / 4] |= byte << ((p % 4) * 8);
+            p += 1;
+        }
+        if block == 5 {
+            m[14] = 336 * 8;
+        }
+        h1 = md5_compress(h1, &m);
+        block += 1;
+    }
+
+    // Key for block 0: MD5(first 5 bytes of h1 || 0u32)
+    let mut m = [0u32; 16];
+    m[0] = h1[0];
+    m[1] = h1[1] & 0xff;
+    m[2] = 0x80 << 8;
+    m[14] = 9 * 8;
+    let key = md5_compress([A0, B0, C0, D0], &m);
+
+    let mut s = [0u32; 256];
+    rc4_init(&key, &mut s);
+    let mut i = 0u32;
+    let mut j = 0u32;
+
+    let mut verifier = [0u32; 4];
+    let mut k = 0;
+    while k < 4 {
+        verifier[k] = rc4_xor_word(&mut s, &mut i, &mut j, target[4 + k]);
+        k += 1;
+    }
+    let mut verifier_hash = [0u32; 4];
+    let mut k = 0;
+    while k < 4 {
+        verifier_hash[k] = rc4_xor_word(&mut s, &mut i, &mut j, target[8 + k]);
+        k += 1;
+    }
+
+    // The decrypted hash must be MD5 of the decrypted verifier
+    let mut m = [0u32; 16];
+    m[0] = verifier[0];
+    m[1] = verifier[1];
+    m[2] = verifier[2];
+    m[3] = verifier[3];
+    m[4] = 0x80;
+    m[14] = 16 * 8;
+    let h = md5_compress([A0, B0, C0, D0], &m);
+
+    if h[0] == verifier_hash[0]
+        && h[1] == verifier_hash[1]
+        && h[2] == verifier_hash[2]
+        && h[3] == verifier_hash[3]
+    {
+        result_buffer[0] = idx as i32;
+    }
+}
+
+/// MySQL 4.1+ native password check: SHA1(SHA1(password))
+///
+/// The target buffer holds the 20 digest bytes.
+#[spirv(compute(threads(64)))]
+pub fn mysql41_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 5],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let inner = sha1_blocks(messages, start, len);
+
+    // Outer SHA1 over the 20 raw bytes of the inner digest
+    let mut w = [0u32; 16];
+    w[0] = inner[0];
+    w[1] = inner[1];
+    w[2] = inner[2];
+    w[3] = inner[3];
+    w[4] = inner[4];
+    w[5] = 0x8000_0000;
+    w[15] = 20 * 8;
+    let h = sha1_compress(SHA1_H0, &w);
+
+    if h[0] == bswap(target[0])
+        && h[1] == bswap(target[1])
+        && h[2] == bswap(target[2])
+        && h[3] == bswap(target[3])
+        && h[4] == bswap(target[4])
+    {
+        result_buffer[0] = idx as i32;
+    }
+}
+
+/// Pre-4.1 MySQL `OLD_PASSWORD()` check
+///
+/// The target buffer holds the two 31-bit halves of the hash.
+#[spirv(compute(threads(64)))]
+pub fn mysql323_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 2],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let mut nr = 1345345333u32;
+    let mut nr2 = 0x12345671u32;
+    let mut add = 7u32;
+    let mut i = 0;
+    while i < len {
+        let c = message_byte(messages, start + i);
+        // Spaces and tabs are skipped by the server
+        if c != 0x20 && c != 0x09 {
+            nr ^= ((nr & 63).wrapping_add(add))
+                .wrapping_mul(c)
+                .wrapping_add(nr << 8);
+            nr2 = nr2.wrapping_add((nr2 << 8) ^ nr);
+            add = add.wrapping_add(c);
         }
+        i += 1;
+    }
+
+    if nr & 0x7fff_ffff == target[0] && nr2 & 0x7fff_ffff == target[1] {
+        result_buffer[0] = idx as i32;
+    }
+}
 
-        // Compute MD5 round
-        let mut a = h[0];
-        let mut b = h[1];
-        let mut c = h[2];
-        let mut d = h[3];
+/// NTLM check: MD4 of the UTF-16LE password
+#[spirv(compute(threads(64)))]
+pub fn ntlm_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target_hash: &[u32; 4],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
 
-        let aa = a;
-        let bb = b;
-        let cc = c;
-        let dd = d;
+    let h = md4_blocks(messages, start, len);
+
+    if h[0] == target_hash[0] && h[1] == target_hash[1] && h[2] == target_hash[2] && h[3] == target_hash[3] {
+        result_buffer[0] = idx as i32;
+    }
+}
 
-        // 64 rounds
+/// LM half check
+///
+/// Each candidate is uppercased and split into two 7-character halves; each
+/// half keys a DES encryption of "KGS!@#$%". The target buffer holds both
+/// target halves and the plaintext, all initial-permuted by the host as
+/// (high, low) word pairs. A half matching the first target half is
+/// reported in result slot 0 and one matching the second in slot 1, as
+/// `candidate * 2 + half`.
+#[spirv(compute(threads(64)))]
+pub fn lm_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 6],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    // LM is not defined for passwords longer than 14 characters
+    if len > 14 {
+        return;
+    }
+
+    let mut half = 0;
+    while half < 2 {
+        // An empty second half is the well-known AAD3B435B51404EE constant,
+        // which the host handles without a candidate
+        if half == 1 && len <= 7 {
+            break;
+        }
+
+        let mut k0 = 0u32;
+        let mut k1 = 0u32;
         let mut i = 0;
-        while i < 64 {
-            let mut f;
-            let g;
-
-            if i < 16 {
-                f = (b & c) | ((!b) & d);
-                g = i;
-            } else if i < 32 {
-                f = (d & b) | ((!d) & c);
-                g = (5 * i + 1) % 16;
-            } else if i < 48 {
-                f = b ^ c ^ d;
-                g = (3 * i + 5) % 16;
+        while i < 7 {
+            let pos = half * 7 + i;
+            let mut c = 0;
+            if pos < len {
+                c = message_byte(messages, start + pos);
+                if c >= 0x61 && c <= 0x7a {
+                    c -= 0x20;
+                }
+            }
+            if i < 4 {
+                k0 |= c << (24 - 8 * i);
             } else {
-                f = c ^ (b | (!d));
-                g = (7 * i) % 16;
+                k1 |= c << (24 - 8 * (i - 4));
             }
+            i += 1;
+        }
+
+        let mut subkeys = [0u32; 128];
+        des_key_schedule(k0, k1, &mut subkeys);
+        let (hi, lo) = des_rounds(target[4], target[5], &subkeys);
+
+        if hi == target[0] && lo == target[1] {
+            result_buffer[0] = (idx * 2 + half) as i32;
+        }
+        if hi == target[2] && lo == target[3] {
+            result_buffer[1] = (idx * 2 + half) as i32;
+        }
+        half += 1;
+    }
+}
+
+/// Byte `i` of a 16-byte digest held as four little-endian words
+#[inline]
+fn digest_byte(h: &[u32; 4], i: usize) -> u32 {
+    if i < 16 {
+        (h[i / 4] >> ((i % 4) * 8)) & 0xff
+    } else {
+        0
+    }
+}
+
+/// NetNTLMv1 check
+///
+/// The NT hash, zero-padded to 21 bytes, is split into three DES keys that
+/// each encrypt the challenge. The target buffer holds the (effective)
+/// challenge followed by the three response blocks, all initial-permuted by
+/// the host as (high, low) word pairs.
+#[spirv(compute(threads(64)))]
+pub fn netntlmv1_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 8],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let nt = md4_blocks(messages, start, len);
 
-            f = f
-                .wrapping_add(a)
-                .wrapping_add(K_TABLE[i])
-                .wrapping_add(m[g]);
-            a = d;
-            d = c;
-            c = b;
-            b = b.wrapping_add(leftrotate(f, SHIFT_AMTS[i]));
+    let mut part = 0;
+    while part < 3 {
+        let first = part * 7;
+        let k0 = (digest_byte(&nt, first) << 24)
+            | (digest_byte(&nt, first + 1) << 16)
+            | (digest_byte(&nt, first + 2) << 8)
+            | digest_byte(&nt, first + 3);
+        let k1 = (digest_byte(&nt, first + 4) << 24)
+            | (digest_byte(&nt, first + 5) << 16)
+            | (digest_byte(&nt, first + 6) << 8);
 
+        let mut subkeys = [0u32; 128];
+        des_key_schedule(k0, k1, &mut subkeys);
+        let (hi, lo) = des_rounds(target[0], target[1], &subkeys);
+
+        if hi != target[2 + part * 2] || lo != target[3 + part * 2] {
+            return;
+        }
+        part += 1;
+    }
+
+    result_buffer[0] = idx as i32;
+}
+
+/// HMAC-MD5 with a 16-byte key over message blocks the host has already
+/// padded (counting the 64-byte key block in the length). The blocks start
+/// at word `8 + first_block * 16` of `params`.
+fn hmac_md5_blocks(
+    key: &[u32; 4],
+    params: &[u32; 512],
+    first_block: usize,
+    block_count: usize,
+) -> [u32; 4] {
+    let mut ipad = [0x3636_3636u32; 16];
+    let mut opad = [0x5c5c_5c5cu32; 16];
+    let mut i = 0;
+    while i < 4 {
+        ipad[i] ^= key[i];
+        opad[i] ^= key[i];
+        i += 1;
+    }
+
+    let mut h = md5_compress([A0, B0, C0, D0], &ipad);
+    let mut block = 0;
+    while block < block_count {
+        let base = 8 + (first_block + block) * 16;
+        let mut m = [0u32; 16];
+        let mut i = 0;
+        while i < 16 {
+            m[i] = params[base + i];
             i += 1;
         }
+        h = md5_compress(h, &m);
+        block += 1;
+    }
 
-        a = a.wrapping_add(aa);
-        b = b.wrapping_add(bb);
-        c = c.wrapping_add(cc);
-        d = d.wrapping_add(dd);
+    // Outer hash: opad block, then the inner digest padded to 80 bytes
+    let outer = md5_compress([A0, B0, C0, D0], &opad);
+    let mut m = [0u32; 16];
+    m[0] = h[0];
+    m[1] = h[1];
+    m[2] = h[2];
+    m[3] = h[3];
+    m[4] = 0x80;
+    m[14] = 80 * 8;
+    md5_compress(outer, &m)
+}
 
-        h = [a, b, c, d];
+/// NetNTLMv2 check: NTProofStr = HMAC-MD5(HMAC-MD5(NT hash, USER || domain),
+/// server challenge || blob)
+///
+/// Target layout: words 0-3 hold the NTProofStr, word 4 the number of
+/// identity blocks and word 5 the number of challenge/blob blocks; the
+/// padded blocks themselves start at word 8.
+#[spirv(compute(threads(64)))]
+pub fn netntlmv2_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 512],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
     }
 
-    // Compare with target
-    if h[0] == target_hash[0] && h[1] == target_hash[1] && h[2] == target_hash[2] && h[3] == target_hash[3] {
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let identity_blocks = target[4] as usize;
+    let data_blocks = target[5] as usize;
+
+    let nt = md4_blocks(messages, start, len);
+    let ntlmv2_key = hmac_md5_blocks(&nt, target, 0, identity_blocks);
+    let proof = hmac_md5_blocks(&ntlmv2_key, target, identity_blocks, data_blocks);
+
+    if proof[0] == target[0] && proof[1] == target[1] && proof[2] == target[2] && proof[3] == target[3] {
+        result_buffer[0] = idx as i32;
+    }
+}
+
+/// A candidate of up to 64 bytes as a zero-padded big-endian HMAC key block
+fn hmac_key_block(messages: &[u32], start: usize, len: usize) -> [u32; 16] {
+    let mut key = [0u32; 16];
+    let mut i = 0;
+    while i < 16 {
+        let mut word = padded_word(messages, start, len, i);
+        if i * 4 + 4 > len {
+            let keep = if len > i * 4 { len - i * 4 } else { 0 };
+            word &= (1u32 << (keep * 8)) - 1;
+        }
+        key[i] = bswap(word);
+        i += 1;
+    }
+    key
+}
+
+/// HMAC-SHA1 inner and outer states for a key of up to 64 bytes, given as
+/// 16 big-endian words
+fn hmac_sha1_init(key: &[u32; 16]) -> ([u32; 5], [u32; 5]) {
+    let mut ipad = [0u32; 16];
+    let mut opad = [0u32; 16];
+    let mut i = 0;
+    while i < 16 {
+        ipad[i] = key[i] ^ 0x3636_3636;
+        opad[i] = key[i] ^ 0x5c5c_5c5c;
+        i += 1;
+    }
+    (sha1_compress(SHA1_H0, &ipad), sha1_compress(SHA1_H0, &opad))
+}
+
+/// Finish an HMAC-SHA1 whose message is a single 20-byte digest
+fn hmac_sha1_digest(inner: [u32; 5], outer: [u32; 5], digest: &[u32; 5]) -> [u32; 5] {
+    let mut m = [0u32; 16];
+    m[0] = digest[0];
+    m[1] = digest[1];
+    m[2] = digest[2];
+    m[3] = digest[3];
+    m[4] = digest[4];
+    m[5] = 0x8000_0000;
+    m[15] = (64 + 20) * 8;
+    let h = sha1_compress(inner, &m);
+
+    m[0] = h[0];
+    m[1] = h[1];
+    m[2] = h[2];
+    m[3] = h[3];
+    m[4] = h[4];
+    sha1_compress(outer, &m)
+}
+
+/// HMAC-SHA1 over message blocks the host has already padded (counting the
+/// 64-byte key block in the length), stored big-endian from word
+/// `8 + first_block * 16` of `params`
+fn hmac_sha1_blocks(
+    inner: [u32; 5],
+    outer: [u32; 5],
+    params: &[u32; 512],
+    first_block: usize,
+    block_count: usize,
+) -> [u32; 5] {
+    let mut h = inner;
+    let mut block = 0;
+    while block < block_count {
+        let base = 8 + (first_block + block) * 16;
+        let mut m = [0u32; 16];
+        let mut i = 0;
+        while i < 16 {
+            m[i] = params[base + i];
+            i += 1;
+        }
+        h = sha1_compress(h, &m);
+        block += 1;
+    }
+
+    let mut m = [0u32; 16];
+    m[0] = h[0];
+    m[1] = h[1];
+    m[2] = h[2];
+    m[3] = h[3];
+    m[4] = h[4];
+    m[5] = 0x8000_0000;
+    m[15] = (64 + 20) * 8;
+    sha1_compress(outer, &m)
+}
+
+/// One 20-byte PBKDF2-HMAC-SHA1 output block: `iterations` rounds starting
+/// from the salt block (salt || INT(i), pre-padded by the host) at
+/// `salt_block` in `params`
+fn pbkdf2_sha1_block(
+    inner: [u32; 5],
+    outer: [u32; 5],
+    params: &[u32; 512],
+    salt_block: usize,
+    iterations: u32,
+) -> [u32; 5] {
+    let mut u = hmac_sha1_blocks(inner, outer, params, salt_block, 1);
+    let mut t = u;
+    let mut i = 1;
+    while i < iterations {
+        u = hmac_sha1_digest(inner, outer, &u);
+        t[0] ^= u[0];
+        t[1] ^= u[1];
+        t[2] ^= u[2];
+        t[3] ^= u[3];
+        t[4] ^= u[4];
+        i += 1;
+    }
+    t
+}
+
+/// WPA/WPA2-PSK check (PMKID or EAPOL MIC)
+///
+/// PMK = PBKDF2-HMAC-SHA1(passphrase, ESSID, 4096, 32). Target layout:
+/// * words 0-3: the PMKID or MIC, in the word order of the final digest
+/// * word 4: 0 = PMKID, 1 = EAPOL with HMAC-MD5 MIC, 2 = EAPOL with
+///   HMAC-SHA1 MIC
+/// * word 5: blocks of the first HMAC message ("PMK Name" || MACs, or the
+///   PTK expansion input)
+/// * word 6: blocks of the EAPOL frame (MD5 layout for key version 1)
+/// * from word 8: the two salt blocks, then the first message, then the
+///   EAPOL frame, all padded as HMAC messages
+#[spirv(compute(threads(64)))]
+pub fn wpa_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 512],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    // WPA passphrases are 8 to 63 characters
+    if len < 8 || len > 63 {
+        return;
+    }
+
+    let key = hmac_key_block(messages, start, len);
+    let (inner, outer) = hmac_sha1_init(&key);
+    let t1 = pbkdf2_sha1_block(inner, outer, target, 0, 4096);
+    let t2 = pbkdf2_sha1_block(inner, outer, target, 1, 4096);
+
+    let mut pmk = [0u32; 16];
+    pmk[0] = t1[0];
+    pmk[1] = t1[1];
+    pmk[2] = t1[2];
+    pmk[3] = t1[3];
+    pmk[4] = t1[4];
+    pmk[5] = t2[0];
+    pmk[6] = t2[1];
+    pmk[7] = t2[2];
+
+    let first_blocks = target[5] as usize;
+    let eapol_blocks = target[6] as usize;
+    let (inner, outer) = hmac_sha1_init(&pmk);
+    let h = hmac_sha1_blocks(inner, outer, target, 2, first_blocks);
+
+    let check = if target[4] == 0 {
+        // PMKID = HMAC-SHA1-128(PMK, "PMK Name" || AA || SPA)
+        [h[0], h[1], h[2], h[3]]
+    } else if target[4] == 1 {
+        // WPA1: MIC = HMAC-MD5(KCK, EAPOL), with KCK the first PTK bytes
+        let kck = [bswap(h[0]), bswap(h[1]), bswap(h[2]), bswap(h[3])];
+        hmac_md5_blocks(&kck, target, 2 + first_blocks, eapol_blocks)
+    } else {
+        // WPA2: MIC = HMAC-SHA1-128(KCK, EAPOL)
+        let mut kck = [0u32; 16];
+        kck[0] = h[0];
+        kck[1] = h[1];
+        kck[2] = h[2];
+        kck[3] = h[3];
+        let (inner, outer) = hmac_sha1_init(&kck);
+        let mic = hmac_sha1_blocks(inner, outer, target, 2 + first_blocks, eapol_blocks);
+        [mic[0], mic[1], mic[2], mic[3]]
+    };
+
+    if check[0] == target[0] && check[1] == target[1] && check[2] == target[2] && check[3] == target[3] {
         result_buffer[0] = idx as i32;
     }
 }
+
+/// Append `value` to the list of hits, for kernels whose check can let
+/// several candidates of a batch through. The first word counts every hit
+/// and starts at 0; hits beyond the last slot are counted but dropped.
+fn record_hit(result_buffer: &mut [i32], value: i32) {
+    let slot = unsafe {
+        atomic_i_add::<i32, { Scope::Device as u32 }, { Semantics::NONE.bits() }>(
+            &mut result_buffer[0],
+            1,
+        )
+    } as usize;
+    if slot < RESULT_SLOTS {
+        result_buffer[1 + slot] = value;
+    }
+}
+
+#[inline]
+fn crc32_update(crc: u32, byte: u32) -> u32 {
+    (crc >> 8) ^ CRC32_TABLE[((crc ^ byte) & 0xff) as usize]
+}
+
+/// Advance the three ZipCrypto keys by one plaintext byte
+#[inline]
+fn zip_update_keys(keys: &mut [u32; 3], byte: u32) {
+    keys[0] = crc32_update(keys[0], byte);
+    keys[1] = keys[1]
+        .wrapping_add(keys[0] & 0xff)
+        .wrapping_mul(134775813)
+        .wrapping_add(1);
+    keys[2] = crc32_update(keys[2], keys[1] >> 24);
+}
+
+/// ZipCrypto (traditional PKWARE) check
+///
+/// The password initialises the three keys, which then decrypt each file's
+/// 12-byte encryption header; the last header byte must equal the file's
+/// check byte. Target layout: word 0 holds the number of files (up to 8),
+/// then each file takes four words from word 4: the encrypted header as
+/// three little-endian words and the check byte. The check only rules out
+/// 255 in 256 wrong passwords per file, so every surviving candidate is
+/// recorded for the host to verify.
+#[spirv(compute(threads(64)))]
+pub fn zipcrypto_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 36],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let mut keys = [0x1234_5678u32, 0x2345_6789, 0x3456_7890];
+    let mut i = 0;
+    while i < len {
+        zip_update_keys(&mut keys, message_byte(messages, start + i));
+        i += 1;
+    }
+
+    let file_count = target[0] as usize;
+    let mut file = 0;
+    while file < file_count {
+        let header = 4 + file * 4;
+        let mut file_keys = keys;
+        let mut plain = 0;
+        let mut j = 0;
+        while j < 12 {
+            let cipher = (target[header + j / 4] >> ((j % 4) * 8)) & 0xff;
+            let temp = file_keys[2] | 2;
+            plain = cipher ^ ((temp.wrapping_mul(temp ^ 1) >> 8) & 0xff);
+            zip_update_keys(&mut file_keys, plain);
+            j += 1;
+        }
+        if plain != target[header + 3] {
+            return;
+        }
+        file += 1;
+    }
+
+    record_hit(result_buffer, idx as i32);
+}
+
+/// PRFs understood by `pbkdf2_crack` (target word 0)
+const PRF_HMAC_SHA1: u32 = 0;
+
+/// Initial state of the PRF's hash; SHA1 uses the first five words
+fn prf_h0(prf: u32) -> [u32; 8] {
+    if prf == PRF_HMAC_SHA1 {
+        [SHA1_H0[0], SHA1_H0[1], SHA1_H0[2], SHA1_H0[3], SHA1_H0[4], 0, 0, 0]
+    } else {
+        SHA256_H0
+    }
+}
+
+/// Digest size of the PRF's hash in words
+fn prf_digest_words(prf: u32) -> usize {
+    if prf == PRF_HMAC_SHA1 {
+        5
+    } else {
+        8
+    }
+}
+
+fn prf_compress(prf: u32, h: [u32; 8], block: &[u32; 16]) -> [u32; 8] {
+    if prf == PRF_HMAC_SHA1 {
+        let s = sha1_compress([h[0], h[1], h[2], h[3], h[4]], block);
+        [s[0], s[1], s[2], s[3], s[4], 0, 0, 0]
+    } else {
+        sha256_compress(h, block)
+    }
+}
+
+/// HMAC inner and outer states for a big-endian key block
+fn prf_hmac_init(prf: u32, key: &[u32; 16]) -> ([u32; 8], [u32; 8]) {
+    let mut ipad = [0u32; 16];
+    let mut opad = [0u32; 16];
+    let mut i = 0;
+    while i < 16 {
+        ipad[i] = key[i] ^ 0x3636_3636;
+        opad[i] = key[i] ^ 0x5c5c_5c5c;
+        i += 1;
+    }
+    (
+        prf_compress(prf, prf_h0(prf), &ipad),
+        prf_compress(prf, prf_h0(prf), &opad),
+    )
+}
+
+/// Finish an HMAC over `block_count` pre-padded message blocks stored
+/// big-endian from word `first_word` of `params`
+fn prf_hmac_blocks(
+    prf: u32,
+    inner: [u32; 8],
+    outer: [u32; 8],
+    params: &[u32; 512],
+    first_word: usize,
+    block_count: usize,
+) -> [u32; 8] {
+    let mut h = inner;
+    let mut block = 0;
+    while block < block_count {
+        let base = first_word + block * 16;
+        let mut m = [0u32; 16];
+        let mut i = 0;
+        while i < 16 {
+            m[i] = params[base + i];
+            i += 1;
+        }
+        h = prf_compress(prf, h, &m);
+        block += 1;
+    }
+    prf_hmac_digest(prf, h, outer)
+}
+
+/// A digest padded as the whole message of an HMAC hash (after the key block)
+fn prf_digest_block(prf: u32, digest: &[u32; 8]) -> [u32; 16] {
+    let words = prf_digest_words(prf);
+    let mut m = [0u32; 16];
+    let mut i = 0;
+    while i < words {
+        m[i] = digest[i];
+        i += 1;
+    }
+    m[words] = 0x8000_0000;
+    m[15] = ((64 + words * 4) * 8) as u32;
+    m
+}
+
+/// Finish an HMAC from the digest of its inner hash
+fn prf_hmac_digest(prf: u32, inner_digest: [u32; 8], outer: [u32; 8]) -> [u32; 8] {
+    prf_compress(prf, outer, &prf_digest_block(prf, &inner_digest))
+}
+
+/// HMAC of a previous digest (every PBKDF2 iteration after the first)
+fn prf_hmac_iterate(prf: u32, inner: [u32; 8], outer: [u32; 8], u: &[u32; 8]) -> [u32; 8] {
+    let h = prf_compress(prf, inner, &prf_digest_block(prf, u));
+    prf_hmac_digest(prf, h, outer)
+}
+
+/// Generic PBKDF2 (HMAC-SHA1 or HMAC-SHA256), run a window of iterations
+/// per dispatch so high iteration counts never make one long dispatch
+///
+/// Only the first output block is derived and compared. Target layout:
+/// * word 0: PRF (0 = HMAC-SHA1, 1 = HMAC-SHA256)
+/// * word 1: total iterations
+/// * word 2: words of the derived key to compare (at most the digest size)
+/// * word 3: salt blocks
+/// * words 8-15: start of the derived key, big-endian
+/// * from word 16: salt || INT(1), padded as an HMAC message
+///
+/// The uniform holds the message count and the window of iterations
+/// `[y, z)` this dispatch runs (counting from 1); U and T for each candidate
+/// are carried between windows in `state`, 16 words per candidate.
+/// Candidates longer than 64 bytes are skipped.
+#[spirv(compute(threads(64)))]
+pub fn pbkdf2_crack(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 512],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 5)] state: &mut [u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    if len > 64 {
+        return;
+    }
+
+    let prf = target[0];
+    let key = hmac_key_block(messages, start, len);
+    let (inner, outer) = prf_hmac_init(prf, &key);
+
+    let slot = idx * 16;
+    let mut u = [0u32; 8];
+    let mut t = [0u32; 8];
+    let mut iteration = dispatch.y;
+    if iteration == 1 {
+        u = prf_hmac_blocks(prf, inner, outer, target, 16, target[3] as usize);
+        t = u;
+        iteration = 2;
+    } else {
+        let mut i = 0;
+        while i < 8 {
+            u[i] = state[slot + i];
+            t[i] = state[slot + 8 + i];
+            i += 1;
+        }
+    }
+
+    while iteration < dispatch.z {
+        u = prf_hmac_iterate(prf, inner, outer, &u);
+        let mut i = 0;
+        while i < 8 {
+            t[i] ^= u[i];
+            i += 1;
+        }
+        iteration += 1;
+    }
+
+    if dispatch.z <= target[1] {
+        let mut i = 0;
+        while i < 8 {
+            state[slot + i] = u[i];
+            state[slot + 8 + i] = t[i];
+            i += 1;
+        }
+        return;
+    }
+
+    let compare = target[2] as usize;
+    let mut i = 0;
+    while i < compare {
+        if t[i] != target[8 + i] {
+            return;
+        }
+        i += 1;
+    }
+    result_buffer[0] = idx as i32;
+}
+
+/// Vanity search: generate candidates on the GPU and test their MD5
+///
+/// Each candidate is a fixed prefix followed by one character per mask
+/// position, enumerated like an odometer (the last position changes
+/// fastest) from a starting point the host advances every dispatch; the
+/// invocation index is added to it. Candidates are at most 55 bytes, so
+/// they fit one MD5 block. Target layout:
+/// * words 0-7: digest and mask, as for `md5_crack`
+/// * word 8: prefix length in bytes; word 9: number of mask positions
+/// * words 16-31: the prefix, packed little-endian
+/// * words 32-63: the starting character index of each position
+/// * words 64-95: the charset length of each position
+/// * words 96-127: the byte offset of each position's charset in the table
+/// * from word 128: the charset table, packed little-endian
+///
+/// `messages` and `offsets` are unused. Every match is recorded by
+/// its invocation index.
+#[spirv(compute(threads(64)))]
+pub fn md5_vanity(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] target: &[u32; 512],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] result_buffer: &mut [i32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+) {
+    let idx = dispatch.w + global_id.x;
+
+    if idx >= dispatch.x {
+        return;
+    }
+
+    let prefix_len = target[8] as usize;
+    let positions = target[9] as usize;
+
+    // Add the invocation index to the starting point, last position first
+    let mut digits = [0u32; 32];
+    let mut i = 0;
+    while i < positions {
+        digits[i] = target[32 + i];
+        i += 1;
+    }
+    let mut carry = idx;
+    let mut pos = positions;
+    while pos > 0 && carry > 0 {
+        pos -= 1;
+        let value = digits[pos] + carry;
+        digits[pos] = value % target[64 + pos];
+        carry = value / target[64 + pos];
+    }
+    if carry > 0 {
+        // Past the end of the keyspace
+        return;
+    }
+
+    let mut m = [0u32; 16];
+    i = 0;
+    while i < prefix_len {
+        m[i / 4] |= byte_at(target, 64 + i) << ((i % 4) * 8);
+        i += 1;
+    }
+    i = 0;
+    while i < positions {
+        let c = byte_at(target, 512 + target[96 + i] as usize + digits[i] as usize);
+        let at = prefix_len + i;
+        m[at / 4] |= c << ((at % 4) * 8);
+        i += 1;
+    }
+    let len = prefix_len + positions;
+    m[len / 4] |= 0x80 << ((len % 4) * 8);
+    m[14] = (len * 8) as u32;
+
+    let h = md5_compress([A0, B0, C0, D0], &m);
+    if (h[0] ^ target[0]) & target[4] == 0
+        && (h[1] ^ target[1]) & target[5] == 0
+        && (h[2] ^ target[2]) & target[6] == 0
+        && (h[3] ^ target[3]) & target[7] == 0
+    {
+        record_hit(result_buffer, idx as i32);
+    }
+}
+
+/// Bulk hashing: write the MD5 of every message to `digests`, four
+/// little-endian words per message, instead of searching for a target
+#[spirv(compute(threads(64)))]
+pub fn md5_digest(
+    #[spirv(global_invocation_id)] global_id: UVec3,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 0)] messages: &[u32],
+    #[spirv(uniform, descriptor_set = 0, binding = 3)] dispatch: &UVec4,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 4)] offsets: &[u32],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 5)] digests: &mut [u32],
+) {
+    let idx = (dispatch.w + global_id.x) as usize;
+
+    if idx >= dispatch.x as usize {
+        return;
+    }
+
+    let start = offsets[idx] as usize;
+    let len = offsets[idx + 1] as usize - start;
+
+    let h = md5_blocks(messages, start, len);
+    digests[idx * 4] = h[0];
+    digests[idx * 4 + 1] = h[1];
+    digests[idx * 4 + 2] = h[2];
+    digests[idx * 4 + 3] = h[3];
+}
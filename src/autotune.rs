@@ -0,0 +1,346 @@
+//! Per-device batch and workgroup size tuning
+//!
+//! The best batch size depends on the GPU and on how heavy each hash mode
+//! is: too small and the GPU idles between dispatches, too large and the
+//! buffers and the latency to the first hit grow for nothing. Autotuning
+//! times every wordlist kernel over a range of batch sizes, and the MD5
+//! kernels over the workgroup sizes the shader has entry points for, then
+//! keeps the fastest setting of each.
+
+use crate::{
+    CrackerError, GpuCracker, Kernel, PackedBatch, PartialMd5Target, Tuning, TARGET_BUFFER_SIZE,
+};
+use std::path::Path;
+use std::time::Instant;
+use std::{fs, io};
+
+/// Kernels that are autotuned: those running one short dispatch per batch
+/// of wordlist candidates. The iterated WPA and PBKDF2 kernels and the
+/// vanity search keep the default batch size.
+const TUNED_KERNELS: [Kernel; 11] = [
+    Kernel::Md5,
+    Kernel::Md5Short,
+    Kernel::OfficeRc4,
+    Kernel::Mysql41,
+    Kernel::Mysql323,
+    Kernel::Ntlm,
+    Kernel::Lm,
+    Kernel::NetNtlmV1,
+    Kernel::NetNtlmV2,
+    Kernel::ZipCrypto,
+    Kernel::Md5Digest,
+];
+
+/// Smallest batch size tried; each further one is four times larger, up
+/// to the device's limit
+const MIN_TUNE_BATCH: usize = 4096;
+/// Timed runs of each setting, of which the fastest counts
+const TUNE_RUNS: usize = 3;
+/// How much slower a smaller batch may be and still be picked, since it
+/// costs less memory and reports hits sooner
+const TUNE_TOLERANCE: f64 = 0.05;
+/// First line of a tuning cache file
+const CACHE_HEADER: &str = "# rustcracker autotune cache";
+
+/// Batch and workgroup size autotuning picked for one kernel
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuneResult {
+    /// Shader entry point of the kernel
+    pub kernel: &'static str,
+    pub batch_size: usize,
+    pub workgroup_size: u32,
+    /// Throughput measured with those sizes
+    pub hashes_per_sec: f64,
+}
+
+impl GpuCracker {
+    /// Time every wordlist kernel over a range of batch and workgroup
+    /// sizes and use the fastest for each from now on
+    ///
+    /// Every measurement is a timed [`process_batch_with_timing`]-style
+    /// dispatch, using GPU timestamps when the device supports them and
+    /// wall-clock time otherwise. This takes a few seconds, and the buffer
+    /// sets are reallocated to fit the largest size picked.
+    ///
+    /// [`process_batch_with_timing`]: GpuCracker::process_batch_with_timing
+    pub fn autotune(&mut self) -> Result<Vec<TuneResult>, CrackerError> {
+        let mut batch_sizes = Vec::new();
+        let mut batch_size = MIN_TUNE_BATCH.min(self.max_batch_size);
+        while batch_size <= self.max_batch_size {
+            batch_sizes.push(batch_size);
+            batch_size *= 4;
+        }
+
+        let largest = *batch_sizes.last().expect("at least one batch size");
+        let candidates: Vec<String> = (0..largest).map(|i| format!("tune{i:08}")).collect();
+        let candidates: Vec<&str> = candidates.iter().map(String::as_str).collect();
+
+        self.ensure_device()?;
+        self.tuning.clear();
+        self.resize_buffer_sets(largest);
+        let results = TUNED_KERNELS
+            .iter()
+            .map(|&kernel| self.tune_kernel(kernel, &batch_sizes, &candidates))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.apply_tuning(&results);
+        Ok(results)
+    }
+
+    /// Like [`autotune`](Self::autotune), but reuse the sizes stored in
+    /// `cache` for this adapter if there are any, and store them there
+    /// otherwise, next to those of any other adapters
+    pub fn autotune_cached(&mut self, cache: &Path) -> Result<Vec<TuneResult>, CrackerError> {
+        let adapter = self.adapter_key();
+        let text = match fs::read_to_string(cache) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(CrackerError::TuneCache(e)),
+        };
+        let cached = parse_cache(&text, &adapter);
+
+        if let Some(results) = cached.filter(|results| self.fits(results)) {
+            self.tuning.clear();
+            self.apply_tuning(&results);
+            return Ok(results);
+        }
+
+        let results = self.autotune()?;
+        fs::write(cache, format_cache(&text, &adapter, &results))
+            .map_err(CrackerError::TuneCache)?;
+        Ok(results)
+    }
+
+    /// Time `kernel` at every batch size and each of its workgroup sizes
+    fn tune_kernel(
+        &mut self,
+        kernel: Kernel,
+        batch_sizes: &[usize],
+        candidates: &[&str],
+    ) -> Result<TuneResult, CrackerError> {
+        let target = tune_target(kernel);
+        let mut batch = PackedBatch::default();
+
+        // The fastest workgroup size at each batch size
+        let mut best: Vec<(usize, u32, f64)> = Vec::new();
+        for &batch_size in batch_sizes {
+            batch.pack(&candidates[..batch_size], kernel.encoding(), &[]);
+
+            let mut fastest: Option<(usize, u32, f64)> = None;
+            for workgroup_size in self.kernel_workgroup_sizes(kernel) {
+                if workgroup_size > self.max_workgroup_size {
+                    continue;
+                }
+                self.tuning.insert(
+                    kernel,
+                    Tuning {
+                        batch_size,
+                        workgroup_size,
+                    },
+                );
+                self.ensure_pipeline(kernel);
+
+                // A setting that loses the device (one dispatch running
+                // into the driver watchdog) counts as the slowest
+                let rate = match self.time_setting(kernel, &batch, &target) {
+                    Ok(seconds) => batch_size as f64 / seconds.max(1e-9),
+                    Err(CrackerError::DeviceLost) => {
+                        self.recover_device()?;
+                        0.0
+                    }
+                    Err(e) => {
+                        self.tuning.remove(&kernel);
+                        return Err(e);
+                    }
+                };
+
+                if fastest.is_none_or(|(_, _, best_rate)| rate > best_rate) {
+                    fastest = Some((batch_size, workgroup_size, rate));
+                }
+            }
+            best.extend(fastest);
+        }
+        self.tuning.remove(&kernel);
+
+        let top_rate = best.iter().map(|&(_, _, rate)| rate).fold(0.0, f64::max);
+        let (batch_size, workgroup_size, hashes_per_sec) = best
+            .into_iter()
+            .find(|&(_, _, rate)| rate >= top_rate * (1.0 - TUNE_TOLERANCE))
+            .expect("at least one setting was timed");
+        Ok(TuneResult {
+            kernel: kernel.entry_point(),
+            batch_size,
+            workgroup_size,
+            hashes_per_sec,
+        })
+    }
+
+    /// Fastest of a few timed dispatches of `kernel` over `batch`, in
+    /// seconds
+    fn time_setting(
+        &mut self,
+        kernel: Kernel,
+        batch: &PackedBatch,
+        target: &[u8],
+    ) -> Result<f64, CrackerError> {
+        self.upload_batch_to_gpu(0, kernel, batch, target);
+
+        // The first run warms up the pipeline
+        self.time_dispatch(kernel, batch.len())?;
+        let mut fastest = f64::INFINITY;
+        for _ in 0..TUNE_RUNS {
+            let start = Instant::now();
+            let (_, gpu_time_ns) = self.time_dispatch(kernel, batch.len())?;
+            let seconds = gpu_time_ns.map_or(start.elapsed().as_secs_f64(), |ns| ns as f64 / 1e9);
+            fastest = fastest.min(seconds);
+        }
+        Ok(fastest)
+    }
+
+    /// Use tuned sizes, shrinking or growing the buffer sets to fit the
+    /// largest batch
+    fn apply_tuning(&mut self, results: &[TuneResult]) {
+        for result in results {
+            let Some(kernel) = tuned_kernel(result.kernel) else {
+                continue;
+            };
+            self.tuning.insert(
+                kernel,
+                Tuning {
+                    batch_size: result.batch_size,
+                    workgroup_size: result.workgroup_size,
+                },
+            );
+        }
+
+        let capacity = self
+            .tuning
+            .values()
+            .map(|tuning| tuning.batch_size)
+            .fold(self.batch_size, usize::max);
+        self.resize_buffer_sets(capacity);
+    }
+
+    /// Whether cached sizes can be used on this device as it is set up now
+    fn fits(&self, results: &[TuneResult]) -> bool {
+        TUNED_KERNELS.iter().all(|&kernel| {
+            results.iter().any(|result| {
+                result.kernel == kernel.entry_point()
+                    && result.batch_size <= self.max_batch_size
+                    && result.workgroup_size <= self.max_workgroup_size
+                    && self
+                        .kernel_workgroup_sizes(kernel)
+                        .contains(&result.workgroup_size)
+            })
+        })
+    }
+
+    /// What identifies the adapter in the cache: a driver update can
+    /// change the best sizes as much as a different GPU, and so can a
+    /// custom kernel
+    fn adapter_key(&self) -> String {
+        let info = &self.adapter_info;
+        let mut key = format!(
+            "{} [{:04x}:{:04x}] {} {}",
+            info.name, info.vendor, info.device, info.driver, info.driver_info
+        );
+        if let Some(shader) = &self.custom_shader {
+            key.push_str(&format!(" shader {:016x}", shader.fingerprint()));
+        }
+        key
+    }
+}
+
+/// Target parameters that `kernel` finds (almost) no hits for, so hits
+/// being recorded do not skew the timings
+fn tune_target(kernel: Kernel) -> Vec<u8> {
+    // An all-zero target matches nothing in most modes
+    let mut target = vec![0u8; TARGET_BUFFER_SIZE as usize];
+    match kernel {
+        // ... but an MD5 target also needs a mask to compare anything
+        Kernel::Md5 | Kernel::Md5Short => {
+            let params = PartialMd5Target::full([0; 16]).params();
+            let params: &[u8] = bytemuck::cast_slice(&params);
+            target[..params.len()].copy_from_slice(params);
+        }
+        // ... and a ZIP target with no files would accept every password
+        Kernel::ZipCrypto => target[0] = 1,
+        _ => {}
+    }
+    target
+}
+
+/// Tuned kernel with the given entry point name
+fn tuned_kernel(entry_point: &str) -> Option<Kernel> {
+    TUNED_KERNELS
+        .into_iter()
+        .find(|kernel| kernel.entry_point() == entry_point)
+}
+
+/// Each adapter's key in a cache file, with the lines of results after it
+///
+/// The cache holds a section per adapter: a line naming the adapter, then
+/// one line per kernel with its entry point, batch size, workgroup size
+/// and throughput. A file without the header has no sections.
+fn cache_sections(text: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut lines = text.lines();
+    if lines.next() != Some(CACHE_HEADER) {
+        return Vec::new();
+    }
+
+    let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        match (line.strip_prefix("adapter "), sections.last_mut()) {
+            (Some(adapter), _) => sections.push((adapter, Vec::new())),
+            (None, Some((_, results))) => results.push(line),
+            // Results before any adapter belong to none
+            (None, None) => {}
+        }
+    }
+    sections
+}
+
+/// Read the results stored for `adapter`, if any
+fn parse_cache(text: &str, adapter: &str) -> Option<Vec<TuneResult>> {
+    let (_, lines) = cache_sections(text)
+        .into_iter()
+        .find(|(key, _)| *key == adapter)?;
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let kernel = tuned_kernel(fields.next()?)?;
+            let result = TuneResult {
+                kernel: kernel.entry_point(),
+                batch_size: fields.next()?.parse().ok()?,
+                workgroup_size: fields.next()?.parse().ok()?,
+                hashes_per_sec: fields.next()?.parse().ok()?,
+            };
+            fields.next().is_none().then_some(result)
+        })
+        .collect()
+}
+
+/// The cache file `text` with `adapter`'s section replaced by `results`,
+/// in the format [`parse_cache`] reads
+fn format_cache(text: &str, adapter: &str, results: &[TuneResult]) -> String {
+    let mut out = format!("{CACHE_HEADER}\n");
+    for (key, lines) in cache_sections(text) {
+        if key != adapter {
+            out.push_str(&format!("adapter {key}\n"));
+            for line in lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+
+    out.push_str(&format!("adapter {adapter}\n"));
+    for result in results {
+        out.push_str(&format!(
+            "{} {} {} {:.0}\n",
+            result.kernel, result.batch_size, result.workgroup_size, result.hashes_per_sec
+        ));
+    }
+    out
+}
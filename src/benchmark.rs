@@ -0,0 +1,368 @@
+//! Built-in hash rate measurements
+//!
+//! [`GpuCracker::benchmark`] runs a hash mode's normal cracking call over
+//! synthetic candidates, against a synthetic target none of them matches so
+//! that every candidate is checked, and repeats it until the time is up.
+//! The host rate counts the hashes read back over the wall-clock time; the
+//! GPU rate comes from the profiler's timestamps of every dispatch.
+//! [`CpuCracker::benchmark`] does the same for the modes hashed on the CPU.
+
+use crate::profiler::Profiler;
+use crate::{
+    BcryptTarget, CpuCracker, CrackerError, GpuCracker, Kernel, MysqlHash, MysqlTarget,
+    NetNtlmResponse, NetNtlmTarget, OfficeRc4Target, Pbkdf2Format, Pbkdf2Prf, Pbkdf2Target,
+    PostgresMd5Target, ScanOutcome, WpaCapture, WpaTarget, ZipCryptoTarget, ZipEntry,
+};
+use std::time::{Duration, Instant};
+
+/// Hash modes [`GpuCracker::benchmark`] measures, by the names `--mode`
+/// takes, with the two NetNTLM versions apart
+pub const BENCHMARK_MODES: [&str; 12] = [
+    "md5",
+    "oldoffice",
+    "postgres",
+    "mysql",
+    "mysql323",
+    "lm",
+    "ntlm",
+    "netntlmv1",
+    "netntlmv2",
+    "wpa",
+    "zip",
+    "pbkdf2",
+];
+
+/// Hash modes [`CpuCracker::benchmark`] measures
+pub const CPU_BENCHMARK_MODES: [&str; 1] = ["bcrypt"];
+
+/// Batches of synthetic candidates each scan runs, per batch in flight
+const BENCHMARK_BATCHES: usize = 4;
+/// Rounds of the synthetic PBKDF2-HMAC-SHA256 target
+const BENCHMARK_PBKDF2_ITERATIONS: u32 = 10_000;
+/// Cost of the synthetic bcrypt target, as hashcat benchmarks it
+const BENCHMARK_BCRYPT_COST: u32 = 5;
+/// Candidates each CPU worker checks per round of the bcrypt benchmark
+const BENCHMARK_CPU_CANDIDATES: usize = 16;
+
+/// Hash rate of one mode, from [`GpuCracker::benchmark`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchmarkResult {
+    /// One of [`BENCHMARK_MODES`] or [`CPU_BENCHMARK_MODES`]
+    pub mode: &'static str,
+    /// Candidates per batch the mode ran with (per round of all workers,
+    /// on the CPU)
+    pub batch_size: usize,
+    /// Candidates hashed, and the wall-clock time it took
+    pub hashes: usize,
+    pub elapsed: Duration,
+    pub hashes_per_sec: f64,
+    /// GPU time of every dispatch, on devices with timestamp queries;
+    /// `None` for the CPU modes
+    pub gpu_time: Option<Duration>,
+}
+
+impl BenchmarkResult {
+    /// Candidates per second of GPU time
+    pub fn gpu_hashes_per_sec(&self) -> Option<f64> {
+        self.gpu_time
+            .filter(|time| !time.is_zero())
+            .map(|time| self.hashes as f64 / time.as_secs_f64())
+    }
+}
+
+/// Benchmark results as the JSON object `rustcracker benchmark --json`
+/// prints, with the adapter (if the GPU was used) and build they were
+/// measured on
+///
+/// The regression benchmark writes the same object, so its baselines and
+/// the command's output can be compared with each other.
+pub fn benchmark_json(adapter: Option<&wgpu::AdapterInfo>, results: &[BenchmarkResult]) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+    let results: Vec<String> = results
+        .iter()
+        .map(|result| {
+            format!(
+                "    {{\"mode\": {}, \"batch_size\": {}, \"hashes\": {}, \"seconds\": {:.6}, \
+                 \"hashes_per_sec\": {:.1}, \"gpu_ns\": {}, \"gpu_hashes_per_sec\": {}}}",
+                json_string(result.mode),
+                result.batch_size,
+                result.hashes,
+                result.elapsed.as_secs_f64(),
+                result.hashes_per_sec,
+                optional(result.gpu_time.map(|time| time.as_nanos().to_string())),
+                optional(result.gpu_hashes_per_sec().map(|rate| format!("{rate:.1}"))),
+            )
+        })
+        .collect();
+    let device = adapter.map_or_else(
+        || "null".to_string(),
+        |adapter| {
+            format!(
+                "{{\"name\": {}, \"backend\": {}, \"driver\": {}, \"driver_info\": {}}}",
+                json_string(&adapter.name),
+                json_string(&format!("{:?}", adapter.backend)),
+                json_string(&adapter.driver),
+                json_string(&adapter.driver_info),
+            )
+        },
+    );
+    format!(
+        "{{\n  \"version\": {},\n  \"device\": {},\n  \"results\": [\n{}\n  ]\n}}\n",
+        json_string(env!("CARGO_PKG_VERSION")),
+        device,
+        results.join(",\n")
+    )
+}
+
+/// `text` as a quoted JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl GpuCracker {
+    /// Measure the hash rate of `mode`, one of [`BENCHMARK_MODES`], over
+    /// about `duration`
+    ///
+    /// The last scan stops when the time is up, like one past its
+    /// deadline; the cracker's own deadline is set aside meanwhile, while a
+    /// cancelled [`CancelToken`](crate::CancelToken) still stops the
+    /// benchmark. Profiling totals gathered before are left as they were.
+    pub fn benchmark(
+        &mut self,
+        mode: &str,
+        duration: Duration,
+    ) -> Result<BenchmarkResult, CrackerError> {
+        let Some(&mode) = BENCHMARK_MODES.iter().find(|&&known| known == mode) else {
+            return Err(CrackerError::InvalidOption(format!(
+                "no benchmark for mode '{mode}'"
+            )));
+        };
+        if duration.is_zero() {
+            return Err(CrackerError::InvalidOption(
+                "benchmark duration must be more than zero".into(),
+            ));
+        }
+
+        let batch_size = self.batch_size_for(benchmark_kernel(mode));
+        let words: Vec<String> = (0..batch_size * self.pipeline_depth() * BENCHMARK_BATCHES)
+            .map(|i| format!("bench{i:08}"))
+            .collect();
+        let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        let profiler = self.profiler.replace(Profiler::default());
+        let started = Instant::now();
+        let deadline = self.deadline.replace(started + duration);
+        let scanned = self.benchmark_scans(mode, &wordlist);
+        let elapsed = started.elapsed();
+        self.deadline = deadline;
+        let report = std::mem::replace(&mut self.profiler, profiler)
+            .expect("profiling during the benchmark")
+            .report();
+        scanned?;
+
+        let hashes = report.kernels.iter().map(|kernel| kernel.candidates).sum();
+        let gpu_time = report
+            .kernels
+            .iter()
+            .map(|kernel| {
+                // Dispatches past the timed ones would be missing
+                kernel
+                    .kernel_time
+                    .filter(|_| kernel.timed_dispatches == kernel.dispatches)
+            })
+            .sum::<Option<Duration>>();
+        Ok(BenchmarkResult {
+            mode,
+            batch_size,
+            hashes,
+            elapsed,
+            hashes_per_sec: hashes as f64 / elapsed.as_secs_f64(),
+            gpu_time,
+        })
+    }
+
+    /// Crack the synthetic target of `mode` over and over until a scan
+    /// ends any other way than running out of candidates
+    fn benchmark_scans(&mut self, mode: &str, wordlist: &[&str]) -> Result<(), CrackerError> {
+        loop {
+            let result = match mode {
+                "md5" => self.crack(&[0; 16], wordlist)?,
+                "oldoffice" => self.crack_office_rc4(
+                    &OfficeRc4Target {
+                        salt: [1; 16],
+                        encrypted_verifier: [2; 16],
+                        encrypted_verifier_hash: [3; 16],
+                    },
+                    wordlist,
+                )?,
+                "postgres" => self.crack_postgres_md5(
+                    &PostgresMd5Target {
+                        username: "postgres".into(),
+                        hash: [0; 16],
+                    },
+                    wordlist,
+                )?,
+                "mysql" | "mysql323" => self.crack_mysql(
+                    &MysqlTarget {
+                        username: None,
+                        hash: if mode == "mysql" {
+                            MysqlHash::Native([0; 20])
+                        } else {
+                            MysqlHash::Old323([0; 2])
+                        },
+                    },
+                    wordlist,
+                )?,
+                // Neither half may be the empty one, which is never searched
+                "lm" => self.crack_lm(&[1; 16], wordlist)?,
+                "ntlm" => self.crack_ntlm(&[0; 16], wordlist)?,
+                "netntlmv1" | "netntlmv2" => self.crack_netntlm(
+                    &NetNtlmTarget {
+                        username: "user".into(),
+                        domain: "DOMAIN".into(),
+                        response: if mode == "netntlmv1" {
+                            NetNtlmResponse::V1 {
+                                lm_response: vec![1; 24],
+                                nt_response: [0; 24],
+                                server_challenge: [2; 8],
+                            }
+                        } else {
+                            NetNtlmResponse::V2 {
+                                server_challenge: [2; 8],
+                                nt_proof: [0; 16],
+                                blob: vec![3; 128],
+                            }
+                        },
+                    },
+                    wordlist,
+                )?,
+                "wpa" => self.crack_wpa(
+                    &WpaTarget {
+                        hash: [0; 16],
+                        mac_ap: [0x02, 0, 0, 0, 0, 1],
+                        mac_client: [0x02, 0, 0, 0, 0, 2],
+                        essid: b"benchmark".to_vec(),
+                        capture: WpaCapture::Pmkid,
+                        message_pair: None,
+                    },
+                    wordlist,
+                )?,
+                // Three headers, so the few candidates passing the GPU's
+                // check hardly cost any verifying on the host
+                "zip" => self.crack_zip(
+                    &ZipCryptoTarget {
+                        entries: (1..=3)
+                            .map(|i| ZipEntry {
+                                name: format!("file{i}.txt"),
+                                method: 0,
+                                crc32: 0,
+                                check_byte: i,
+                                data: vec![i; 32],
+                            })
+                            .collect(),
+                    },
+                    wordlist,
+                )?,
+                "pbkdf2" => self.crack_pbkdf2(
+                    &Pbkdf2Target {
+                        format: Pbkdf2Format::Django,
+                        prf: Pbkdf2Prf::HmacSha256,
+                        iterations: BENCHMARK_PBKDF2_ITERATIONS,
+                        salt: b"benchmarksalt".to_vec(),
+                        hash: vec![0; 32],
+                    },
+                    wordlist,
+                )?,
+                _ => unreachable!("checked against BENCHMARK_MODES"),
+            };
+            if result.outcome != ScanOutcome::Exhausted {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Kernel whose batch size `mode` runs with
+fn benchmark_kernel(mode: &str) -> Kernel {
+    match mode {
+        "oldoffice" => Kernel::OfficeRc4,
+        "mysql" => Kernel::Mysql41,
+        "mysql323" => Kernel::Mysql323,
+        "lm" => Kernel::Lm,
+        "ntlm" => Kernel::Ntlm,
+        "netntlmv1" => Kernel::NetNtlmV1,
+        "netntlmv2" => Kernel::NetNtlmV2,
+        "wpa" => Kernel::Wpa,
+        "zip" => Kernel::ZipCrypto,
+        "pbkdf2" => Kernel::Pbkdf2,
+        _ => Kernel::Md5,
+    }
+}
+
+impl CpuCracker {
+    /// Measure the hash rate of `mode`, one of [`CPU_BENCHMARK_MODES`],
+    /// over about `duration` on every worker
+    ///
+    /// As on the GPU, the cracker's deadline is set aside meanwhile, while
+    /// a cancelled [`CancelToken`](crate::CancelToken) still stops the
+    /// benchmark.
+    pub fn benchmark(
+        &mut self,
+        mode: &str,
+        duration: Duration,
+    ) -> Result<BenchmarkResult, CrackerError> {
+        let Some(&mode) = CPU_BENCHMARK_MODES.iter().find(|&&known| known == mode) else {
+            return Err(CrackerError::InvalidOption(format!(
+                "no CPU benchmark for mode '{mode}'"
+            )));
+        };
+        if duration.is_zero() {
+            return Err(CrackerError::InvalidOption(
+                "benchmark duration must be more than zero".into(),
+            ));
+        }
+
+        let target = BcryptTarget {
+            variant: 'b',
+            cost: BENCHMARK_BCRYPT_COST,
+            salt: [0; 16],
+            hash: [0; 23],
+        };
+        let batch_size = self.threads() * BENCHMARK_CPU_CANDIDATES;
+        let words: Vec<String> = (0..batch_size).map(|i| format!("bench{i:08}")).collect();
+        let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        let started = Instant::now();
+        let deadline = self.deadline.take();
+        let mut hashes = 0;
+        while hashes == 0 || started.elapsed() < duration {
+            // A cancelled round checked an unknown part of the batch
+            if self.crack_bcrypt(&target, &wordlist).outcome == ScanOutcome::Cancelled {
+                break;
+            }
+            hashes += batch_size;
+        }
+        let elapsed = started.elapsed();
+        self.deadline = deadline;
+        Ok(BenchmarkResult {
+            mode,
+            batch_size,
+            hashes,
+            elapsed,
+            hashes_per_sec: hashes as f64 / elapsed.as_secs_f64(),
+            gpu_time: None,
+        })
+    }
+}
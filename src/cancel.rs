@@ -0,0 +1,71 @@
+//! Stopping scans early
+//!
+//! A scan checks for a cancelled [`CancelToken`] or a passed deadline
+//! before submitting each batch. Once either is seen it submits nothing
+//! more, but still reads back every batch already in flight, so a hit found
+//! there is reported as usual. The [`ScanResult`] the scan returns then
+//! tells why it ended.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Flag another thread sets to stop the cracker's scans
+///
+/// Cloning shares the same flag; two tokens compare equal when they share
+/// one. Once cancelled, a token stays cancelled, so every later scan stops
+/// before its first batch.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every scan watching this token to stop after the batches in
+    /// flight
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelToken {}
+
+/// What a scan found, and why it ended
+///
+/// `found` is what the method looks for: the password for most of them,
+/// every match for [`crack_md5_partial`], or the number of solutions for
+/// [`vanity_search`].
+///
+/// [`crack_md5_partial`]: crate::GpuCracker::crack_md5_partial
+/// [`vanity_search`]: crate::GpuCracker::vanity_search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanResult<T = Option<String>> {
+    pub found: T,
+    pub outcome: ScanOutcome,
+}
+
+/// Why a scan ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanOutcome {
+    /// Every candidate was checked
+    Exhausted,
+    /// The scan stopped early because it had what it was after: the hit,
+    /// or as many solutions as were asked for
+    Finished,
+    /// The [`CancelToken`] was cancelled before every candidate was
+    /// checked
+    Cancelled,
+    /// The deadline passed before every candidate was checked
+    TimedOut,
+}
@@ -0,0 +1,264 @@
+//! CPU worker pool for slow, GPU-hostile hashes such as bcrypt
+//!
+//! [`CpuCracker`] mirrors [`GpuCracker`](crate::GpuCracker), behind the
+//! same [`Cracker`](crate::Cracker) trait: it takes a parsed target and a
+//! wordlist and returns the password if it is in the list. Candidates are
+//! handed out to one scoped thread per core in chunks sized from the
+//! target's cost, so expensive hashes still spread evenly and every worker
+//! stops soon after one of them finds the password.
+
+use crate::{CancelToken, ScanOutcome, ScanResult};
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+/// A hash that is checked one candidate at a time on the CPU
+pub trait SlowHash: Sync {
+    /// Check a password
+    fn verify(&self, password: &str) -> bool;
+
+    /// log2 of the work per candidate (bcrypt's cost factor)
+    fn cost(&self) -> u32;
+}
+
+/// Multi-threaded CPU cracker for [`SlowHash`] targets
+pub struct CpuCracker {
+    threads: usize,
+    cancel: Option<CancelToken>,
+    // Set aside while benchmarking
+    pub(crate) deadline: Option<Instant>,
+}
+
+impl CpuCracker {
+    /// Use one worker per available core
+    pub fn new() -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_threads(threads)
+    }
+
+    /// Use a fixed number of workers
+    pub fn with_threads(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            cancel: None,
+            deadline: None,
+        }
+    }
+
+    /// Number of workers each crack runs on
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Stop cracking once `token` is cancelled, or never with `None`
+    ///
+    /// Every worker checks the token before each candidate, so a crack
+    /// ends within one hash of it being cancelled, with a
+    /// [`ScanOutcome::Cancelled`] outcome.
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.cancel = token;
+    }
+
+    /// Stop cracking the same way once `deadline` passes, or never with
+    /// `None`
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Why the workers should stop before their next candidate, if they
+    /// should
+    fn stop_requested(&self) -> Option<ScanOutcome> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Some(ScanOutcome::Cancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(ScanOutcome::TimedOut)
+        } else {
+            None
+        }
+    }
+
+    /// Crack any CPU-side hash
+    pub fn crack_slow<H: SlowHash>(&mut self, target: &H, wordlist: &[&str]) -> ScanResult {
+        // Cheap hashes take candidates in bigger chunks to keep the shared
+        // counter out of the way; bcrypt at cost 12+ goes one at a time
+        let chunk_size = (1usize << 12 >> target.cost().min(12)).max(1);
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let found = Mutex::new(None);
+        let stopped = Mutex::new(None);
+        let this = &*self;
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(wordlist.len()) {
+                scope.spawn(|| {
+                    // Set the flag for every worker on the first reason to
+                    // stop any of them sees
+                    let stop_early = || match this.stop_requested() {
+                        Some(outcome) => {
+                            stopped.lock().unwrap().get_or_insert(outcome);
+                            stop.store(true, Ordering::Relaxed);
+                            true
+                        }
+                        None => false,
+                    };
+
+                    while !stop.load(Ordering::Relaxed) && !stop_early() {
+                        let start = next.fetch_add(chunk_size, Ordering::Relaxed);
+                        if start >= wordlist.len() {
+                            break;
+                        }
+                        let end = (start + chunk_size).min(wordlist.len());
+                        for &password in &wordlist[start..end] {
+                            if stop.load(Ordering::Relaxed) || stop_early() {
+                                return;
+                            }
+                            if target.verify(password) {
+                                *found.lock().unwrap() = Some(password.to_string());
+                                stop.store(true, Ordering::Relaxed);
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let found = found.into_inner().unwrap();
+        let outcome = match (&found, stopped.into_inner().unwrap()) {
+            (Some(_), _) => ScanOutcome::Finished,
+            (None, Some(outcome)) => outcome,
+            (None, None) => ScanOutcome::Exhausted,
+        };
+        ScanResult { found, outcome }
+    }
+
+    /// Crack a bcrypt hash
+    pub fn crack_bcrypt(&mut self, target: &BcryptTarget, wordlist: &[&str]) -> ScanResult {
+        self.crack_slow(target, wordlist)
+    }
+}
+
+impl Default for CpuCracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A `$2a$`, `$2b$` or `$2y$` bcrypt hash
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BcryptTarget {
+    /// Version letter after the `2` (`a`, `b` or `y`); all three hash the
+    /// same way
+    pub variant: char,
+    pub cost: u32,
+    pub salt: [u8; 16],
+    /// The 23 bytes of output bcrypt stores
+    pub hash: [u8; 23],
+}
+
+impl BcryptTarget {
+    /// Parse a 60-character bcrypt hash, optionally preceded by a username
+    /// and other fields as in htpasswd or shadow files
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let start = line.find("$2").ok_or("no bcrypt hash found")?;
+        let hash = line[start..]
+            .split(|c: char| c == ':' || c.is_whitespace())
+            .next()
+            .unwrap();
+        if hash.len() != 60 || !hash.is_ascii() {
+            return Err("bcrypt hashes are 60 characters".into());
+        }
+
+        let variant = hash[2..3].chars().next().unwrap();
+        if !matches!(variant, 'a' | 'b' | 'y') || &hash[3..4] != "$" || &hash[6..7] != "$" {
+            return Err("expected $2a$, $2b$ or $2y$".into());
+        }
+        let cost: u32 = hash[4..6].parse()?;
+        if !(4..=31).contains(&cost) {
+            return Err(format!("bcrypt cost {cost} is out of range").into());
+        }
+
+        Ok(Self {
+            variant,
+            cost,
+            salt: decode_base64(&hash[7..29])?,
+            hash: decode_base64(&hash[29..])?,
+        })
+    }
+
+    /// Parse every hash of a file, skipping lines that don't match
+    pub fn parse_dump(text: &str) -> Vec<Self> {
+        text.lines().filter_map(|l| Self::parse(l).ok()).collect()
+    }
+}
+
+impl SlowHash for BcryptTarget {
+    fn verify(&self, password: &str) -> bool {
+        // bcrypt keys with the NUL-terminated password, capped at 72 bytes
+        let mut key = password.as_bytes().to_vec();
+        key.push(0);
+        key.truncate(72);
+        bcrypt::bcrypt(self.cost, self.salt, &key)[..23] == self.hash
+    }
+
+    fn cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+impl std::fmt::Display for BcryptTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "$2{}${:02}${}{}",
+            self.variant,
+            self.cost,
+            encode_base64(&self.salt),
+            encode_base64(&self.hash)
+        )
+    }
+}
+
+/// bcrypt's base64 alphabet (no padding)
+const BCRYPT_BASE64: &[u8; 64] =
+    b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+fn decode_base64<const N: usize>(text: &str) -> Result<[u8; N], Box<dyn Error>> {
+    let mut bytes = Vec::with_capacity(N + 2);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = BCRYPT_BASE64
+            .iter()
+            .position(|&b| b == c)
+            .ok_or("invalid character in bcrypt hash")?;
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    bytes
+        .try_into()
+        .map_err(|_| "bcrypt salt or hash has the wrong length".into())
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let mut acc = 0u32;
+        for (i, &b) in chunk.iter().enumerate() {
+            acc |= (b as u32) << (16 - 8 * i);
+        }
+        for i in 0..=chunk.len() {
+            out.push(BCRYPT_BASE64[(acc >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
@@ -0,0 +1,102 @@
+//! One interface over the GPU and CPU backends
+//!
+//! Every mode's target parses into a [`Target`], and both [`GpuCracker`]
+//! and [`CpuCracker`] implement [`Cracker`] for the targets they can run.
+//! A caller holding one of each picks whichever
+//! [`supports`](Cracker::supports) the target instead of matching on modes
+//! itself.
+
+use crate::{
+    BcryptTarget, CpuCracker, CrackerError, GpuCracker, MysqlTarget, NetNtlmTarget,
+    OfficeRc4Target, PartialMd5Target, Pbkdf2Target, PostgresMd5Target, PwdumpEntry, ScanResult,
+    WpaTarget, ZipCryptoTarget,
+};
+
+/// A single target of any mode
+pub enum Target {
+    Md5([u8; 16]),
+    PartialMd5(PartialMd5Target),
+    OldOffice(OfficeRc4Target),
+    Postgres(PostgresMd5Target),
+    Mysql(MysqlTarget),
+    Lm([u8; 16]),
+    Ntlm([u8; 16]),
+    Pwdump(PwdumpEntry),
+    NetNtlm(NetNtlmTarget),
+    Wpa(WpaTarget),
+    Zip(ZipCryptoTarget),
+    Bcrypt(BcryptTarget),
+    Pbkdf2(Pbkdf2Target),
+}
+
+/// A backend that looks for a target's password in a wordlist
+pub trait Cracker {
+    /// Whether the backend runs `target`'s mode
+    fn supports(&self, target: &Target) -> bool;
+
+    /// Crack `target`, failing with [`CrackerError::InvalidTarget`] if the
+    /// backend does not support it
+    ///
+    /// A [`Target::PartialMd5`] stops at its first match; see
+    /// [`GpuCracker::crack_md5_partial`] for every one.
+    fn crack_target(
+        &mut self,
+        target: &Target,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError>;
+}
+
+impl Cracker for GpuCracker {
+    fn supports(&self, target: &Target) -> bool {
+        !matches!(target, Target::Bcrypt(_))
+    }
+
+    fn crack_target(
+        &mut self,
+        target: &Target,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        match target {
+            Target::Md5(hash) => self.crack(hash, wordlist),
+            Target::PartialMd5(partial) => {
+                let result = self.crack_md5_partial(partial, wordlist, false)?;
+                Ok(ScanResult {
+                    found: result.found.into_iter().next(),
+                    outcome: result.outcome,
+                })
+            }
+            Target::OldOffice(office) => self.crack_office_rc4(office, wordlist),
+            Target::Postgres(pg) => self.crack_postgres_md5(pg, wordlist),
+            Target::Mysql(my) => self.crack_mysql(my, wordlist),
+            Target::Lm(lm) => self.crack_lm(lm, wordlist),
+            Target::Ntlm(nt) => self.crack_ntlm(nt, wordlist),
+            Target::Pwdump(entry) => self.crack_pwdump(entry, wordlist),
+            Target::NetNtlm(net) => self.crack_netntlm(net, wordlist),
+            Target::Wpa(wpa) => self.crack_wpa(wpa, wordlist),
+            Target::Zip(zip) => self.crack_zip(zip, wordlist),
+            Target::Pbkdf2(pbkdf2) => self.crack_pbkdf2(pbkdf2, wordlist),
+            Target::Bcrypt(_) => Err(CrackerError::InvalidTarget(
+                "bcrypt runs on the CPU cracker".into(),
+            )),
+        }
+    }
+}
+
+impl Cracker for CpuCracker {
+    fn supports(&self, target: &Target) -> bool {
+        matches!(target, Target::Bcrypt(_))
+    }
+
+    fn crack_target(
+        &mut self,
+        target: &Target,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let Target::Bcrypt(bcrypt) = target else {
+            return Err(CrackerError::InvalidTarget(
+                "only bcrypt runs on the CPU cracker".into(),
+            ));
+        };
+        Ok(self.crack_bcrypt(bcrypt, wordlist))
+    }
+}
@@ -0,0 +1,203 @@
+//! Database password hash modes
+//!
+//! * PostgreSQL `md5` passwords: `"md5" || md5(password || username)`. The
+//!   username acts as a per-target salt and is appended to every candidate
+//!   before it goes through the regular MD5 kernel.
+//! * MySQL 4.1+ native passwords: `"*" || SHA1(SHA1(password))`.
+//! * Pre-4.1 MySQL `OLD_PASSWORD()` hashes (MySQL323), 16 hex characters.
+
+use crate::{CrackerError, GpuCracker, Kernel, PartialMd5Target, ScanResult};
+use std::error::Error;
+
+/// A PostgreSQL `md5` password hash together with its owner
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PostgresMd5Target {
+    pub username: String,
+    pub hash: [u8; 16],
+}
+
+impl PostgresMd5Target {
+    /// Parse one line of a `pg_shadow`/`pg_authid` dump
+    ///
+    /// Accepts `user:md5<hex>`, `user | md5<hex> | ...` rows as printed by
+    /// psql, and hashcat's `<hex>:user` format. The username is the first
+    /// field that isn't the hash.
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let fields = split_fields(line);
+        let hash_pos = fields
+            .iter()
+            .position(|f| parse_pg_hash(f).is_some())
+            .ok_or("no PostgreSQL md5 hash found")?;
+        let hash = parse_pg_hash(fields[hash_pos]).unwrap();
+        let username = fields
+            .iter()
+            .enumerate()
+            .find(|&(i, _)| i != hash_pos)
+            .map(|(_, f)| f.to_string())
+            .ok_or("no username found (needed as salt)")?;
+
+        Ok(Self { username, hash })
+    }
+
+    /// Parse every md5 entry of a dump, skipping headers and other rows
+    pub fn parse_dump(text: &str) -> Vec<Self> {
+        text.lines().filter_map(|l| Self::parse(l).ok()).collect()
+    }
+
+    /// Check a password on the CPU
+    pub fn verify(&self, password: &str) -> bool {
+        let mut data = password.as_bytes().to_vec();
+        data.extend_from_slice(self.username.as_bytes());
+        md5::compute(data).0 == self.hash
+    }
+}
+
+impl std::fmt::Display for PostgresMd5Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:md5{}", self.username, hex::encode(self.hash))
+    }
+}
+
+/// The two MySQL password hash formats
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MysqlHash {
+    /// `*` followed by SHA1(SHA1(password)) in hex
+    Native([u8; 20]),
+    /// Pre-4.1 `OLD_PASSWORD()` hash, as two 31-bit words
+    Old323([u32; 2]),
+}
+
+impl MysqlHash {
+    fn parse(field: &str) -> Option<Self> {
+        if let Some(hex_part) = field.strip_prefix('*') {
+            let bytes = hex::decode(hex_part).ok()?;
+            return bytes.try_into().ok().map(MysqlHash::Native);
+        }
+        if field.len() == 16 && field.bytes().all(|b| b.is_ascii_hexdigit()) {
+            let hi = u32::from_str_radix(&field[..8], 16).ok()?;
+            let lo = u32::from_str_radix(&field[8..], 16).ok()?;
+            // Both halves are masked to 31 bits by the server
+            if hi & 0x8000_0000 == 0 && lo & 0x8000_0000 == 0 {
+                return Some(MysqlHash::Old323([hi, lo]));
+            }
+        }
+        None
+    }
+}
+
+/// A MySQL password hash, optionally with the account it belongs to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MysqlTarget {
+    pub username: Option<String>,
+    pub hash: MysqlHash,
+}
+
+impl MysqlTarget {
+    /// Parse one line of a `mysql.user` dump
+    ///
+    /// Accepts a bare hash, `user:hash`, and whitespace or pipe separated rows such as
+    /// `localhost | root | *2470...`. The username is taken from the column
+    /// right before the hash, as in `SELECT host, user, password`.
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let fields = split_fields(line);
+        let hash_pos = fields
+            .iter()
+            .position(|f| MysqlHash::parse(f).is_some())
+            .ok_or("no MySQL password hash found")?;
+        let hash = MysqlHash::parse(fields[hash_pos]).unwrap();
+        let username = if hash_pos > 0 {
+            Some(fields[hash_pos - 1].to_string())
+        } else {
+            fields.get(1).map(|f| f.to_string())
+        };
+
+        Ok(Self { username, hash })
+    }
+
+    /// Parse every hash of a dump, skipping headers and other rows
+    pub fn parse_dump(text: &str) -> Vec<Self> {
+        text.lines().filter_map(|l| Self::parse(l).ok()).collect()
+    }
+}
+
+impl std::fmt::Display for MysqlTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(user) = &self.username {
+            write!(f, "{user}:")?;
+        }
+        match &self.hash {
+            MysqlHash::Native(h) => write!(f, "*{}", hex::encode_upper(h)),
+            MysqlHash::Old323([hi, lo]) => write!(f, "{hi:08x}{lo:08x}"),
+        }
+    }
+}
+
+/// MySQL `OLD_PASSWORD()` on the CPU
+pub fn mysql323(password: &[u8]) -> [u32; 2] {
+    let mut nr = 1345345333u32;
+    let mut nr2 = 0x12345671u32;
+    let mut add = 7u32;
+    for &b in password {
+        if b == b' ' || b == b'\t' {
+            continue;
+        }
+        let c = b as u32;
+        nr ^= ((nr & 63).wrapping_add(add))
+            .wrapping_mul(c)
+            .wrapping_add(nr << 8);
+        nr2 = nr2.wrapping_add((nr2 << 8) ^ nr);
+        add = add.wrapping_add(c);
+    }
+    [nr & 0x7fff_ffff, nr2 & 0x7fff_ffff]
+}
+
+impl GpuCracker {
+    /// Crack a PostgreSQL `md5` password hash
+    pub fn crack_postgres_md5(
+        &mut self,
+        target: &PostgresMd5Target,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let params = PartialMd5Target::full(target.hash).params();
+        self.crack_with(
+            Kernel::Md5,
+            bytemuck::cast_slice(&params),
+            target.username.as_bytes(),
+            wordlist,
+        )
+    }
+
+    /// Crack a MySQL native or MySQL323 password hash
+    pub fn crack_mysql(
+        &mut self,
+        target: &MysqlTarget,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        match &target.hash {
+            MysqlHash::Native(digest) => self.crack_with(Kernel::Mysql41, digest, &[], wordlist),
+            MysqlHash::Old323(words) => self.crack_with(
+                Kernel::Mysql323,
+                bytemuck::cast_slice(words.as_slice()),
+                &[],
+                wordlist,
+            ),
+        }
+    }
+}
+
+/// Split a dump line on the separators psql, mysql and hashcat use
+fn split_fields(line: &str) -> Vec<&str> {
+    line.split(|c: char| matches!(c, '|' | ':' | ',') || c.is_whitespace())
+        .map(|f| f.trim().trim_matches(['\'', '"', '`']))
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+/// `md5` + 32 hex characters (pg_shadow) or bare 32 hex characters (hashcat)
+fn parse_pg_hash(field: &str) -> Option<[u8; 16]> {
+    let hex_part = field.strip_prefix("md5").unwrap_or(field);
+    if hex_part.len() != 32 {
+        return None;
+    }
+    hex::decode(hex_part).ok()?.try_into().ok()
+}
@@ -0,0 +1,92 @@
+//! Errors the GPU cracker reports
+//!
+//! Every fallible [`GpuCracker`](crate::GpuCracker) method returns a
+//! [`CrackerError`], so callers can tell a machine without a usable GPU
+//! from a device that went away mid-scan or a wordlist the kernels cannot
+//! take. Target parsers keep returning plain messages, since there is
+//! nothing to do about a malformed hash but show it.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// What went wrong setting up or running the GPU cracker
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CrackerError {
+    /// No GPU adapter is available (no Vulkan driver, or none that works)
+    NoAdapter(wgpu::RequestAdapterError),
+    /// The adapter refused to open a device with the features and limits
+    /// the cracker needs
+    DeviceRequest(wgpu::RequestDeviceError),
+    /// The device was lost (reset by the driver, say) more often than one
+    /// scan recovers from
+    DeviceLost,
+    /// Reading results back from the GPU failed
+    MapFailed(wgpu::BufferAsyncError),
+    /// wgpu reported an error, such as running out of GPU memory
+    Gpu(String),
+    /// A candidate is longer than the kernels take, once encoded and with
+    /// any salt appended
+    CandidateTooLong {
+        /// Position of the candidate in the wordlist or inputs
+        index: usize,
+        /// Its length in bytes
+        len: usize,
+        /// Longest the kernels take
+        max: usize,
+    },
+    /// The target does not fit what the kernels expect
+    InvalidTarget(String),
+    /// An option is out of range, such as a batch size of zero or one
+    /// larger than the device allows
+    InvalidOption(String),
+    /// The autotuning cache could not be read or written
+    TuneCache(io::Error),
+    /// A custom SPIR-V module does not fit the cracker, or the device
+    /// rejected it
+    InvalidShader(String),
+    /// A custom SPIR-V module could not be read
+    ShaderFile(io::Error),
+    /// The thread polling the device for the async calls could not be
+    /// started
+    PollerThread(io::Error),
+}
+
+impl fmt::Display for CrackerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrackerError::NoAdapter(e) => write!(f, "no GPU adapter available: {e}"),
+            CrackerError::DeviceRequest(e) => write!(f, "failed to open the GPU device: {e}"),
+            CrackerError::DeviceLost => write!(f, "GPU device lost and could not be recovered"),
+            CrackerError::MapFailed(e) => write!(f, "failed to read results from the GPU: {e}"),
+            CrackerError::Gpu(message) => write!(f, "GPU error: {message}"),
+            CrackerError::CandidateTooLong { index, len, max } => write!(
+                f,
+                "candidate {index} is {len} bytes long, more than the {max} the GPU takes"
+            ),
+            CrackerError::InvalidTarget(message) => write!(f, "invalid target: {message}"),
+            CrackerError::InvalidOption(message) => f.write_str(message),
+            CrackerError::TuneCache(e) => write!(f, "autotune cache: {e}"),
+            CrackerError::InvalidShader(message) => write!(f, "invalid shader: {message}"),
+            CrackerError::ShaderFile(e) => write!(f, "failed to read the shader: {e}"),
+            CrackerError::PollerThread(e) => {
+                write!(f, "failed to start the device polling thread: {e}")
+            }
+        }
+    }
+}
+
+impl Error for CrackerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CrackerError::NoAdapter(e) => Some(e),
+            CrackerError::DeviceRequest(e) => Some(e),
+            CrackerError::MapFailed(e) => Some(e),
+            CrackerError::TuneCache(e) => Some(e),
+            CrackerError::ShaderFile(e) => Some(e),
+            CrackerError::PollerThread(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Events an embedding application can observe the cracker through
+//!
+//! Diagnostics go to the [`log`] facade; events are the typed counterpart
+//! for programs that want to react to what the cracker does, such as
+//! updating a progress bar or storing hits as soon as they are found. A
+//! handler is set with [`GpuOptions::on_event`](crate::GpuOptions::on_event)
+//! or [`GpuCracker::set_event_handler`](crate::GpuCracker::set_event_handler)
+//! and is called on the thread driving the cracker.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Something the cracker did
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum CrackerEvent {
+    /// A device was opened on this adapter, at startup or to replace a
+    /// lost one
+    AdapterSelected {
+        name: String,
+        backend: wgpu::Backend,
+        driver: String,
+    },
+    /// A batch was submitted to the GPU
+    BatchSubmitted {
+        /// Shader entry point running it
+        kernel: &'static str,
+        candidates: usize,
+        /// Dispatches it was split into to stay under the dispatch target
+        dispatches: usize,
+    },
+    /// A batch's results are in
+    Progress(Progress),
+    /// A candidate matched the target
+    HitFound { candidate: String },
+    /// The device was lost and is being recreated; every batch in flight
+    /// is submitted again
+    DeviceLost {
+        /// Recoveries so far in this scan, this one included
+        recoveries: usize,
+    },
+}
+
+/// How far a scan has got, reported after every batch
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Progress {
+    /// Candidates checked so far in this scan
+    pub checked: usize,
+    /// Candidates in the whole scan, when known up front: the wordlist
+    /// length, or the keyspace of a vanity search
+    pub total: Option<usize>,
+    /// Time since the scan started
+    pub elapsed: Duration,
+    /// Candidates per second over the last few batches, as seen from the
+    /// host
+    pub hashes_per_sec: f64,
+    /// Candidates per second the GPU spent on the last batch, measured
+    /// with timestamp queries when the device supports them
+    pub gpu_hashes_per_sec: Option<f64>,
+}
+
+impl Progress {
+    /// Share of the scan done, from 0 to 1, when its size is known
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .map(|total| (self.checked as f64 / total.max(1) as f64).min(1.0))
+    }
+
+    /// Estimated time left at the current rate, when the scan's size is
+    /// known
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.checked);
+        (self.hashes_per_sec > 0.0)
+            .then(|| Duration::from_secs_f64(remaining as f64 / self.hashes_per_sec))
+    }
+}
+
+/// Callback receiving every [`CrackerEvent`]
+///
+/// Cloning shares the same callback; two handlers compare equal when they
+/// share one.
+#[derive(Clone)]
+pub struct EventHandler(Arc<dyn Fn(&CrackerEvent) + Send + Sync>);
+
+impl EventHandler {
+    pub fn new(handler: impl Fn(&CrackerEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    pub(crate) fn emit(&self, event: &CrackerEvent) {
+        (self.0)(event);
+    }
+}
+
+impl fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventHandler(..)")
+    }
+}
+
+impl PartialEq for EventHandler {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for EventHandler {}
+
+/// Hand `event` to `handler`, if there is one, building it only then
+pub(crate) fn emit(handler: &Option<EventHandler>, event: impl FnOnce() -> CrackerEvent) {
+    if let Some(handler) = handler {
+        handler.emit(&event());
+    }
+}
@@ -0,0 +1,130 @@
+//! Bulk MD5 hashing
+//!
+//! The same batched, pipelined ring of buffer sets the crackers use, but
+//! returning every digest instead of searching for one: `md5_digest`
+//! writes each message's MD5 into the per-candidate storage buffer, which
+//! is copied back after every batch. Useful for deduplicating or
+//! fingerprinting millions of small records.
+
+use crate::{CrackerError, GpuCracker, Kernel};
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Instant;
+
+impl GpuCracker {
+    /// MD5 every input on the GPU, returning the digests in input order
+    ///
+    /// Inputs can be up to one batch's messages buffer long (20 MiB at the
+    /// default batch size); a longer one fails with
+    /// [`CrackerError::CandidateTooLong`] before anything is hashed.
+    pub fn md5_digests<T: AsRef<[u8]>>(
+        &mut self,
+        inputs: &[T],
+    ) -> Result<Vec<[u8; 16]>, CrackerError> {
+        // Leave room for the padding to whole words and the spare word
+        // after them
+        let max_bytes = self.messages_buffer_size() - 8;
+        let batches = split_batches(inputs, self.batch_size_for(Kernel::Md5Digest), max_bytes)?;
+        let mut digests = Vec::with_capacity(inputs.len());
+        if batches.is_empty() {
+            return Ok(digests);
+        }
+
+        self.begin_scan(Kernel::Md5Digest, Some(inputs.len()))?;
+
+        // Same ring as `scan_with`: keep every buffer set busy, reading
+        // each batch back just before its set is reused
+        let depth = self.pipeline_depth();
+        let mut in_flight = VecDeque::with_capacity(depth);
+        for (i, batch) in batches.iter().enumerate() {
+            let slot = i % depth;
+            if in_flight.len() == depth {
+                digests.extend(self.read_oldest_digests(&mut in_flight, inputs)?);
+            }
+            self.prepare_digest_batch(slot, &inputs[batch.clone()]);
+            in_flight.push_back((slot, batch.clone()));
+        }
+        while !in_flight.is_empty() {
+            digests.extend(self.read_oldest_digests(&mut in_flight, inputs)?);
+        }
+
+        Ok(digests)
+    }
+
+    /// MD5 a single input on the GPU
+    pub fn md5_digest(&mut self, input: &[u8]) -> Result<[u8; 16], CrackerError> {
+        Ok(self.md5_digests(&[input])?[0])
+    }
+
+    /// Read the digests of the oldest batch in flight, hashing every batch
+    /// in flight again if the device was lost
+    ///
+    /// On any other error the remaining batches are left to finish
+    /// unread.
+    fn read_oldest_digests<T: AsRef<[u8]>>(
+        &mut self,
+        in_flight: &mut VecDeque<(usize, Range<usize>)>,
+        inputs: &[T],
+    ) -> Result<Vec<[u8; 16]>, CrackerError> {
+        let (slot, batch) = in_flight.pop_front().expect("a batch in flight");
+        loop {
+            let started = Instant::now();
+            match self.read_digests(slot, batch.len()) {
+                Ok(digests) => {
+                    self.batch_done(slot, started.elapsed());
+                    return Ok(digests);
+                }
+                Err(CrackerError::DeviceLost) => {
+                    self.recover_device()?;
+                    for (slot, batch) in std::iter::once(&(slot, batch.clone())).chain(&*in_flight)
+                    {
+                        self.prepare_digest_batch(*slot, &inputs[batch.clone()]);
+                    }
+                }
+                Err(e) => {
+                    self.discard_in_flight(in_flight);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn prepare_digest_batch<T: AsRef<[u8]>>(&mut self, slot: usize, inputs: &[T]) {
+        let mut batch = std::mem::take(&mut self.batch);
+        batch.pack_raw(inputs);
+        self.submit_packed(Kernel::Md5Digest, &[], slot, &batch, 0);
+        self.batch = batch;
+    }
+}
+
+/// Split inputs into batches of at most `max_count` inputs and `max_bytes`
+/// bytes
+fn split_batches<T: AsRef<[u8]>>(
+    inputs: &[T],
+    max_count: usize,
+    max_bytes: usize,
+) -> Result<Vec<Range<usize>>, CrackerError> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+    for (i, input) in inputs.iter().enumerate() {
+        let input_bytes = input.as_ref().len();
+        if input_bytes > max_bytes {
+            return Err(CrackerError::CandidateTooLong {
+                index: i,
+                len: input_bytes,
+                max: max_bytes,
+            });
+        }
+        if i - start == max_count || bytes + input_bytes > max_bytes {
+            batches.push(start..i);
+            start = i;
+            bytes = 0;
+        }
+        bytes += input_bytes;
+    }
+    if start < inputs.len() {
+        batches.push(start..inputs.len());
+    }
+    Ok(batches)
+}
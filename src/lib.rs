@@ -1,9 +1,278 @@
 use bytemuck::{Pod, Zeroable};
 use std::borrow::Cow;
-
-// How many hashes do we compute at a time?
-pub const BATCH_SIZE: usize = 65536; // Optimized for GPU utilization (was 4096)
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use profiler::{BatchTiming, Profiler};
+
+pub mod autotune;
+pub mod benchmark;
+pub mod cancel;
+pub mod cpu;
+pub mod cracker;
+pub mod database;
+pub mod error;
+pub mod events;
+pub mod hasher;
+pub mod netntlm;
+pub mod nonblocking;
+pub mod office;
+pub mod partial;
+pub mod pbkdf2;
+pub mod profiler;
+pub mod shader;
+pub mod vanity;
+pub mod windows;
+pub mod wpa;
+pub mod zip;
+
+pub use autotune::TuneResult;
+pub use benchmark::{benchmark_json, BenchmarkResult, BENCHMARK_MODES, CPU_BENCHMARK_MODES};
+pub use cancel::{CancelToken, ScanOutcome, ScanResult};
+pub use cpu::{BcryptTarget, CpuCracker, SlowHash};
+pub use cracker::{Cracker, Target};
+pub use database::{MysqlHash, MysqlTarget, PostgresMd5Target};
+pub use error::CrackerError;
+pub use events::{CrackerEvent, EventHandler, Progress};
+pub use netntlm::{NetNtlmResponse, NetNtlmTarget};
+pub use nonblocking::HitStream;
+pub use office::OfficeRc4Target;
+pub use partial::PartialMd5Target;
+pub use pbkdf2::{Pbkdf2Format, Pbkdf2Prf, Pbkdf2Target};
+pub use profiler::{KernelProfile, ProfileReport};
+pub use shader::CustomShader;
+pub use vanity::{CandidateGenerator, VanitySearch};
+pub use windows::PwdumpEntry;
+pub use wpa::{WpaCapture, WpaTarget};
+pub use zip::{ZipCryptoTarget, ZipEntry};
+
+/// Candidates per batch unless set with [`GpuOptions::batch_size`] or
+/// autotuned
+pub const BATCH_SIZE: usize = 65536;
+/// Largest batch a cracker allocates buffers for, however much more the
+/// device would allow
+pub const MAX_BATCH_SIZE: usize = 262144;
+/// Longest candidate the kernels take, in bytes once encoded and salted
 pub const MAX_MSG_SIZE: usize = 256;
+/// Batches kept in flight by default: one being read back, the rest
+/// queued so the GPU always has the next one
+pub const DEFAULT_PIPELINE_DEPTH: usize = 3;
+/// Size of the shared target buffer (target hash plus any per-mode
+/// parameters, up to a padded NetNTLMv2 blob)
+const TARGET_BUFFER_SIZE: u64 = 2048;
+/// Number of hits a kernel that lists them can report per batch; kernels
+/// with a weak check (ZipCrypto) fill it with candidates to verify
+const RESULT_SLOTS: usize = 1024;
+/// Size of the result buffer: a hit count followed by the list of hits
+const RESULT_BUFFER_SIZE: u64 = ((RESULT_SLOTS + 1) * 4) as u64;
+/// GPU time a single dispatch aims to stay under by default, far below the
+/// couple of seconds after which desktop drivers reset a busy GPU
+pub const DEFAULT_DISPATCH_TARGET: Duration = Duration::from_millis(50);
+/// Candidates in the first dispatch of a scan, which is timed to work out
+/// how many fit in the dispatch target
+const PROBE_CANDIDATES: usize = 4096;
+/// Times one scan recreates a lost device before giving up
+const MAX_DEVICE_RECOVERIES: usize = 3;
+/// Delivered batches the reported hash rate is averaged over
+const RATE_WINDOW: usize = 16;
+/// Dispatches of one batch that get timestamps of their own while
+/// profiling; any after those go untimed
+const PROFILED_DISPATCHES: u32 = 256;
+/// Iterations an iterated kernel (PBKDF2) runs per dispatch, so a high
+/// iteration count never turns into one dispatch long enough to trip the
+/// driver's watchdog
+pub const ITERATIONS_PER_DISPATCH: u32 = 1000;
+/// Words of per-candidate storage: state iterated kernels keep between
+/// dispatches, or the digests `md5_digest` writes
+const STATE_WORDS: usize = 16;
+/// Bytes of packed candidates a batch has room for per candidate
+const MESSAGE_BYTES_PER_CANDIDATE: usize = 320;
+/// Invocations per workgroup every kernel has an entry point for
+const DEFAULT_WORKGROUP_SIZE: u32 = 64;
+/// Longest candidate that still pads out to a single MD5 block
+const SINGLE_BLOCK_LEN: u32 = 55;
+/// MD5 initial state
+const MD5_IV: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// Name of the variant of entry point `name` with `workgroup_size`
+/// invocations per workgroup
+fn sized_entry_point<'a>(name: &'a str, workgroup_size: u32) -> Cow<'a, str> {
+    if workgroup_size == DEFAULT_WORKGROUP_SIZE {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("{name}_wg{workgroup_size}"))
+    }
+}
+
+/// Compute kernels exported by the shader crate
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Kernel {
+    Md5,
+    Md5Short,
+    OfficeRc4,
+    Mysql41,
+    Mysql323,
+    Ntlm,
+    Lm,
+    NetNtlmV1,
+    NetNtlmV2,
+    Wpa,
+    ZipCrypto,
+    Pbkdf2,
+    Md5Vanity,
+    Md5Digest,
+}
+
+impl Kernel {
+    fn entry_point(self) -> &'static str {
+        match self {
+            Kernel::Md5 => "md5_crack",
+            Kernel::Md5Short => "md5_crack_short",
+            Kernel::OfficeRc4 => "office_rc4_crack",
+            Kernel::Mysql41 => "mysql41_crack",
+            Kernel::Mysql323 => "mysql323_crack",
+            Kernel::Ntlm => "ntlm_crack",
+            Kernel::Lm => "lm_crack",
+            Kernel::NetNtlmV1 => "netntlmv1_crack",
+            Kernel::NetNtlmV2 => "netntlmv2_crack",
+            Kernel::Wpa => "wpa_crack",
+            Kernel::ZipCrypto => "zipcrypto_crack",
+            Kernel::Pbkdf2 => "pbkdf2_crack",
+            Kernel::Md5Vanity => "md5_vanity",
+            Kernel::Md5Digest => "md5_digest",
+        }
+    }
+
+    /// Name of the entry point running the kernel with `workgroup_size`
+    /// invocations per workgroup
+    fn entry_point_for(self, workgroup_size: u32) -> Cow<'static, str> {
+        sized_entry_point(self.entry_point(), workgroup_size)
+    }
+
+    /// Workgroup sizes the shader has entry points for
+    fn workgroup_sizes(self) -> &'static [u32] {
+        match self {
+            Kernel::Md5 | Kernel::Md5Short => &[64, 128, 256],
+            _ => &[DEFAULT_WORKGROUP_SIZE],
+        }
+    }
+
+    /// Whether the kernel's check can let several candidates of a batch
+    /// through, so it counts hits and appends them to a list instead of
+    /// writing the one it found
+    fn lists_hits(self) -> bool {
+        matches!(
+            self,
+            Kernel::Md5 | Kernel::Md5Short | Kernel::ZipCrypto | Kernel::Md5Vanity
+        )
+    }
+
+    /// What the kernel's words of the result buffer start out as: no hits
+    /// counted, or an empty slot per target (LM has one for each half)
+    fn result_init(self) -> &'static [i32] {
+        match self {
+            _ if self.lists_hits() => &[0],
+            Kernel::Lm => &[-1, -1],
+            _ => &[-1],
+        }
+    }
+
+    /// Bytes of the result buffer the kernel writes, and so reads back
+    fn result_bytes(self) -> u64 {
+        if self.lists_hits() {
+            RESULT_BUFFER_SIZE
+        } else {
+            self.result_init().len() as u64 * 4
+        }
+    }
+
+    /// Byte encoding the kernel expects candidates in
+    fn encoding(self) -> Encoding {
+        match self {
+            Kernel::Md5
+            | Kernel::Md5Short
+            | Kernel::Mysql41
+            | Kernel::Mysql323
+            | Kernel::Lm
+            | Kernel::Wpa
+            | Kernel::ZipCrypto
+            | Kernel::Pbkdf2
+            | Kernel::Md5Vanity
+            | Kernel::Md5Digest => Encoding::Utf8,
+            Kernel::OfficeRc4 | Kernel::Ntlm | Kernel::NetNtlmV1 | Kernel::NetNtlmV2 => {
+                Encoding::Utf16Le
+            }
+        }
+    }
+}
+
+/// Batch and workgroup size picked for a kernel by autotuning
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Tuning {
+    batch_size: usize,
+    workgroup_size: u32,
+}
+
+/// How candidate strings are turned into bytes before hashing
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+}
+
+impl Encoding {
+    /// Length of `msg` in bytes once encoded
+    fn encoded_len(self, msg: &str) -> usize {
+        match self {
+            Encoding::Utf8 => msg.len(),
+            Encoding::Utf16Le => msg.encode_utf16().count() * 2,
+        }
+    }
+}
+
+/// Check that every candidate, encoded and with `suffix` appended, fits in
+/// [`MAX_MSG_SIZE`]
+fn check_candidates(
+    messages: &[&str],
+    encoding: Encoding,
+    suffix: &[u8],
+) -> Result<(), CrackerError> {
+    for (index, msg) in messages.iter().enumerate() {
+        let len = encoding.encoded_len(msg) + suffix.len();
+        if len > MAX_MSG_SIZE {
+            return Err(CrackerError::CandidateTooLong {
+                index,
+                len,
+                max: MAX_MSG_SIZE,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Outcome of mapping a buffer for reading, filled in by the `map_async`
+/// callback once the GPU is done with it
+#[derive(Clone, Default)]
+struct MapStatus(Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>);
+
+impl MapStatus {
+    /// Map `slice` for reading, pending until the callback runs
+    fn request(&self, slice: wgpu::BufferSlice<'_>) {
+        *self.0.lock().unwrap() = None;
+        let status = Arc::clone(&self.0);
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            *status.lock().unwrap() = Some(result);
+        });
+    }
+
+    /// The outcome, once the callback has run
+    fn get(&self) -> Option<Result<(), wgpu::BufferAsyncError>> {
+        self.0.lock().unwrap().clone()
+    }
+}
 
 /// Append the MD5 16-word blocks for a message into the provided buffer
 /// Returns the number of 64-byte blocks appended
@@ -35,36 +304,217 @@ fn append_md5_blocks_for(msg: &[u8], out: &mut Vec<u32>) -> u32 {
     block_count as u32
 }
 
+/// Append the MD5 blocks of an HMAC message, padded as if the 64-byte key
+/// block came first, so a kernel can continue from the ipad state
+/// Returns the number of 64-byte blocks appended
+fn append_hmac_md5_blocks_for(msg: &[u8], out: &mut Vec<u32>) -> u32 {
+    let mut with_key_block = vec![0u8; 64];
+    with_key_block.extend_from_slice(msg);
+
+    let mut blocks = Vec::new();
+    let block_count = append_md5_blocks_for(&with_key_block, &mut blocks);
+    out.extend_from_slice(&blocks[16..]);
+    block_count - 1
+}
+
+/// Like [`append_hmac_md5_blocks_for`], but with SHA1's big-endian words and
+/// length (SHA256 pads the same way)
+fn append_hmac_sha1_blocks_for(msg: &[u8], out: &mut Vec<u32>) -> u32 {
+    let mut data = msg.to_vec();
+    data.push(0x80);
+    while data.len() % 64 != 56 {
+        data.push(0);
+    }
+    let bit_len = (64 + msg.len() as u64) * 8;
+    data.extend_from_slice(&bit_len.to_be_bytes());
+
+    out.extend(
+        data.chunks_exact(4)
+            .map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]])),
+    );
+    (data.len() / 64) as u32
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 pub struct TargetHash {
     pub data: [u32; 4],
 }
 
+/// A batch of candidates packed back to back as the kernels read them
+///
+/// Only the raw bytes and each candidate's byte offset are uploaded; the
+/// kernels build the hash padding and length words themselves.
+#[derive(Default)]
+struct PackedBatch {
+    bytes: Vec<u8>,
+    offsets: Vec<u32>,
+    // Positions of the messages left out for being longer than
+    // MAX_MSG_SIZE once encoded and salted
+    skipped: Vec<usize>,
+}
+
+impl PackedBatch {
+    /// Pack encoded candidates, appending `suffix` (a per-target salt) to
+    /// every candidate after encoding
+    ///
+    /// Candidates longer than [`MAX_MSG_SIZE`] are skipped with a warning;
+    /// [`messages`](Self::messages) gives what was packed.
+    fn pack(&mut self, messages: &[&str], encoding: Encoding, suffix: &[u8]) {
+        self.bytes.clear();
+        self.offsets.clear();
+        self.offsets.push(0);
+        self.offsets.reserve(messages.len());
+        self.skipped.clear();
+
+        for (i, msg) in messages.iter().enumerate() {
+            let start = self.bytes.len();
+            match encoding {
+                Encoding::Utf8 => self.bytes.extend_from_slice(msg.as_bytes()),
+                Encoding::Utf16Le => {
+                    for unit in msg.encode_utf16() {
+                        self.bytes.extend_from_slice(&unit.to_le_bytes());
+                    }
+                }
+            }
+            self.bytes.extend_from_slice(suffix);
+            if self.bytes.len() - start > MAX_MSG_SIZE {
+                self.bytes.truncate(start);
+                self.skipped.push(i);
+                continue;
+            }
+            self.offsets.push(self.bytes.len() as u32);
+        }
+        self.finish();
+
+        if !self.skipped.is_empty() {
+            log::warn!(
+                "Skipped {} candidates longer than {MAX_MSG_SIZE} bytes",
+                self.skipped.len()
+            );
+        }
+        debug_assert_eq!(self.offsets.len(), messages.len() - self.skipped.len() + 1);
+    }
+
+    /// The `messages` the batch was packed from, minus those it skipped,
+    /// so that the kernels' hit positions index into them
+    fn messages<'a, 'b>(&self, messages: &'a [&'b str]) -> Cow<'a, [&'b str]> {
+        if self.skipped.is_empty() {
+            return Cow::Borrowed(messages);
+        }
+        let packed = messages
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.skipped.binary_search(i).is_err())
+            .map(|(_, msg)| *msg);
+        Cow::Owned(packed.collect())
+    }
+
+    /// Like [`pack`](Self::pack), for raw byte strings
+    fn pack_raw<T: AsRef<[u8]>>(&mut self, messages: &[T]) {
+        self.bytes.clear();
+        self.offsets.clear();
+        self.offsets.push(0);
+
+        for msg in messages {
+            self.bytes.extend_from_slice(msg.as_ref());
+            self.offsets.push(self.bytes.len() as u32);
+        }
+        self.finish();
+    }
+
+    /// Round the packed bytes up to whole words plus one spare zero word,
+    /// which a kernel reads when a candidate's last word straddles two
+    fn finish(&mut self) {
+        let len = self.bytes.len().next_multiple_of(4) + 4;
+        self.bytes.resize(len, 0);
+    }
+
+    /// Number of candidates
+    fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Whether every candidate pads out to a single MD5 block
+    fn single_block(&self) -> bool {
+        self.offsets
+            .windows(2)
+            .all(|w| w[1] - w[0] <= SINGLE_BLOCK_LEN)
+    }
+}
+
 /// A set of buffers for processing one batch
-/// Used for double-buffering to overlap CPU and GPU work
+///
+/// The cracker keeps a ring of them so several batches can be queued on
+/// the GPU while earlier ones are read back.
 struct BufferSet {
     messages_buffer: wgpu::Buffer,
     message_count_buffer: wgpu::Buffer,
-    block_offsets_buffer: wgpu::Buffer,
+    offsets_buffer: wgpu::Buffer,
     result_buffer: wgpu::Buffer,
     staging_buffer: wgpu::Buffer,
+    // Per-candidate storage, and where its digests are read back from
+    state_buffer: wgpu::Buffer,
+    digest_staging_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
+    // The last submission of the batch in this set, and whether its
+    // readback (results, or digests for `md5_digest`) has been mapped and,
+    // if so, is still to be read or released
+    submission: Option<wgpu::SubmissionIndex>,
+    reads_digests: bool,
+    readback: MapStatus,
+    unread: AtomicBool,
+    // The batch in the set, for progress reports and profiling: the
+    // kernel that ran it, its candidates and dispatches, and how long its
+    // upload took
+    kernel: Kernel,
+    candidates: usize,
+    dispatches: usize,
+    upload: Duration,
+    // Timestamps of its dispatches when the device supports them, and the
+    // passes they cover: the whole batch as one span, or each dispatch on
+    // its own while profiling
+    timer: Option<PassTimer>,
+    timer_readback: MapStatus,
+    timed_passes: u32,
 }
 
 impl BufferSet {
+    /// One buffer set per batch in flight
+    fn ring(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        target_buffer: &wgpu::Buffer,
+        depth: usize,
+        capacity: usize,
+        timestamps: bool,
+    ) -> Vec<Self> {
+        (0..depth)
+            .map(|i| {
+                Self::new(
+                    device,
+                    bind_group_layout,
+                    target_buffer,
+                    capacity,
+                    timestamps,
+                    &format!("Set {i}"),
+                )
+            })
+            .collect()
+    }
+
     fn new(
         device: &wgpu::Device,
         bind_group_layout: &wgpu::BindGroupLayout,
         target_buffer: &wgpu::Buffer,
+        capacity: usize,
+        timestamps: bool,
         label: &str,
     ) -> Self {
-        // Allocate buffers for this set
-        // Each message is now up to 5 blocks (320 bytes) preprocessed
-        let messages_buffer_size = (BATCH_SIZE * 5 * 16 * 4) as u64;
+        // Allocate buffers for `capacity` candidates in this set
         let messages_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some(&format!("{label} Messages Buffer")),
-            size: messages_buffer_size,
+            size: (capacity * MESSAGE_BYTES_PER_CANDIDATE) as u64,
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -76,16 +526,16 @@ impl BufferSet {
             mapped_at_creation: false,
         });
 
-        let block_offsets_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some(&format!("{label} Block Offsets Buffer")),
-            size: ((BATCH_SIZE as u64) + 1) * 4,
+        let offsets_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{label} Offsets Buffer")),
+            size: ((capacity as u64) + 1) * 4,
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
         let result_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some(&format!("{label} Result Buffer")),
-            size: 4,
+            size: RESULT_BUFFER_SIZE,
             usage: wgpu::BufferUsages::STORAGE
                 | wgpu::BufferUsages::COPY_SRC
                 | wgpu::BufferUsages::COPY_DST,
@@ -94,7 +544,23 @@ impl BufferSet {
 
         let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some(&format!("{label} Staging Buffer")),
-            size: 4,
+            size: RESULT_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Per-candidate state of iterated kernels, carried between
+        // dispatches, or the output of `md5_digest`
+        let state_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{label} State Buffer")),
+            size: (capacity * STATE_WORDS * 4) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let digest_staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{label} Digest Staging Buffer")),
+            size: (capacity * 16) as u64,
             usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -122,7 +588,11 @@ impl BufferSet {
                 },
                 wgpu::BindGroupEntry {
                     binding: 4,
-                    resource: block_offsets_buffer.as_entire_binding(),
+                    resource: offsets_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: state_buffer.as_entire_binding(),
                 },
             ],
         });
@@ -130,35 +600,438 @@ impl BufferSet {
         Self {
             messages_buffer,
             message_count_buffer,
-            block_offsets_buffer,
+            offsets_buffer,
             result_buffer,
             staging_buffer,
+            state_buffer,
+            digest_staging_buffer,
             bind_group,
+            submission: None,
+            reads_digests: false,
+            readback: MapStatus::default(),
+            unread: AtomicBool::new(false),
+            kernel: Kernel::Md5,
+            candidates: 0,
+            dispatches: 0,
+            upload: Duration::ZERO,
+            timer: timestamps.then(|| PassTimer::new(device, PROFILED_DISPATCHES)),
+            timer_readback: MapStatus::default(),
+            timed_passes: 0,
+        }
+    }
+}
+
+/// Timestamp queries around compute passes, a pair for each, resolved into
+/// a buffer the host can read back
+struct PassTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    staging_buffer: wgpu::Buffer,
+}
+
+impl PassTimer {
+    /// Room for the timestamps of `passes` passes
+    fn new(device: &wgpu::Device, passes: u32) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Timestamp Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: passes * 2,
+        });
+
+        let size = passes as u64 * 16; // 2 timestamps * 8 bytes per pass
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Query Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Query Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set,
+            resolve_buffer,
+            staging_buffer,
         }
     }
+
+    /// Timestamps to write for pass number `pass`: at its start, its end
+    /// or both
+    ///
+    /// A pass split over several dispatches writes its start with the
+    /// first and its end with the last.
+    fn writes(
+        &self,
+        pass: u32,
+        start: bool,
+        end: bool,
+    ) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+        (start || end).then(|| wgpu::ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: start.then_some(pass * 2),
+            end_of_pass_write_index: end.then_some(pass * 2 + 1),
+        })
+    }
+
+    /// Copy the timestamps of the first `passes` passes where
+    /// [`durations_ns`](Self::durations_ns) reads them
+    fn resolve(&self, encoder: &mut wgpu::CommandEncoder, passes: u32) {
+        encoder.resolve_query_set(&self.query_set, 0..passes * 2, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.staging_buffer,
+            0,
+            passes as u64 * 16,
+        );
+    }
+
+    /// Nanoseconds each of the first `passes` passes took, once the staging
+    /// buffer is mapped; unmaps it again
+    fn durations_ns(&self, passes: u32, timestamp_period: f32) -> Vec<u64> {
+        let data = self.staging_buffer.slice(..).get_mapped_range();
+        let durations = data[..passes as usize * 16]
+            .chunks_exact(16)
+            .map(|pair| {
+                let start_timestamp = u64::from_le_bytes(pair[0..8].try_into().unwrap());
+                let end_timestamp = u64::from_le_bytes(pair[8..16].try_into().unwrap());
+                (end_timestamp.saturating_sub(start_timestamp) as f64 * timestamp_period as f64)
+                    as u64
+            })
+            .collect();
+        drop(data);
+        self.staging_buffer.unmap();
+        durations
+    }
+}
+
+/// Everything tied to one device, which is all recreated if it is lost
+struct DeviceResources {
+    adapter_info: wgpu::AdapterInfo,
+    limits: wgpu::Limits,
+    supports_timestamps: bool,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    shader_module: wgpu::ShaderModule,
+    custom_module: Option<wgpu::ShaderModule>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    target_buffer: wgpu::Buffer,
+    lost: Arc<AtomicBool>,
+    error: Arc<Mutex<Option<String>>>,
 }
 
 /// GPU-based MD5 hash cracker with pipelined execution
 pub struct GpuCracker {
     device: wgpu::Device,
     queue: wgpu::Queue,
-    pipeline: wgpu::ComputePipeline,
-    #[allow(dead_code)]
+    shader_module: wgpu::ShaderModule,
+    // Module loaded at runtime whose kernels replace the built-in ones
+    custom_shader: Option<CustomShader>,
+    custom_module: Option<wgpu::ShaderModule>,
+    pipeline_layout: wgpu::PipelineLayout,
+    // Compute pipelines, created on first use of each kernel and
+    // workgroup size
+    pipelines: HashMap<(Kernel, u32), wgpu::ComputePipeline>,
     bind_group_layout: wgpu::BindGroupLayout,
     supports_timestamps: bool,
-    // Double-buffering: two complete buffer sets for pipelining
-    buffer_set_a: BufferSet,
-    buffer_set_b: BufferSet,
-    // Shared buffers (don't need double-buffering)
+    adapter_info: wgpu::AdapterInfo,
+    // Set once the device is lost, and how often the current scan has
+    // recreated it
+    device_lost: Arc<AtomicBool>,
+    recoveries: usize,
+    // First error wgpu reported outside any call that could return it
+    gpu_error: Arc<Mutex<Option<String>>>,
+    // GPU time each dispatch should stay under, and the measured cost of a
+    // candidate (per iteration, for iterated kernels) in nanoseconds
+    dispatch_target: Duration,
+    dispatch_costs: HashMap<Kernel, f64>,
+    // Dispatches left before the device is lost on purpose, for tests
+    #[cfg(feature = "test-hooks")]
+    lose_device_after: Option<usize>,
+    // Largest batch and workgroup the device allows
+    max_batch_size: usize,
+    max_workgroup_size: u32,
+    // Candidates per batch, and per kernel where autotuning picked sizes
+    batch_size: usize,
+    tuning: HashMap<Kernel, Tuning>,
+    // Ring of complete buffer sets, one per batch in flight, each with room
+    // for `batch_capacity` candidates
+    buffer_sets: Vec<BufferSet>,
+    batch_capacity: usize,
+    // Shared buffers (one per cracker, not per batch)
     target_buffer: wgpu::Buffer,
-    // Pre-allocated CPU buffers to avoid repeated allocations
-    batch_blocks: Vec<u32>, // Preprocessed MD5 blocks
-    block_offsets: Vec<u32>,
+    // Pre-allocated CPU buffers for batches packed on the calling thread
+    batch: PackedBatch,
+    // Where events go, and the progress of the current scan: when it
+    // started, the candidates checked by each recent delivery, and the
+    // GPU's own rate on the last timed batch
+    events: Option<EventHandler>,
+    scan_checked: usize,
+    scan_total: Option<usize>,
+    scan_started: Instant,
+    scan_history: VecDeque<(Instant, usize)>,
+    scan_gpu_rate: Option<f64>,
+    // What stops scans early, and why the current one is ending
+    cancel: Option<CancelToken>,
+    deadline: Option<Instant>,
+    scan_outcome: ScanOutcome,
+    // Polls the device for the async calls, started by the first of them
+    poller: Option<nonblocking::DevicePoller>,
+    // Per-stage timing of every batch read back, while profiling
+    profiler: Option<Profiler>,
+}
+
+/// Settings for [`GpuCracker::with_options`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GpuOptions {
+    /// Batches kept in flight, each with its own set of GPU buffers
+    pub pipeline_depth: usize,
+    /// Candidates per batch; `None` uses [`BATCH_SIZE`], or as much of it
+    /// as the device allows
+    pub batch_size: Option<usize>,
+    /// Measure each hash mode at startup to pick its batch and workgroup
+    /// size (see [`GpuCracker::autotune`])
+    pub autotune: bool,
+    /// File the autotuned sizes are kept in, per adapter, so later runs on
+    /// the same device can skip the measurements
+    pub tune_cache: Option<PathBuf>,
+    /// GPU time each dispatch should stay under; batches that would take
+    /// longer are split over several dispatches
+    pub dispatch_target: Duration,
+    /// Receives every [`CrackerEvent`], starting with the adapter picked
+    pub on_event: Option<EventHandler>,
+    /// Stops scans once cancelled (see [`GpuCracker::set_cancel_token`])
+    pub cancel: Option<CancelToken>,
+    /// Scans stop once this passes (see [`GpuCracker::set_deadline`])
+    pub deadline: Option<Instant>,
+    /// Time every dispatch and add up where each batch's time goes (see
+    /// [`GpuCracker::set_profiling`])
+    pub profile: bool,
+    /// SPIR-V module loaded at runtime to take the place of the built-in
+    /// MD5 kernel (see [`GpuCracker::with_shader`])
+    pub shader: Option<CustomShader>,
+}
+
+impl Default for GpuOptions {
+    fn default() -> Self {
+        Self {
+            pipeline_depth: DEFAULT_PIPELINE_DEPTH,
+            batch_size: None,
+            autotune: false,
+            tune_cache: None,
+            dispatch_target: DEFAULT_DISPATCH_TARGET,
+            on_event: None,
+            cancel: None,
+            deadline: None,
+            profile: false,
+            shader: None,
+        }
+    }
+}
+
+/// Largest batch the device's buffer and dispatch limits allow, capped at
+/// [`MAX_BATCH_SIZE`]
+fn device_batch_limit(limits: &wgpu::Limits) -> usize {
+    let per_candidate = MESSAGE_BYTES_PER_CANDIDATE as u64;
+    let binding = limits.max_storage_buffer_binding_size as u64 / per_candidate;
+    let buffer = limits.max_buffer_size / per_candidate;
+    let dispatch =
+        limits.max_compute_workgroups_per_dimension as u64 * DEFAULT_WORKGROUP_SIZE as u64;
+    binding.min(buffer).min(dispatch).min(MAX_BATCH_SIZE as u64) as usize
 }
 
 impl GpuCracker {
     /// Initialize the GPU cracker
-    pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new() -> Result<Self, CrackerError> {
+        Self::with_options(GpuOptions::default()).await
+    }
+
+    /// Initialize the GPU cracker with `depth` batches in flight
+    ///
+    /// Each batch in flight holds its own set of GPU buffers (about 25 MiB
+    /// at the default batch size), so deeper pipelines trade memory for
+    /// fewer GPU stalls.
+    pub async fn with_pipeline_depth(depth: usize) -> Result<Self, CrackerError> {
+        Self::with_options(GpuOptions {
+            pipeline_depth: depth,
+            ..GpuOptions::default()
+        })
+        .await
+    }
+
+    /// Initialize the GPU cracker running `shader`'s entry point as the
+    /// MD5 kernel
+    ///
+    /// The module is checked against the cracker's bind group layout when
+    /// it is loaded and again by the device here, so a kernel that does not
+    /// fit fails with [`CrackerError::InvalidShader`] rather than on its
+    /// first batch.
+    pub async fn with_shader(shader: CustomShader) -> Result<Self, CrackerError> {
+        Self::with_options(GpuOptions {
+            shader: Some(shader),
+            ..GpuOptions::default()
+        })
+        .await
+    }
+
+    /// Initialize the GPU cracker with the given pipeline depth, batch size
+    /// and autotuning
+    pub async fn with_options(options: GpuOptions) -> Result<Self, CrackerError> {
+        let depth = options.pipeline_depth;
+        if depth == 0 {
+            return Err(CrackerError::InvalidOption(
+                "pipeline depth must be at least 1".into(),
+            ));
+        }
+        if options.batch_size == Some(0) {
+            return Err(CrackerError::InvalidOption(
+                "batch size must be at least 1".into(),
+            ));
+        }
+        if options.dispatch_target.is_zero() {
+            return Err(CrackerError::InvalidOption(
+                "dispatch target must be more than zero".into(),
+            ));
+        }
+
+        let resources = Self::open_device(options.shader.as_ref()).await?;
+        log::debug!("Loading shader from: {}", env!("shader.spv"));
+        if let Some(shader) = &options.shader {
+            log::info!("Running custom kernel {}", shader.entry_point());
+        }
+
+        let max_batch_size = device_batch_limit(&resources.limits);
+        let max_workgroup_size = resources
+            .limits
+            .max_compute_workgroup_size_x
+            .min(resources.limits.max_compute_invocations_per_workgroup);
+
+        let batch_size = match options.batch_size {
+            Some(n) if n > max_batch_size => {
+                return Err(CrackerError::InvalidOption(format!(
+                    "batch size {n} is larger than this device allows ({max_batch_size})"
+                )));
+            }
+            Some(n) => n,
+            None => BATCH_SIZE.min(max_batch_size),
+        };
+
+        // One complete buffer set per batch in flight
+        let buffer_sets = BufferSet::ring(
+            &resources.device,
+            &resources.bind_group_layout,
+            &resources.target_buffer,
+            depth,
+            batch_size,
+            resources.supports_timestamps,
+        );
+
+        // Pre-allocate CPU-side buffers with capacity for max batch
+        let batch = PackedBatch {
+            bytes: Vec::with_capacity(batch_size * MESSAGE_BYTES_PER_CANDIDATE),
+            offsets: Vec::with_capacity(batch_size + 1),
+            skipped: Vec::new(),
+        };
+
+        let mut cracker = Self {
+            device: resources.device,
+            queue: resources.queue,
+            shader_module: resources.shader_module,
+            custom_shader: options.shader,
+            custom_module: resources.custom_module,
+            pipeline_layout: resources.pipeline_layout,
+            pipelines: HashMap::new(),
+            bind_group_layout: resources.bind_group_layout,
+            supports_timestamps: resources.supports_timestamps,
+            adapter_info: resources.adapter_info,
+            device_lost: resources.lost,
+            recoveries: 0,
+            gpu_error: resources.error,
+            dispatch_target: options.dispatch_target,
+            dispatch_costs: HashMap::new(),
+            #[cfg(feature = "test-hooks")]
+            lose_device_after: None,
+            max_batch_size,
+            max_workgroup_size,
+            batch_size,
+            tuning: HashMap::new(),
+            buffer_sets,
+            batch_capacity: batch_size,
+            target_buffer: resources.target_buffer,
+            batch,
+            events: options.on_event,
+            scan_checked: 0,
+            scan_total: None,
+            scan_started: Instant::now(),
+            scan_history: VecDeque::with_capacity(RATE_WINDOW + 1),
+            scan_gpu_rate: None,
+            cancel: options.cancel,
+            deadline: options.deadline,
+            scan_outcome: ScanOutcome::Exhausted,
+            poller: None,
+            profiler: options.profile.then(Profiler::default),
+        };
+        cracker.adapter_selected();
+
+        // Create the MD5 pipeline up front; other kernels are built on
+        // demand. A custom kernel is validated against the layout here,
+        // where a mismatch can still be reported as such.
+        if cracker.custom_shader.is_some() {
+            cracker
+                .device
+                .push_error_scope(wgpu::ErrorFilter::Validation);
+        }
+        cracker.ensure_pipeline(Kernel::Md5);
+        if cracker.custom_shader.is_some() {
+            if let Some(error) = cracker.device.pop_error_scope().await {
+                return Err(CrackerError::InvalidShader(error.to_string()));
+            }
+        }
+
+        if options.autotune || options.tune_cache.is_some() {
+            match &options.tune_cache {
+                Some(path) => {
+                    cracker.autotune_cached(path)?;
+                }
+                None => {
+                    cracker.autotune()?;
+                }
+            }
+        }
+
+        Ok(cracker)
+    }
+
+    /// Log and report the adapter the device was opened on
+    fn adapter_selected(&self) {
+        let info = &self.adapter_info;
+        log::info!(
+            "Using GPU: {} ({:?}, {})",
+            info.name,
+            info.backend,
+            info.driver
+        );
+        self.emit(|| CrackerEvent::AdapterSelected {
+            name: info.name.clone(),
+            backend: info.backend,
+            driver: info.driver.clone(),
+        });
+    }
+
+    /// Hand an event to the handler, if there is one
+    fn emit(&self, event: impl FnOnce() -> CrackerEvent) {
+        events::emit(&self.events, event);
+    }
+
+    /// Open the adapter's device with everything that does not depend on
+    /// the batch size: shaders, layouts and target buffer
+    async fn open_device(shader: Option<&CustomShader>) -> Result<DeviceResources, CrackerError> {
         // Create wgpu instance with Vulkan backend (for AMD GPU support)
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::VULKAN,
@@ -172,13 +1045,20 @@ impl GpuCracker {
                 compatible_surface: None,
                 force_fallback_adapter: false,
             })
-            .await?;
-
-        println!("Using GPU: {}", adapter.get_info().name);
+            .await
+            .map_err(CrackerError::NoAdapter)?;
 
         // Check if timestamp queries are supported
         let supports_timestamps = adapter.features().contains(wgpu::Features::TIMESTAMP_QUERY);
 
+        // Ask for the adapter's full buffer sizes, which decide how large a
+        // batch can get
+        let adapter_limits = adapter.limits();
+        let required_limits = wgpu::Limits {
+            max_storage_buffer_binding_size: adapter_limits.max_storage_buffer_binding_size,
+            max_buffer_size: adapter_limits.max_buffer_size,
+            ..wgpu::Limits::default()
+        };
         // Request device and queue with timestamp support if available
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
@@ -188,16 +1068,15 @@ impl GpuCracker {
                 } else {
                     wgpu::Features::empty()
                 },
-                required_limits: wgpu::Limits::default(),
+                required_limits: required_limits.clone(),
                 memory_hints: wgpu::MemoryHints::default(),
                 trace: wgpu::Trace::Off,
                 experimental_features: Default::default(),
             })
-            .await?;
+            .await
+            .map_err(CrackerError::DeviceRequest)?;
 
         // Load the compiled shader
-        let shader_path = env!("shader.spv");
-        println!("Loading shader from: {shader_path}");
         let shader_bytes = include_bytes!(env!("shader.spv"));
         // Convert to u32 array for SPIR-V
         let mut shader_u32 = Vec::with_capacity(shader_bytes.len() / 4);
@@ -208,6 +1087,20 @@ impl GpuCracker {
             label: Some("MD5 Shader"),
             source: wgpu::ShaderSource::SpirV(Cow::Owned(shader_u32)),
         });
+        let custom_module = match shader {
+            Some(shader) => {
+                device.push_error_scope(wgpu::ErrorFilter::Validation);
+                let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("Custom Shader"),
+                    source: wgpu::ShaderSource::SpirV(Cow::Borrowed(shader.words())),
+                });
+                if let Some(error) = device.pop_error_scope().await {
+                    return Err(CrackerError::InvalidShader(error.to_string()));
+                }
+                Some(module)
+            }
+            None => None,
+        };
 
         // Create bind group layout
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -257,7 +1150,7 @@ impl GpuCracker {
                     },
                     count: None,
                 },
-                // block_offsets
+                // offsets
                 wgpu::BindGroupLayoutEntry {
                     binding: 4,
                     visibility: wgpu::ShaderStages::COMPUTE,
@@ -268,6 +1161,17 @@ impl GpuCracker {
                     },
                     count: None,
                 },
+                // state (iterated kernels only)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -278,327 +1182,1211 @@ impl GpuCracker {
             push_constant_ranges: &[],
         });
 
-        // Create compute pipeline
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("MD5 Pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader_module,
-            entry_point: Some("md5_crack"),
-            compilation_options: Default::default(),
-            cache: None,
+        // A lost device (a driver reset, say) fails every call made on it
+        // until it is recreated, so errors only count while it is alive.
+        // They come in on whichever call hit them; the first is kept to be
+        // returned from the next wait on the GPU.
+        let lost = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&lost);
+        device.set_device_lost_callback(move |_reason, _message| {
+            flag.store(true, Ordering::Release);
         });
+        let error = Arc::new(Mutex::new(None));
+        let (flag, first_error) = (Arc::clone(&lost), Arc::clone(&error));
+        device.on_uncaptured_error(Arc::new(move |e| {
+            if !flag.load(Ordering::Acquire) {
+                first_error.lock().unwrap().get_or_insert(e.to_string());
+            }
+        }));
 
-        // Create shared buffers (don't need double-buffering)
+        // Create shared buffers (one per cracker, not per batch)
         let target_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Target Buffer"),
-            size: 16, // 4 u32s = 16 bytes
+            size: TARGET_BUFFER_SIZE,
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        // Create two complete buffer sets for double-buffering
-        let buffer_set_a = BufferSet::new(&device, &bind_group_layout, &target_buffer, "Set A");
-        let buffer_set_b = BufferSet::new(&device, &bind_group_layout, &target_buffer, "Set B");
-
-        // Pre-allocate CPU-side buffers with capacity for max batch
-        let batch_blocks = Vec::with_capacity(BATCH_SIZE * 5 * 16);
-
-        Ok(Self {
+        Ok(DeviceResources {
+            adapter_info: adapter.get_info(),
+            limits: required_limits,
+            supports_timestamps,
             device,
             queue,
-            pipeline,
+            shader_module,
+            custom_module,
             bind_group_layout,
-            supports_timestamps,
-            buffer_set_a,
-            buffer_set_b,
+            pipeline_layout,
             target_buffer,
-            batch_blocks,
-            block_offsets: Vec::with_capacity(BATCH_SIZE + 1),
+            lost,
+            error,
         })
     }
 
-    fn build_cpu_buffers(&mut self, messages: &[&str]) {
-        self.batch_blocks.clear();
-        self.block_offsets.clear();
-        self.block_offsets.push(0);
-        self.block_offsets.reserve(messages.len());
+    /// Number of batches kept in flight
+    pub fn pipeline_depth(&self) -> usize {
+        self.buffer_sets.len()
+    }
+
+    /// Candidates per batch for hash modes autotuning has not picked a
+    /// size for
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
 
-        self.batch_blocks.reserve(messages.len() * 5 * 16);
+    /// Largest batch size this device allows
+    pub fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
 
-        let mut total_blocks = 0u32;
-        for msg in messages {
-            let blocks_added = append_md5_blocks_for(msg.as_bytes(), &mut self.batch_blocks);
-            total_blocks += blocks_added;
-            self.block_offsets.push(total_blocks);
+    /// Use `batch_size` candidates per batch for every hash mode,
+    /// replacing any autotuned sizes
+    ///
+    /// The buffer sets are reallocated to fit the new size.
+    pub fn set_batch_size(&mut self, batch_size: usize) -> Result<(), CrackerError> {
+        if batch_size == 0 {
+            return Err(CrackerError::InvalidOption(
+                "batch size must be at least 1".into(),
+            ));
+        }
+        if batch_size > self.max_batch_size {
+            return Err(CrackerError::InvalidOption(format!(
+                "batch size {batch_size} is larger than this device allows ({})",
+                self.max_batch_size
+            )));
         }
 
-        debug_assert_eq!(self.block_offsets.len(), messages.len() + 1);
+        self.batch_size = batch_size;
+        self.tuning.clear();
+        self.resize_buffer_sets(batch_size);
+        Ok(())
     }
 
-    fn upload_batch_to_gpu(
-        &self,
-        buffer_set: &BufferSet,
-        target_hash: &[u8; 16],
-        message_count: usize,
-    ) {
-        if !self.batch_blocks.is_empty() {
-            let messages_bytes = bytemuck::cast_slice(&self.batch_blocks);
-            self.queue
-                .write_buffer(&buffer_set.messages_buffer, 0, messages_bytes);
+    /// GPU time each dispatch is kept under
+    pub fn dispatch_target(&self) -> Duration {
+        self.dispatch_target
+    }
+
+    /// Keep each dispatch under `target` of GPU time from the next batch on
+    ///
+    /// Batches that would take longer are split over several dispatches,
+    /// so a single one never runs into the driver's watchdog (about two
+    /// seconds on Windows, and on Linux desktops driving a display).
+    pub fn set_dispatch_target(&mut self, target: Duration) -> Result<(), CrackerError> {
+        if target.is_zero() {
+            return Err(CrackerError::InvalidOption(
+                "dispatch target must be more than zero".into(),
+            ));
         }
+        self.dispatch_target = target;
+        Ok(())
+    }
 
-        self.queue.write_buffer(&self.target_buffer, 0, target_hash);
-        self.queue
-            .write_buffer(&buffer_set.result_buffer, 0, &(-1i32).to_le_bytes());
+    /// Send every [`CrackerEvent`] to `handler` from now on, or stop
+    /// reporting them with `None`
+    pub fn set_event_handler(&mut self, handler: Option<EventHandler>) {
+        self.events = handler;
+    }
 
-        let mut message_count_bytes = [0u8; 16];
-        message_count_bytes[..4].copy_from_slice(&(message_count as u32).to_le_bytes());
-        self.queue
-            .write_buffer(&buffer_set.message_count_buffer, 0, &message_count_bytes);
+    /// Stop scans once `token` is cancelled, or never with `None`
+    ///
+    /// A scan seeing the token cancelled submits no more batches but reads
+    /// back those in flight, then returns what it found so far, with a
+    /// [`ScanOutcome::Cancelled`] outcome.
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.cancel = token;
+    }
 
-        self.queue.write_buffer(
-            &buffer_set.block_offsets_buffer,
-            0,
-            bytemuck::cast_slice(&self.block_offsets),
+    /// Stop scans the same way once `deadline` passes, or never with
+    /// `None`
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Time every dispatch of the pipeline and add up where each batch's
+    /// time goes, starting from empty totals, or stop with `false`
+    ///
+    /// Without profiling, a batch is timed as a whole for the progress
+    /// reports. See [`profiler`] for what is measured.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiler = enabled.then(Profiler::default);
+    }
+
+    /// Per-kernel totals of the batches read back since profiling was
+    /// turned on, or `None` while it is off
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profiler.as_ref().map(Profiler::report)
+    }
+
+    /// Why the scan should stop before its next batch, if it should
+    fn stop_requested(&self) -> Option<ScanOutcome> {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            Some(ScanOutcome::Cancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(ScanOutcome::TimedOut)
+        } else {
+            None
+        }
+    }
+
+    /// Lose the device on purpose after `submissions` more dispatches, as
+    /// a driver reset would, to exercise the recovery
+    #[cfg(feature = "test-hooks")]
+    pub fn lose_device_after(&mut self, submissions: usize) {
+        self.lose_device_after = Some(submissions);
+    }
+
+    /// Get ready to start a scan: recreate the device if it was lost since
+    /// the last one, and forget errors earlier scans left behind
+    ///
+    /// A scan that never finished (an async one whose future or stream was
+    /// dropped) leaves readbacks mapped or pending, which are released
+    /// first so their buffer sets can be used again.
+    fn ensure_device(&mut self) -> Result<(), CrackerError> {
+        for slot in 0..self.buffer_sets.len() {
+            if self.buffer_sets[slot].unread.load(Ordering::Acquire) {
+                self.discard_readback(slot);
+            }
+        }
+        self.recoveries = 0;
+        self.gpu_error.lock().unwrap().take();
+        if self.device_lost.load(Ordering::Acquire) {
+            self.recover_device()?;
+        }
+        Ok(())
+    }
+
+    /// Get ready to start a scan of `total` candidates (if known) with
+    /// `kernel`, timing its dispatches anew
+    fn begin_scan(&mut self, kernel: Kernel, total: Option<usize>) -> Result<(), CrackerError> {
+        self.ensure_device()?;
+        self.dispatch_costs.clear();
+        self.ensure_pipeline(kernel);
+        self.scan_checked = 0;
+        self.scan_total = total;
+        self.scan_started = Instant::now();
+        self.scan_history.clear();
+        self.scan_history.push_back((self.scan_started, 0));
+        self.scan_gpu_rate = None;
+        self.scan_outcome = ScanOutcome::Exhausted;
+        Ok(())
+    }
+
+    /// Count the batch in `slot` as checked, after `readback` spent reading
+    /// it back, and report the progress
+    ///
+    /// The host rate is taken over the last [`RATE_WINDOW`] deliveries, so
+    /// it follows the current speed rather than the average since the
+    /// start.
+    fn batch_done(&mut self, slot: usize, readback: Duration) {
+        let passes = self.read_batch_timer(slot);
+        let buffer_set = &self.buffer_sets[slot];
+        let candidates = buffer_set.candidates;
+        if let Some(passes) = &passes {
+            let ns: u64 = passes.iter().sum();
+            // While profiling, dispatches past the timed ones would be
+            // missing from the batch's GPU time
+            let whole_batch = self.profiler.is_none() || passes.len() == buffer_set.dispatches;
+            if ns > 0 && whole_batch {
+                self.scan_gpu_rate = Some(candidates as f64 * 1e9 / ns as f64);
+            }
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(
+                buffer_set.kernel.entry_point(),
+                BatchTiming {
+                    candidates,
+                    dispatches: buffer_set.dispatches,
+                    upload: buffer_set.upload,
+                    kernel: passes
+                        .map(|passes| (Duration::from_nanos(passes.iter().sum()), passes.len())),
+                    readback,
+                },
+            );
+        }
+        self.scan_checked += candidates;
+
+        let now = Instant::now();
+        self.scan_history.push_back((now, self.scan_checked));
+        if self.scan_history.len() > RATE_WINDOW + 1 {
+            self.scan_history.pop_front();
+        }
+        let (since, checked_then) = self.scan_history[0];
+        let seconds = now.duration_since(since).as_secs_f64();
+        let hashes_per_sec = if seconds > 0.0 {
+            (self.scan_checked - checked_then) as f64 / seconds
+        } else {
+            0.0
+        };
+
+        let progress = Progress {
+            checked: self
+                .scan_total
+                .map_or(self.scan_checked, |total| self.scan_checked.min(total)),
+            total: self.scan_total,
+            elapsed: now.duration_since(self.scan_started),
+            hashes_per_sec,
+            gpu_hashes_per_sec: self.scan_gpu_rate,
+        };
+        self.emit(|| CrackerEvent::Progress(progress));
+    }
+
+    /// Replace a lost device with a new one, along with everything created
+    /// on it
+    ///
+    /// Whatever was in flight is gone and has to be submitted again. The
+    /// dispatches that ran up to the loss may have been what tripped the
+    /// watchdog, so the measured costs are doubled to make the next ones
+    /// smaller. Gives up with [`CrackerError::DeviceLost`] once a scan has
+    /// lost the device too often.
+    fn recover_device(&mut self) -> Result<(), CrackerError> {
+        self.recoveries += 1;
+        if self.recoveries > MAX_DEVICE_RECOVERIES {
+            return Err(CrackerError::DeviceLost);
+        }
+        log::warn!(
+            "GPU device lost, recreating it (attempt {} of {MAX_DEVICE_RECOVERIES})",
+            self.recoveries
+        );
+        let recoveries = self.recoveries;
+        self.emit(|| CrackerEvent::DeviceLost { recoveries });
+
+        let resources = pollster::block_on(Self::open_device(self.custom_shader.as_ref()))?;
+        self.adapter_info = resources.adapter_info;
+        self.device = resources.device;
+        self.queue = resources.queue;
+        self.shader_module = resources.shader_module;
+        self.custom_module = resources.custom_module;
+        self.pipeline_layout = resources.pipeline_layout;
+        self.bind_group_layout = resources.bind_group_layout;
+        self.target_buffer = resources.target_buffer;
+        self.device_lost = resources.lost;
+        self.gpu_error = resources.error;
+        self.pipelines.clear();
+        self.poller = None;
+
+        self.buffer_sets = BufferSet::ring(
+            &self.device,
+            &self.bind_group_layout,
+            &self.target_buffer,
+            self.buffer_sets.len(),
+            self.batch_capacity,
+            self.supports_timestamps,
         );
+        for cost in self.dispatch_costs.values_mut() {
+            *cost *= 2.0;
+        }
+        self.adapter_selected();
+        Ok(())
     }
 
-    /// Process a batch of messages and check against target hash
-    pub fn process_batch(&mut self, messages: &[&str], target_hash: &[u8; 16]) -> Option<usize> {
-        self.build_cpu_buffers(messages);
+    /// Candidates per batch for `kernel`
+    fn batch_size_for(&self, kernel: Kernel) -> usize {
+        self.tuning
+            .get(&kernel)
+            .map_or(self.batch_size, |tuning| tuning.batch_size)
+    }
 
-        // Use buffer_set_a for now (will implement pipelining later)
-        let buffer_set = &self.buffer_set_a;
+    /// Invocations per workgroup `kernel` runs with
+    fn workgroup_size(&self, kernel: Kernel) -> u32 {
+        self.tuning
+            .get(&kernel)
+            .map_or(DEFAULT_WORKGROUP_SIZE, |tuning| tuning.workgroup_size)
+    }
 
-        self.upload_batch_to_gpu(buffer_set, target_hash, messages.len());
+    /// Bytes of packed candidates one batch can upload
+    fn messages_buffer_size(&self) -> usize {
+        self.batch_capacity * MESSAGE_BYTES_PER_CANDIDATE
+    }
 
-        // Create command encoder and dispatch compute shader
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("MD5 Command Encoder"),
-            });
+    /// Reallocate every buffer set with room for `capacity` candidates
+    fn resize_buffer_sets(&mut self, capacity: usize) {
+        if capacity == self.batch_capacity {
+            return;
+        }
 
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("MD5 Crack Pass"),
-                timestamp_writes: None,
-            });
-            compute_pass.set_pipeline(&self.pipeline);
-            compute_pass.set_bind_group(0, &buffer_set.bind_group, &[]);
+        self.buffer_sets = BufferSet::ring(
+            &self.device,
+            &self.bind_group_layout,
+            &self.target_buffer,
+            self.buffer_sets.len(),
+            capacity,
+            self.supports_timestamps,
+        );
+        self.batch_capacity = capacity;
+    }
 
-            // Dispatch with workgroups based on actual batch size (each workgroup has 64 threads)
-            let num_workgroups = (messages.len() as u32).div_ceil(64);
-            compute_pass.dispatch_workgroups(num_workgroups, 1, 1);
+    /// Entry point of the custom shader running `kernel` at
+    /// `workgroup_size`, if it has one; its MD5 kernel goes by the name it
+    /// was loaded with
+    fn custom_entry_point(&self, kernel: Kernel, workgroup_size: u32) -> Option<String> {
+        let shader = self.custom_shader.as_ref()?;
+        let name = match kernel {
+            Kernel::Md5 => shader.entry_point(),
+            _ => kernel.entry_point(),
+        };
+        let name = sized_entry_point(name, workgroup_size);
+        shader
+            .exports(&name, workgroup_size)
+            .then(|| name.into_owned())
+    }
+
+    /// Workgroup sizes `kernel` can run at: a custom MD5 kernel only has
+    /// the ones its module exports
+    fn kernel_workgroup_sizes(&self, kernel: Kernel) -> Vec<u32> {
+        let custom = kernel == Kernel::Md5 && self.custom_shader.is_some();
+        kernel
+            .workgroup_sizes()
+            .iter()
+            .copied()
+            .filter(|&size| !custom || self.custom_entry_point(kernel, size).is_some())
+            .collect()
+    }
+
+    fn create_pipeline(&self, kernel: Kernel, workgroup_size: u32) -> wgpu::ComputePipeline {
+        let (module, entry_point) = match (
+            &self.custom_module,
+            self.custom_entry_point(kernel, workgroup_size),
+        ) {
+            (Some(module), Some(entry_point)) => (module, Cow::Owned(entry_point)),
+            _ => (&self.shader_module, kernel.entry_point_for(workgroup_size)),
+        };
+        self.device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&entry_point),
+                layout: Some(&self.pipeline_layout),
+                module,
+                entry_point: Some(&entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+    }
+
+    /// Make sure the pipeline for `kernel` at its workgroup size has been
+    /// created
+    fn ensure_pipeline(&mut self, kernel: Kernel) {
+        let key = (kernel, self.workgroup_size(kernel));
+        if !self.pipelines.contains_key(&key) {
+            let pipeline = self.create_pipeline(kernel, key.1);
+            self.pipelines.insert(key, pipeline);
         }
+    }
 
-        // Copy result to staging buffer
-        encoder.copy_buffer_to_buffer(
+    /// Pipeline of `kernel` at its workgroup size, which
+    /// [`ensure_pipeline`](Self::ensure_pipeline) must have created
+    fn pipeline(&self, kernel: Kernel) -> &wgpu::ComputePipeline {
+        &self.pipelines[&(kernel, self.workgroup_size(kernel))]
+    }
+
+    fn upload_batch_to_gpu(&self, slot: usize, kernel: Kernel, batch: &PackedBatch, target: &[u8]) {
+        let buffer_set = &self.buffer_sets[slot];
+        self.queue
+            .write_buffer(&buffer_set.messages_buffer, 0, &batch.bytes);
+
+        if !target.is_empty() {
+            self.queue.write_buffer(&self.target_buffer, 0, target);
+        }
+        self.queue.write_buffer(
             &buffer_set.result_buffer,
             0,
-            &buffer_set.staging_buffer,
-            0,
-            4,
+            bytemuck::cast_slice(kernel.result_init()),
         );
 
-        // Submit commands
-        self.queue.submit(Some(encoder.finish()));
+        self.queue.write_buffer(
+            &buffer_set.offsets_buffer,
+            0,
+            bytemuck::cast_slice(&batch.offsets),
+        );
+    }
 
-        // Read result
-        let buffer_slice = buffer_set.staging_buffer.slice(..);
-        let (sender, receiver) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender.send(result).unwrap();
-        });
+    /// Process a batch of messages and check against target hash
+    pub fn process_batch(
+        &mut self,
+        messages: &[&str],
+        target_hash: &[u8; 16],
+    ) -> Result<Option<usize>, CrackerError> {
+        Ok(self.process_batch_with_timing(messages, target_hash)?.0)
+    }
 
-        self.device
-            .poll(wgpu::PollType::wait_indefinitely())
-            .expect("Failed to poll device");
-        receiver.recv().unwrap().unwrap();
+    /// Like [`process_batch`](Self::process_batch), also returning the GPU
+    /// time of the batch's dispatches in nanoseconds when the device
+    /// supports timestamp queries (for benchmarking)
+    pub fn process_batch_with_timing(
+        &mut self,
+        messages: &[&str],
+        target_hash: &[u8; 16],
+    ) -> Result<(Option<usize>, Option<u64>), CrackerError> {
+        self.check_batch(messages)?;
+        let params = PartialMd5Target::full(*target_hash).params();
+        self.ensure_device()?;
+
+        let mut batch = std::mem::take(&mut self.batch);
+        batch.pack(messages, Encoding::Utf8, &[]);
+        let result = self.run_batch(bytemuck::cast_slice(&params), &batch);
+        self.batch = batch;
+        result
+    }
 
-        let data = buffer_slice.get_mapped_range();
-        let result: i32 = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        drop(data);
-        buffer_set.staging_buffer.unmap();
+    /// Run `md5_crack` over a single packed batch and wait for it,
+    /// returning the first hit and the GPU time of its dispatches
+    fn run_batch(
+        &mut self,
+        target: &[u8],
+        batch: &PackedBatch,
+    ) -> Result<(Option<usize>, Option<u64>), CrackerError> {
+        // A single batch waited on right away, so the first set will do
+        loop {
+            self.submit_packed(Kernel::Md5, target, 0, batch, 0);
+            match self.read_result(0) {
+                Ok(slots) => {
+                    let gpu_time_ns = self.read_batch_timer(0).map(|passes| passes.iter().sum());
+                    return Ok(((slots[0] >= 0).then_some(slots[0] as usize), gpu_time_ns));
+                }
+                Err(CrackerError::DeviceLost) => self.recover_device()?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
 
-        if result >= 0 {
-            Some(result as usize)
-        } else {
-            None
+    /// Check that `messages` fit in a single batch of MD5 candidates
+    fn check_batch(&self, messages: &[&str]) -> Result<(), CrackerError> {
+        if messages.len() > self.batch_capacity {
+            return Err(CrackerError::InvalidOption(format!(
+                "{} candidates do not fit in a batch of {}",
+                messages.len(),
+                self.batch_capacity
+            )));
         }
+        check_candidates(messages, Encoding::Utf8, &[])
     }
 
     /// Crack a hash using a wordlist with pipelined execution
     /// Overlaps CPU preparation of batch N+1 with GPU execution of batch N
-    pub fn crack(&mut self, target_hash: &[u8; 16], wordlist: &[&str]) -> Option<String> {
-        let chunks: Vec<&[&str]> = wordlist.chunks(BATCH_SIZE).collect();
+    pub fn crack(
+        &mut self,
+        target_hash: &[u8; 16],
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let params = PartialMd5Target::full(*target_hash).params();
+        self.crack_with(Kernel::Md5, bytemuck::cast_slice(&params), &[], wordlist)
+    }
+
+    /// Run `kernel` over the wordlist with the given target parameters,
+    /// appending `suffix` to every candidate before hashing
+    fn crack_with(
+        &mut self,
+        kernel: Kernel,
+        target: &[u8],
+        suffix: &[u8],
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let mut found = None;
+        let events = self.events.clone();
+        let outcome = self.scan_with(kernel, target, suffix, wordlist, |batch, slots| {
+            if slots[0] >= 0 {
+                let candidate = batch[slots[0] as usize];
+                events::emit(&events, || CrackerEvent::HitFound {
+                    candidate: candidate.to_string(),
+                });
+                found = Some(candidate.to_string());
+                true
+            } else {
+                false
+            }
+        })?;
+        Ok(ScanResult { found, outcome })
+    }
+
+    /// Run `kernel` over the wordlist with pipelined batches, handing every
+    /// batch and its result slots to `on_result` until it returns `true`,
+    /// then saying why the scan ended
+    ///
+    /// Candidates longer than [`MAX_MSG_SIZE`] are left out of their batch
+    /// with a warning, and of the batch `on_result` gets. Once cancelled or
+    /// past the deadline, no further batch is submitted and those in
+    /// flight are still delivered.
+    fn scan_with(
+        &mut self,
+        kernel: Kernel,
+        target: &[u8],
+        suffix: &[u8],
+        wordlist: &[&str],
+        on_result: impl FnMut(&[&str], &[i32]) -> bool,
+    ) -> Result<ScanOutcome, CrackerError> {
+        self.scan_iterated(kernel, target, suffix, 0, wordlist, on_result)
+    }
+
+    /// Like [`scan_with`](Self::scan_with), but for a kernel that runs
+    /// `iterations` rounds per candidate, split into windows of
+    /// [`ITERATIONS_PER_DISPATCH`]; 0 means a single plain dispatch
+    fn scan_iterated(
+        &mut self,
+        kernel: Kernel,
+        target: &[u8],
+        suffix: &[u8],
+        iterations: u32,
+        wordlist: &[&str],
+        mut on_result: impl FnMut(&[&str], &[i32]) -> bool,
+    ) -> Result<ScanOutcome, CrackerError> {
+        if target.len() as u64 > TARGET_BUFFER_SIZE {
+            return Err(CrackerError::InvalidTarget(format!(
+                "{} bytes of parameters, more than the {TARGET_BUFFER_SIZE} the kernels read",
+                target.len()
+            )));
+        }
+        let chunks: Vec<&[&str]> = wordlist.chunks(self.batch_size_for(kernel)).collect();
         if chunks.is_empty() {
-            return None;
+            return Ok(ScanOutcome::Exhausted);
         }
 
-        // Process first batch (no overlap yet) - use buffer set A
-        self.prepare_and_submit_batch(false, chunks[0], target_hash);
+        self.begin_scan(kernel, Some(wordlist.len()))?;
 
-        // Pipeline: overlap CPU prep of batch N+1 with GPU execution of batch N
-        for i in 1..chunks.len() {
-            // Alternate between buffer sets (false = A, true = B)
-            let use_set_b = i % 2 == 1;
+        let depth = self.buffer_sets.len();
+        let encoding = kernel.encoding();
+        let chunks = &chunks;
+        let submit = |cracker: &mut Self, slot: usize, (_, batch): &(usize, PackedBatch)| {
+            cracker.submit_packed(kernel, target, slot, batch, iterations);
+        };
+        std::thread::scope(|scope| {
+            // Batches are packed on a producer thread, at most a ring's
+            // worth ahead, and handed back for reuse once their results are
+            // in (until then they may have to be submitted again)
+            let (batch_tx, batch_rx) = mpsc::sync_channel::<PackedBatch>(depth);
+            let (spare_tx, spare_rx) = mpsc::channel::<PackedBatch>();
+            scope.spawn(move || {
+                for chunk in chunks {
+                    let mut batch = spare_rx.try_recv().unwrap_or_default();
+                    batch.pack(chunk, encoding, suffix);
+                    if batch_tx.send(batch).is_err() {
+                        // The scan stopped early
+                        return;
+                    }
+                }
+            });
 
-            // While GPU processes current batch, prepare next batch on CPU
-            self.prepare_batch(use_set_b, chunks[i], target_hash);
+            let mut deliver = |(chunk, batch): (usize, PackedBatch), slots: &[i32]| {
+                let stop = on_result(&batch.messages(chunks[chunk]), slots);
+                let _ = spare_tx.send(batch);
+                stop
+            };
+            let mut in_flight: VecDeque<(usize, (usize, PackedBatch))> =
+                VecDeque::with_capacity(depth);
+            for (i, batch) in batch_rx.iter().enumerate() {
+                if let Some(outcome) = self.stop_requested() {
+                    self.scan_outcome = outcome;
+                    break;
+                }
+
+                // The oldest batch has to finish before its buffer set can
+                // take this one
+                if in_flight.len() == depth
+                    && self.deliver_oldest(&mut in_flight, &mut deliver, &submit)?
+                {
+                    return Ok(());
+                }
+
+                let slot = i % depth;
+                let batch = (i, batch);
+                submit(self, slot, &batch);
+                in_flight.push_back((slot, batch));
+
+                // Hand over whatever has already finished without waiting
+                while in_flight
+                    .front()
+                    .is_some_and(|&(slot, _)| self.readback_ready(slot))
+                {
+                    if self.deliver_oldest(&mut in_flight, &mut deliver, &submit)? {
+                        return Ok(());
+                    }
+                }
+            }
 
-            // Wait for previous batch to complete and check result
-            let prev_use_set_b = (i - 1) % 2 == 1;
-            if let Some(idx) = self.read_result(prev_use_set_b) {
-                return Some(chunks[i - 1][idx].to_string());
+            while !in_flight.is_empty() {
+                if self.deliver_oldest(&mut in_flight, &mut deliver, &submit)? {
+                    return Ok(());
+                }
             }
+            Ok(())
+        })?;
+        Ok(self.scan_outcome)
+    }
 
-            // Submit next batch to GPU (non-blocking)
-            self.submit_batch(use_set_b, chunks[i].len());
-        }
+    /// Run a kernel that generates its own candidates, `total` of them if
+    /// known
+    ///
+    /// `next_batch` returns the target parameters and candidate count of
+    /// each dispatch until it returns `None`; every batch's parameters come
+    /// back to `on_result` with its result slots, until it returns `true`,
+    /// and the scan then says why it ended.
+    /// Like [`scan_with`](Self::scan_with), batches are queued on every
+    /// buffer set of the ring while earlier ones are checked.
+    fn generate_with(
+        &mut self,
+        kernel: Kernel,
+        total: Option<usize>,
+        mut next_batch: impl FnMut() -> Option<(Vec<u32>, usize)>,
+        mut on_result: impl FnMut(&[u32], &[i32]) -> bool,
+    ) -> Result<ScanOutcome, CrackerError> {
+        self.begin_scan(kernel, total)?;
+
+        let depth = self.buffer_sets.len();
+        let submit = |cracker: &mut Self, slot: usize, (params, count): &(Vec<u32>, usize)| {
+            debug_assert!(params.len() as u64 * 4 <= TARGET_BUFFER_SIZE);
+            let started = Instant::now();
+            cracker
+                .queue
+                .write_buffer(&cracker.target_buffer, 0, bytemuck::cast_slice(params));
+            cracker.queue.write_buffer(
+                &cracker.buffer_sets[slot].result_buffer,
+                0,
+                bytemuck::cast_slice(kernel.result_init()),
+            );
+            cracker.buffer_sets[slot].upload = started.elapsed();
+            cracker.submit_batch(kernel, slot, *count, 0);
+        };
+        let mut deliver = |(params, _): (Vec<u32>, usize), slots: &[i32]| on_result(&params, slots);
+        let mut in_flight: VecDeque<(usize, (Vec<u32>, usize))> = VecDeque::with_capacity(depth);
+        let mut submitted = 0;
+        while let Some(batch) = next_batch() {
+            if let Some(outcome) = self.stop_requested() {
+                self.scan_outcome = outcome;
+                break;
+            }
+            if in_flight.len() == depth
+                && self.deliver_oldest(&mut in_flight, &mut deliver, &submit)?
+            {
+                return Ok(self.scan_outcome);
+            }
 
-        // Process last batch result
-        let last_use_set_b = (chunks.len() - 1) % 2 == 1;
-        if let Some(idx) = self.read_result(last_use_set_b) {
-            return Some(chunks[chunks.len() - 1][idx].to_string());
+            let slot = submitted % depth;
+            submitted += 1;
+            submit(self, slot, &batch);
+            in_flight.push_back((slot, batch));
+
+            while in_flight
+                .front()
+                .is_some_and(|&(slot, _)| self.readback_ready(slot))
+            {
+                if self.deliver_oldest(&mut in_flight, &mut deliver, &submit)? {
+                    return Ok(self.scan_outcome);
+                }
+            }
         }
 
-        None
+        while !in_flight.is_empty() {
+            if self.deliver_oldest(&mut in_flight, &mut deliver, &submit)? {
+                return Ok(self.scan_outcome);
+            }
+        }
+        Ok(self.scan_outcome)
     }
 
-    /// Prepare batch data on CPU (no GPU submission)
-    fn prepare_batch(&mut self, use_set_b: bool, messages: &[&str], target_hash: &[u8; 16]) {
-        self.build_cpu_buffers(messages);
-
-        let buffer_set = if use_set_b {
-            &self.buffer_set_b
-        } else {
-            &self.buffer_set_a
+    /// Read the oldest batch in flight and hand it to `on_result`
+    ///
+    /// This is also how a cancelled scan drains the batches it already
+    /// submitted, so none of their hits are lost.
+    ///
+    /// If the device was lost, it is recreated and every batch in flight is
+    /// submitted again with `resubmit` before reading on. When `on_result`
+    /// asks to stop, the batches still in flight are drained and `true` is
+    /// returned; so are they on any other error.
+    fn deliver_oldest<P>(
+        &mut self,
+        in_flight: &mut VecDeque<(usize, P)>,
+        on_result: &mut impl FnMut(P, &[i32]) -> bool,
+        resubmit: &impl Fn(&mut Self, usize, &P),
+    ) -> Result<bool, CrackerError> {
+        let Some(&(slot, _)) = in_flight.front() else {
+            return Ok(false);
+        };
+        let (slots, readback) = loop {
+            let started = Instant::now();
+            let error = match self.read_result(slot) {
+                Ok(slots) => break (slots, started.elapsed()),
+                Err(CrackerError::DeviceLost) => match self.recover_device() {
+                    Ok(()) => {
+                        for (slot, payload) in in_flight.iter() {
+                            resubmit(self, *slot, payload);
+                        }
+                        continue;
+                    }
+                    Err(e) => e,
+                },
+                Err(e) => e,
+            };
+            // The oldest batch failed, so only the ones after it are left
+            in_flight.pop_front();
+            self.discard_in_flight(in_flight);
+            return Err(error);
         };
-        self.upload_batch_to_gpu(buffer_set, target_hash, messages.len());
+
+        self.batch_done(slot, readback);
+        let (_, payload) = in_flight.pop_front().expect("checked above");
+        if !on_result(payload, &slots) {
+            return Ok(false);
+        }
+
+        self.scan_outcome = ScanOutcome::Finished;
+        self.discard_in_flight(in_flight);
+        Ok(true)
     }
 
-    /// Prepare batch data on CPU and submit to GPU (combined)
-    fn prepare_and_submit_batch(
+    /// Release the readbacks of every batch still in flight
+    fn discard_in_flight<P>(&self, in_flight: &mut VecDeque<(usize, P)>) {
+        for (slot, _) in in_flight.drain(..) {
+            self.discard_readback(slot);
+        }
+    }
+
+    /// Upload a packed batch to the buffer set in `slot` and submit it,
+    /// with `iterations` as for [`scan_iterated`](Self::scan_iterated)
+    fn submit_packed(
         &mut self,
-        use_set_b: bool,
-        messages: &[&str],
-        target_hash: &[u8; 16],
+        kernel: Kernel,
+        target: &[u8],
+        slot: usize,
+        batch: &PackedBatch,
+        iterations: u32,
     ) {
-        self.prepare_batch(use_set_b, messages, target_hash);
-        self.submit_batch(use_set_b, messages.len());
+        let batch_kernel = self.batch_kernel(kernel, target, batch.single_block());
+        let started = Instant::now();
+        self.upload_batch_to_gpu(slot, batch_kernel, batch, target);
+        self.buffer_sets[slot].upload = started.elapsed();
+        self.submit_batch(batch_kernel, slot, batch.len(), iterations);
+    }
+
+    /// Pick the kernel for a batch: `md5_crack` batches whose candidates
+    /// all fit one block go to the unrolled `md5_crack_short`, unless the
+    /// target is only partly known or a custom MD5 kernel comes without
+    /// a short one
+    fn batch_kernel(&mut self, kernel: Kernel, target: &[u8], single_block: bool) -> Kernel {
+        // Bytes 16-31 of an MD5 target are the mask of known bits
+        let full_digest = target.len() >= 32 && target[16..32].iter().all(|&b| b == 0xff);
+        if kernel != Kernel::Md5 || !full_digest || !single_block {
+            return kernel;
+        }
+        let short_size = self.workgroup_size(Kernel::Md5Short);
+        if self.custom_shader.is_some()
+            && self
+                .custom_entry_point(Kernel::Md5Short, short_size)
+                .is_none()
+        {
+            return kernel;
+        }
+
+        self.ensure_pipeline(Kernel::Md5Short);
+        Kernel::Md5Short
     }
 
     /// Submit batch to GPU (non-blocking)
-    fn submit_batch(&mut self, use_set_b: bool, batch_size: usize) {
-        let buffer_set = if use_set_b {
-            &self.buffer_set_b
+    ///
+    /// Each dispatch is kept under the dispatch target: a batch that would
+    /// take longer is split over several dispatches, each preceded by
+    /// writing its first candidate into the dispatch uniform. The first
+    /// dispatch of a scan is a small one, timed to learn what a candidate
+    /// costs. Iterated kernels also get one round of dispatches per window
+    /// of iterations. The readback of the results is requested right away,
+    /// so [`readback_ready`](Self::readback_ready) can tell when they are in.
+    ///
+    /// The batch is timed as a whole, or each dispatch on its own while
+    /// profiling, using the set's timestamp queries.
+    fn submit_batch(&mut self, kernel: Kernel, slot: usize, batch_size: usize, iterations: u32) {
+        self.ensure_pipeline(kernel);
+        // The timestamps of the set's last batch are still mapped if
+        // nobody read them
+        self.read_batch_timer(slot);
+
+        let windows: Vec<(u32, u32)> = if iterations == 0 {
+            vec![(0, 0)]
         } else {
-            &self.buffer_set_a
+            (1..=iterations)
+                .step_by(ITERATIONS_PER_DISPATCH as usize)
+                .map(|first| (first, (first + ITERATIONS_PER_DISPATCH).min(iterations + 1)))
+                .collect()
         };
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("MD5 Command Encoder"),
-            });
+        let profiling = self.profiler.is_some();
+        let mut timed_passes = 0;
+        let mut submission = None;
+        let mut dispatches = 0;
+        for (i, &(first, end)) in windows.iter().enumerate() {
+            // Iterations each candidate runs in this window
+            let steps = (end - first).max(1);
+            let mut base = 0;
+            loop {
+                #[cfg(feature = "test-hooks")]
+                if let Some(remaining) = self.lose_device_after.as_mut() {
+                    if *remaining == 0 {
+                        self.lose_device_after = None;
+                        self.device_lost.store(true, Ordering::Release);
+                        self.device.destroy();
+                    } else {
+                        *remaining -= 1;
+                    }
+                }
+
+                let probe = !self.dispatch_costs.contains_key(&kernel);
+                let count = if probe {
+                    PROBE_CANDIDATES
+                } else {
+                    self.dispatch_size(kernel, steps)
+                }
+                .min(batch_size - base);
+                let last = i + 1 == windows.len() && base + count == batch_size;
+
+                let buffer_set = &self.buffer_sets[slot];
+                let dispatch = [batch_size as u32, first, end, base as u32];
+                self.queue.write_buffer(
+                    &buffer_set.message_count_buffer,
+                    0,
+                    bytemuck::cast_slice(&dispatch),
+                );
+
+                let mut encoder =
+                    self.device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("MD5 Command Encoder"),
+                        });
+
+                // The first pass is the probe's own, then the span of the
+                // whole batch, which starts there too
+                let (pass, start, end) = if profiling {
+                    (dispatches as u32, true, true)
+                } else {
+                    (0, dispatches == 0, probe || last)
+                };
+                let timer = buffer_set.timer.as_ref();
+                let timestamp_writes = timer
+                    .filter(|_| pass < PROFILED_DISPATCHES)
+                    .and_then(|timer| timer.writes(pass, start, end));
+                if timestamp_writes.is_some() {
+                    timed_passes = pass + 1;
+                }
+                {
+                    let mut compute_pass =
+                        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                            label: Some("MD5 Crack Pass"),
+                            timestamp_writes,
+                        });
+                    compute_pass.set_pipeline(self.pipeline(kernel));
+                    compute_pass.set_bind_group(0, &buffer_set.bind_group, &[]);
+
+                    let num_workgroups = (count as u32).div_ceil(self.workgroup_size(kernel));
+                    compute_pass.dispatch_workgroups(num_workgroups, 1, 1);
+                }
+                if let Some(timer) = timer.filter(|_| probe || last) {
+                    timer.resolve(&mut encoder, timed_passes);
+                }
+
+                // Copy result to staging buffer after the last dispatch
+                if last {
+                    encoder.copy_buffer_to_buffer(
+                        &buffer_set.result_buffer,
+                        0,
+                        &buffer_set.staging_buffer,
+                        0,
+                        kernel.result_bytes(),
+                    );
+                    if kernel == Kernel::Md5Digest {
+                        encoder.copy_buffer_to_buffer(
+                            &buffer_set.state_buffer,
+                            0,
+                            &buffer_set.digest_staging_buffer,
+                            0,
+                            (batch_size * 16) as u64,
+                        );
+                    }
+                }
+
+                // Submit commands (non-blocking, except for the probe)
+                let started = Instant::now();
+                let index = self.queue.submit(Some(encoder.finish()));
+                if probe {
+                    // Whatever went wrong shows up again when the batch is
+                    // read back, so there is nothing to do about it here
+                    if let Ok(ns) = self.time_probe(slot, index.clone(), started) {
+                        let cost = ns / (count.max(1) as f64 * steps as f64);
+                        self.dispatch_costs.insert(kernel, cost);
+                        log::debug!(
+                            "{}: {cost:.2} ns per candidate, {} per dispatch",
+                            kernel.entry_point(),
+                            self.dispatch_size(kernel, steps)
+                        );
+                    }
+                }
+                submission = Some(index);
+                dispatches += 1;
+
+                base += count;
+                if base >= batch_size {
+                    break;
+                }
+            }
+        }
 
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("MD5 Crack Pass"),
-                timestamp_writes: None,
-            });
-            compute_pass.set_pipeline(&self.pipeline);
-            compute_pass.set_bind_group(0, &buffer_set.bind_group, &[]);
+        self.buffer_sets[slot].kernel = kernel;
+        self.request_readback(slot, kernel == Kernel::Md5Digest, submission);
+        self.request_timer_readback(slot, timed_passes);
+        let buffer_set = &mut self.buffer_sets[slot];
+        buffer_set.candidates = batch_size;
+        buffer_set.dispatches = dispatches;
+        self.emit(|| CrackerEvent::BatchSubmitted {
+            kernel: kernel.entry_point(),
+            candidates: batch_size,
+            dispatches,
+        });
+    }
 
-            let num_workgroups = (batch_size as u32).div_ceil(64);
-            compute_pass.dispatch_workgroups(num_workgroups, 1, 1);
-        }
+    /// Candidates one dispatch of `kernel` running `steps` iterations can
+    /// take within the dispatch target, in whole workgroups
+    fn dispatch_size(&self, kernel: Kernel, steps: u32) -> usize {
+        let cost = self.dispatch_costs[&kernel] * steps as f64;
+        let workgroup_size = self.workgroup_size(kernel) as usize;
+        let fits = (self.dispatch_target.as_nanos() as f64 / cost) as usize;
+        (fits / workgroup_size * workgroup_size).max(workgroup_size)
+    }
 
-        // Copy result to staging buffer
-        encoder.copy_buffer_to_buffer(
-            &buffer_set.result_buffer,
-            0,
-            &buffer_set.staging_buffer,
-            0,
-            4,
-        );
+    /// GPU time of the probe dispatch just submitted in `slot` in
+    /// nanoseconds, from the first pass of the set's timestamps
+    ///
+    /// Without timestamp queries this is the wall-clock time until it
+    /// finished, which also counts any batches queued before it and so
+    /// errs on the side of shorter dispatches.
+    fn time_probe(
+        &self,
+        slot: usize,
+        submission: wgpu::SubmissionIndex,
+        started: Instant,
+    ) -> Result<f64, CrackerError> {
+        self.poll(wgpu::PollType::Wait {
+            submission_index: Some(submission),
+            timeout: None,
+        })?;
+        let elapsed = started.elapsed();
+        let Some(timer) = &self.buffer_sets[slot].timer else {
+            return Ok(elapsed.as_nanos() as f64);
+        };
 
-        // Submit commands (non-blocking)
-        self.queue.submit(Some(encoder.finish()));
+        let status = MapStatus::default();
+        status.request(timer.staging_buffer.slice(..));
+        self.wait_for_map(&status, None)?;
+        Ok(timer.durations_ns(1, self.queue.get_timestamp_period())[0] as f64)
     }
 
-    /// Read result from staging buffer (blocks until ready)
-    fn read_result(&self, use_set_b: bool) -> Option<usize> {
-        let buffer_set = if use_set_b {
-            &self.buffer_set_b
+    /// Map the readback of the batch just submitted in `slot`: its result
+    /// slots, or its digests for `md5_digest`
+    fn request_readback(
+        &mut self,
+        slot: usize,
+        reads_digests: bool,
+        submission: Option<wgpu::SubmissionIndex>,
+    ) {
+        let buffer_set = &mut self.buffer_sets[slot];
+        buffer_set.submission = submission;
+        buffer_set.reads_digests = reads_digests;
+
+        let readback = if reads_digests {
+            buffer_set.digest_staging_buffer.slice(..)
         } else {
-            &self.buffer_set_a
+            buffer_set
+                .staging_buffer
+                .slice(..buffer_set.kernel.result_bytes())
         };
+        buffer_set.readback.request(readback);
+        buffer_set.unread.store(true, Ordering::Release);
+    }
 
-        let buffer_slice = buffer_set.staging_buffer.slice(..);
-        let (sender, receiver) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender.send(result).unwrap();
-        });
+    /// Map the timestamps of the first `passes` passes of the batch just
+    /// submitted in `slot`, if the set has a timer
+    fn request_timer_readback(&mut self, slot: usize, passes: u32) {
+        let buffer_set = &mut self.buffer_sets[slot];
+        if let Some(timer) = &buffer_set.timer {
+            buffer_set
+                .timer_readback
+                .request(timer.staging_buffer.slice(..));
+            buffer_set.timed_passes = passes;
+        }
+    }
 
+    /// Poll the device, reporting a lost one, or any error wgpu reported
+    /// since the scan started
+    ///
+    /// The device lost callback flags a lost device, which is not polled
+    /// again after that.
+    fn poll(&self, poll_type: wgpu::PollType) -> Result<(), CrackerError> {
+        self.check_device()?;
         self.device
-            .poll(wgpu::PollType::wait_indefinitely())
-            .expect("Failed to poll device");
-        receiver.recv().unwrap().unwrap();
+            .poll(poll_type)
+            .map_err(|e| CrackerError::Gpu(e.to_string()))?;
+        self.check_device()
+    }
 
-        let data = buffer_slice.get_mapped_range();
-        let result: i32 = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        drop(data);
-        buffer_set.staging_buffer.unmap();
+    /// Whether the device is still there and has reported no error
+    fn check_device(&self) -> Result<(), CrackerError> {
+        if self.device_lost.load(Ordering::Acquire) {
+            return Err(CrackerError::DeviceLost);
+        }
+        match self.gpu_error.lock().unwrap().clone() {
+            Some(message) => Err(CrackerError::Gpu(message)),
+            None => Ok(()),
+        }
+    }
 
-        if result >= 0 {
-            Some(result as usize)
-        } else {
-            None
+    /// Wait until `status` is in, then check it and the device
+    fn wait_for_map(
+        &self,
+        status: &MapStatus,
+        submission: Option<wgpu::SubmissionIndex>,
+    ) -> Result<(), CrackerError> {
+        let mapped = loop {
+            if let Some(mapped) = status.get() {
+                break mapped;
+            }
+            self.poll(wgpu::PollType::Wait {
+                submission_index: submission.clone(),
+                timeout: None,
+            })?;
+        };
+        // Mapping fails when the device is lost, which is the error worth
+        // reporting then
+        self.check_device()?;
+        mapped.map_err(CrackerError::MapFailed)
+    }
+
+    /// Whether the batch in `slot` has finished and its readback is
+    /// mapped, without blocking
+    ///
+    /// A failed device counts as finished, so reading the batch finds out.
+    fn readback_ready(&self, slot: usize) -> bool {
+        self.poll(wgpu::PollType::Poll).is_err() || self.buffer_sets[slot].readback.get().is_some()
+    }
+
+    /// Block until the batch in `slot` has finished and its readback is
+    /// mapped, leaving later submissions running
+    fn wait_for_readback(&self, slot: usize) -> Result<(), CrackerError> {
+        let buffer_set = &self.buffer_sets[slot];
+        self.wait_for_map(&buffer_set.readback, buffer_set.submission.clone())
+    }
+
+    /// Release the readback of a batch nobody will look at, once it is in
+    ///
+    /// If the device failed meanwhile there is nothing left to release; the
+    /// error is up to whoever reads the batch that hit it.
+    fn discard_readback(&self, slot: usize) {
+        if self.wait_for_readback(slot).is_err() {
+            self.buffer_sets[slot]
+                .unread
+                .store(false, Ordering::Release);
+            return;
         }
+        self.unmap_readback(slot);
     }
 
-    /// Process a batch with GPU timing information (for benchmarking)
-    /// Returns (result_index, gpu_time_ns) where gpu_time_ns is the GPU execution time in nanoseconds
-    pub fn process_batch_with_timing(
-        &mut self,
-        messages: &[&str],
-        target_hash: &[u8; 16],
-    ) -> (Option<usize>, Option<u64>) {
-        if !self.supports_timestamps {
-            // Fall back to regular processing without timing
-            return (self.process_batch(messages, target_hash), None);
+    /// Unmap the readback of the batch in `slot` once it has been read
+    fn unmap_readback(&self, slot: usize) {
+        let buffer_set = &self.buffer_sets[slot];
+        if buffer_set.reads_digests {
+            buffer_set.digest_staging_buffer.unmap();
+        } else {
+            buffer_set.staging_buffer.unmap();
         }
+        buffer_set.unread.store(false, Ordering::Release);
+    }
 
-        self.build_cpu_buffers(messages);
+    /// Read the result slots of the batch in `slot` (blocks until ready)
+    ///
+    /// A kernel that lists its hits gets them back in the order they were
+    /// recorded, followed by a -1 unless all [`RESULT_SLOTS`] are taken;
+    /// any other gets its slots as the kernel left them.
+    fn read_result(&self, slot: usize) -> Result<Vec<i32>, CrackerError> {
+        self.wait_for_readback(slot)?;
+        let buffer_set = &self.buffer_sets[slot];
+        let kernel = buffer_set.kernel;
+
+        let data = buffer_set
+            .staging_buffer
+            .slice(..kernel.result_bytes())
+            .get_mapped_range();
+        let mut words = data
+            .chunks_exact(4)
+            .map(|bytes| i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        let slots = if kernel.lists_hits() {
+            let count = (words.next().unwrap_or(0).max(0) as usize).min(RESULT_SLOTS);
+            let mut hits: Vec<i32> = words.take(count).collect();
+            if count < RESULT_SLOTS {
+                hits.push(-1);
+            }
+            hits
+        } else {
+            words.collect()
+        };
+        drop(data);
+        self.unmap_readback(slot);
 
-        // Use buffer_set_a for timing measurements
-        let buffer_set = &self.buffer_set_a;
+        Ok(slots)
+    }
 
-        // Write preprocessed data directly to GPU buffers
-        self.upload_batch_to_gpu(buffer_set, target_hash, messages.len());
+    /// Read `count` digests written by `md5_digest` (blocks until ready)
+    fn read_digests(&self, slot: usize, count: usize) -> Result<Vec<[u8; 16]>, CrackerError> {
+        self.wait_for_readback(slot)?;
+        let buffer_set = &self.buffer_sets[slot];
+
+        let data = buffer_set
+            .digest_staging_buffer
+            .slice(..)
+            .get_mapped_range();
+        let digests = data[..count * 16]
+            .chunks_exact(16)
+            .map(|d| d.try_into().unwrap())
+            .collect();
+        drop(data);
+        self.unmap_readback(slot);
 
-        // Create timestamp query set
-        let query_set = self.device.create_query_set(&wgpu::QuerySetDescriptor {
-            label: Some("Timestamp Query Set"),
-            ty: wgpu::QueryType::Timestamp,
-            count: 2,
-        });
+        Ok(digests)
+    }
 
-        let query_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Query Resolve Buffer"),
-            size: 16, // 2 timestamps * 8 bytes
-            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
+    /// GPU time of each pass timed in the batch last submitted in `slot`,
+    /// in nanoseconds, once it has finished
+    ///
+    /// Each timed batch's timestamps are read exactly once, whether by the
+    /// progress report or before the set is reused.
+    fn read_batch_timer(&mut self, slot: usize) -> Option<Vec<u64>> {
+        let buffer_set = &self.buffer_sets[slot];
+        let passes = buffer_set.timed_passes;
+        if passes == 0 {
+            return None;
+        }
+        let mapped = self.wait_for_map(&buffer_set.timer_readback, buffer_set.submission.clone());
+        self.buffer_sets[slot].timed_passes = 0;
+        mapped.ok()?;
 
-        let query_staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Query Staging Buffer"),
-            size: 16,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let timer = self.buffer_sets[slot].timer.as_ref()?;
+        Some(timer.durations_ns(passes, self.queue.get_timestamp_period()))
+    }
+
+    /// Run `kernel` over the `count` candidates uploaded to the first
+    /// buffer set in a single dispatch and wait for it
+    ///
+    /// Returns the first result slot and, if timestamp queries are
+    /// supported, the GPU execution time in nanoseconds.
+    fn time_dispatch(
+        &mut self,
+        kernel: Kernel,
+        count: usize,
+    ) -> Result<(i32, Option<u64>), CrackerError> {
+        self.ensure_pipeline(kernel);
+        self.read_batch_timer(0);
+
+        let buffer_set = &self.buffer_sets[0];
+        let dispatch = [count as u32, 0, 0, 0];
+        self.queue.write_buffer(
+            &buffer_set.message_count_buffer,
+            0,
+            bytemuck::cast_slice(&dispatch),
+        );
 
         // Create command encoder and dispatch with timestamps
         let mut encoder = self
@@ -610,22 +2398,22 @@ impl GpuCracker {
         {
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                 label: Some("MD5 Crack Pass"),
-                timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
-                    query_set: &query_set,
-                    beginning_of_pass_write_index: Some(0),
-                    end_of_pass_write_index: Some(1),
-                }),
+                timestamp_writes: buffer_set
+                    .timer
+                    .as_ref()
+                    .and_then(|timer| timer.writes(0, true, true)),
             });
-            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_pipeline(self.pipeline(kernel));
             compute_pass.set_bind_group(0, &buffer_set.bind_group, &[]);
 
-            let num_workgroups = (messages.len() as u32).div_ceil(64);
+            let num_workgroups = (count as u32).div_ceil(self.workgroup_size(kernel));
             compute_pass.dispatch_workgroups(num_workgroups, 1, 1);
         }
 
         // Resolve timestamp queries
-        encoder.resolve_query_set(&query_set, 0..2, &query_buffer, 0);
-        encoder.copy_buffer_to_buffer(&query_buffer, 0, &query_staging_buffer, 0, 16);
+        if let Some(timer) = &buffer_set.timer {
+            timer.resolve(&mut encoder, 1);
+        }
 
         // Copy result to staging buffer
         encoder.copy_buffer_to_buffer(
@@ -633,77 +2421,23 @@ impl GpuCracker {
             0,
             &buffer_set.staging_buffer,
             0,
-            4,
+            kernel.result_bytes(),
         );
 
-        // Submit commands
-        self.queue.submit(Some(encoder.finish()));
-
-        // Read result
-        let buffer_slice = buffer_set.staging_buffer.slice(..);
-        let (sender, receiver) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender.send(result).unwrap();
-        });
-
-        self.device
-            .poll(wgpu::PollType::wait_indefinitely())
-            .expect("Failed to poll device");
-        receiver.recv().unwrap().unwrap();
-
-        let data = buffer_slice.get_mapped_range();
-        let result: i32 = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        drop(data);
-        buffer_set.staging_buffer.unmap();
-
-        // Read timestamps
-        let query_slice = query_staging_buffer.slice(..);
-        let (sender2, receiver2) = std::sync::mpsc::channel();
-        query_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender2.send(result).unwrap();
-        });
+        // Submit commands and wait for the result
+        let submission = self.queue.submit(Some(encoder.finish()));
+        self.buffer_sets[0].kernel = kernel;
+        self.request_readback(0, false, Some(submission));
+        self.request_timer_readback(0, 1);
+        let slots = self.read_result(0)?;
 
-        self.device
-            .poll(wgpu::PollType::wait_indefinitely())
-            .expect("Failed to poll device");
-        receiver2.recv().unwrap().unwrap();
-
-        let timestamp_data = query_slice.get_mapped_range();
-        let start_timestamp = u64::from_le_bytes([
-            timestamp_data[0],
-            timestamp_data[1],
-            timestamp_data[2],
-            timestamp_data[3],
-            timestamp_data[4],
-            timestamp_data[5],
-            timestamp_data[6],
-            timestamp_data[7],
-        ]);
-        let end_timestamp = u64::from_le_bytes([
-            timestamp_data[8],
-            timestamp_data[9],
-            timestamp_data[10],
-            timestamp_data[11],
-            timestamp_data[12],
-            timestamp_data[13],
-            timestamp_data[14],
-            timestamp_data[15],
-        ]);
-        drop(timestamp_data);
-        query_staging_buffer.unmap();
-
-        // Calculate elapsed time in nanoseconds
-        let timestamp_period = self.queue.get_timestamp_period();
-        let gpu_time_ns =
-            ((end_timestamp - start_timestamp) as f64 * timestamp_period as f64) as u64;
-
-        let result_idx = if result >= 0 {
-            Some(result as usize)
-        } else {
-            None
-        };
+        let gpu_time_ns = self.read_batch_timer(0).map(|passes| passes[0]);
+        Ok((slots[0], gpu_time_ns))
+    }
 
-        (result_idx, Some(gpu_time_ns))
+    /// Adapter the device was opened on
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
     }
 
     /// Get whether this GPU supports timestamp queries
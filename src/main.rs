@@ -1,31 +1,502 @@
-use rustcracker::GpuCracker;
+use rustcracker::{
+    benchmark_json, BcryptTarget, CandidateGenerator, CpuCracker, Cracker, CrackerEvent,
+    CustomShader, EventHandler, GpuCracker, GpuOptions, MysqlHash, MysqlTarget, NetNtlmTarget,
+    OfficeRc4Target, PartialMd5Target, Pbkdf2Target, PostgresMd5Target, Progress, PwdumpEntry,
+    ScanOutcome, ScanResult, Target, VanitySearch, WpaTarget, ZipCryptoTarget, BENCHMARK_MODES,
+    CPU_BENCHMARK_MODES,
+};
 use std::env;
 use std::fs;
-use std::io::Read;
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often the status line is redrawn at most
+const STATUS_INTERVAL: Duration = Duration::from_millis(500);
+/// Time `benchmark` measures each mode for unless set with `--runtime`
+const BENCHMARK_DURATION: Duration = Duration::from_secs(3);
+
+/// Whether the status line is on screen, so whatever prints next clears it
+static STATUS_SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// Writes the library's diagnostics to stderr, keeping stdout for results
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level() && metadata.target().starts_with("rustcracker")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        clear_status();
+        match record.level() {
+            log::Level::Info => eprintln!("{}", record.args()),
+            level => eprintln!("{}: {}", level.as_str().to_lowercase(), record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Show the progress of each scan on one line of stderr, redrawn every
+/// [`STATUS_INTERVAL`] and once more when the scan completes
+fn status_line() -> EventHandler {
+    let last_drawn: Mutex<Option<Instant>> = Mutex::new(None);
+    EventHandler::new(move |event| {
+        let CrackerEvent::Progress(progress) = event else {
+            return;
+        };
+        let mut last_drawn = last_drawn.lock().unwrap();
+        let complete = progress.total == Some(progress.checked);
+        if !complete && last_drawn.is_some_and(|drawn| drawn.elapsed() < STATUS_INTERVAL) {
+            return;
+        }
+        *last_drawn = Some(Instant::now());
+        eprint!("\r\x1b[2K{}", format_progress(progress));
+        STATUS_SHOWN.store(true, Ordering::Relaxed);
+    })
+}
+
+/// Erase the status line, if it is shown, before printing anything else
+fn clear_status() {
+    if STATUS_SHOWN.swap(false, Ordering::Relaxed) {
+        eprint!("\r\x1b[2K");
+    }
+}
+
+/// `1234/5000 (24.7%) | 1.52 MH/s (GPU 1.60 MH/s) | 0:00:03 | ETA 0:00:09`
+fn format_progress(progress: &Progress) -> String {
+    let mut line = match (progress.total, progress.fraction()) {
+        (Some(total), Some(fraction)) => {
+            format!("{}/{total} ({:.1}%)", progress.checked, fraction * 100.0)
+        }
+        _ => format!("{} checked", progress.checked),
+    };
+    line.push_str(&format!(" | {}", format_rate(progress.hashes_per_sec)));
+    if let Some(gpu_rate) = progress.gpu_hashes_per_sec {
+        line.push_str(&format!(" (GPU {})", format_rate(gpu_rate)));
+    }
+    line.push_str(&format!(" | {}", format_duration(progress.elapsed)));
+    if let Some(eta) = progress.eta() {
+        line.push_str(&format!(" | ETA {}", format_duration(eta)));
+    }
+    line
+}
+
+/// A hash rate with a metric prefix, such as `1.52 MH/s`
+fn format_rate(hashes_per_sec: f64) -> String {
+    let units = ["H/s", "kH/s", "MH/s", "GH/s", "TH/s"];
+    let mut rate = hashes_per_sec;
+    let mut unit = 0;
+    while rate >= 1000.0 && unit + 1 < units.len() {
+        rate /= 1000.0;
+        unit += 1;
+    }
+    format!("{rate:.2} {}", units[unit])
+}
+
+/// `h:mm:ss`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn usage(program: &str) -> ! {
+    eprintln!("Usage: {program} [--mode <mode>] [--all] <wordlist_file> <target>");
+    eprintln!("       {program} vanity [--count <n>] [--counter] <prefix> <mask> <md5 prefix>");
+    eprintln!("       {program} benchmark [--mode <mode>] [--runtime <s>] [--json]");
+    eprintln!();
+    eprintln!("Modes:");
+    eprintln!("  md5        (default) target is a 32 hex character MD5 hash, a shorter");
+    eprintln!("             prefix, or <digest>/<mask> to compare only some bits");
+    eprintln!("  oldoffice  target is a .doc/.xls file or a $oldoffice$ hash");
+    eprintln!("  postgres   target is a pg_shadow line or dump file (user + md5 hash)");
+    eprintln!("  mysql      target is a mysql.user line or dump file (*SHA1 hashes)");
+    eprintln!("  mysql323   target is a mysql.user line or dump file (OLD_PASSWORD hashes)");
+    eprintln!("  lm         target is an LM hash or pwdump file (case fixed up via NTLM)");
+    eprintln!("  ntlm       target is an NTLM hash or pwdump file");
+    eprintln!("  netntlm    target is a NetNTLMv1/v2 response or Responder log file");
+    eprintln!("  wpa        target is a WPA*01*/WPA*02* line or .hc22000 file");
+    eprintln!("  zip        target is a ZIP archive using legacy ZipCrypto encryption");
+    eprintln!("  bcrypt     target is a $2a$/$2b$/$2y$ hash or file (runs on the CPU)");
+    eprintln!("  pbkdf2     target is a Django/passlib PBKDF2-SHA256 or -SHA1 hash or file");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --all      report every candidate matching a partial MD5, not just the first");
+    eprintln!("  --batch-size <n>    candidates per GPU batch (default 65536)");
+    eprintln!("  --autotune          time each mode at startup to pick batch and workgroup sizes");
+    eprintln!("  --tune-cache <file> keep autotuned sizes in <file> for later runs (implies");
+    eprintln!("                      --autotune)");
+    eprintln!("  --dispatch-target <ms> GPU time each dispatch stays under (default 50)");
+    eprintln!("  --runtime <s>       stop scans after <s> seconds, keeping what was found");
+    eprintln!("  --profile           time every GPU dispatch and print where the time went");
+    eprintln!("  --shader <file.spv> load the GPU kernels from a SPIR-V module instead of the");
+    eprintln!("                      built-in one, running --entry-point as the MD5 kernel");
+    eprintln!("  --entry-point <name> MD5 kernel of --shader (default md5_crack)");
+    eprintln!("  --json              print benchmark results as JSON");
+    eprintln!("  -v, --verbose       also log dispatch sizing and other details to stderr");
+    eprintln!("  -q, --quiet         only log errors to stderr");
+    eprintln!();
+    eprintln!("Vanity search generates <prefix> followed by a hashcat-style mask (?l ?u ?d");
+    eprintln!("?s ?a ?h ?H) on the GPU and prints the first <n> (default 1) whose MD5");
+    eprintln!("matches. With --counter the mask is a charset and every length is tried,");
+    eprintln!("shortest first.");
+    eprintln!();
+    eprintln!("Benchmark runs every GPU mode and bcrypt on the CPU, or only --mode (netntlm");
+    eprintln!("as netntlmv1 and netntlmv2), over synthetic candidates for 3 seconds each");
+    eprintln!("(--runtime) and prints the hash rate of each.");
+    eprintln!();
+    eprintln!("Example: {program} wordlist.txt 5f4dcc3b5aa765d61d8327deb882cf99");
+    eprintln!("         {program} vanity --counter pow- ?d 000000");
+    eprintln!("         {program} benchmark --json > bench.json");
+    std::process::exit(1);
+}
+
+/// Read `target` as a dump file if it names one, otherwise treat it as a
+/// single line
+fn target_lines(target: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if Path::new(target).is_file() {
+        Ok(fs::read_to_string(target)?)
+    } else {
+        Ok(target.to_string())
+    }
+}
+
+fn decode_hash16(target: &str) -> Result<[u8; 16], Box<dyn std::error::Error>> {
+    let bytes = hex::decode(target)?;
+    bytes
+        .try_into()
+        .map_err(|_| "hash must be 32 hex characters (16 bytes)".into())
+}
+
+fn parse_targets(mode: &str, target: &str) -> Result<Vec<Target>, Box<dyn std::error::Error>> {
+    let targets = match mode {
+        "md5" => {
+            // A full hash goes through the plain path, anything less is partial
+            let partial = PartialMd5Target::parse(target)?;
+            if partial.is_full() {
+                vec![Target::Md5(partial.digest)]
+            } else {
+                println!("Matching {} known bits of the digest", partial.known_bits());
+                vec![Target::PartialMd5(partial)]
+            }
+        }
+        "oldoffice" => {
+            if target.starts_with("$oldoffice$") {
+                vec![Target::OldOffice(OfficeRc4Target::parse(target)?)]
+            } else if Path::new(target).is_file() {
+                let office = OfficeRc4Target::from_file(target)?;
+                println!("Extracted {office}");
+                vec![Target::OldOffice(office)]
+            } else {
+                return Err(format!("{target} is neither a $oldoffice$ hash nor a file").into());
+            }
+        }
+        "postgres" => PostgresMd5Target::parse_dump(&target_lines(target)?)
+            .into_iter()
+            .map(Target::Postgres)
+            .collect(),
+        "mysql" | "mysql323" => {
+            let want_old = mode == "mysql323";
+            MysqlTarget::parse_dump(&target_lines(target)?)
+                .into_iter()
+                .filter(|t| matches!(t.hash, MysqlHash::Old323(_)) == want_old)
+                .map(Target::Mysql)
+                .collect()
+        }
+        "lm" | "ntlm" => {
+            if let Ok(hash) = decode_hash16(target) {
+                if mode == "lm" {
+                    vec![Target::Lm(hash)]
+                } else {
+                    vec![Target::Ntlm(hash)]
+                }
+            } else {
+                PwdumpEntry::parse_dump(&target_lines(target)?)
+                    .into_iter()
+                    .filter_map(|mut entry| {
+                        if mode == "ntlm" {
+                            entry.lm = None;
+                        } else if entry.lm.is_none() {
+                            return None;
+                        }
+                        Some(Target::Pwdump(entry))
+                    })
+                    .collect()
+            }
+        }
+        "netntlm" => NetNtlmTarget::parse_dump(&target_lines(target)?)
+            .into_iter()
+            .map(Target::NetNtlm)
+            .collect(),
+        "wpa" => WpaTarget::parse_dump(&target_lines(target)?)
+            .into_iter()
+            .map(Target::Wpa)
+            .collect(),
+        "zip" => {
+            let zip = ZipCryptoTarget::from_file(target)?;
+            println!("Extracted {zip}");
+            vec![Target::Zip(zip)]
+        }
+        "bcrypt" => BcryptTarget::parse_dump(&target_lines(target)?)
+            .into_iter()
+            .map(Target::Bcrypt)
+            .collect(),
+        "pbkdf2" => Pbkdf2Target::parse_dump(&target_lines(target)?)
+            .into_iter()
+            .map(Target::Pbkdf2)
+            .collect(),
+        other => return Err(format!("unknown mode '{other}'").into()),
+    };
+
+    if targets.is_empty() {
+        return Err(format!("no {mode} hashes found in {target}").into());
+    }
+    Ok(targets)
+}
+
+/// Search for `count` strings starting with `prefix` whose MD5 matches
+/// `target`
+fn run_vanity(
+    prefix: &str,
+    mask: &str,
+    target: &str,
+    counter: bool,
+    count: usize,
+    gpu_options: GpuOptions,
+    runtime: Option<Duration>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let generator = if counter {
+        CandidateGenerator::Counter {
+            charset: CandidateGenerator::parse_charset(mask)?,
+            max_len: 55usize.saturating_sub(prefix.len()).min(32),
+        }
+    } else {
+        CandidateGenerator::parse_mask(mask)?
+    };
+    let target = PartialMd5Target::parse(target)?;
+    let search = match VanitySearch::new(prefix, generator, target) {
+        Ok(search) => search,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    println!("Initializing GPU...");
+    let mut cracker = pollster::block_on(GpuCracker::with_options(gpu_options))?;
+    cracker.set_deadline(runtime.map(|runtime| Instant::now() + runtime));
+    println!("Searching for {count} match(es) of {target} after {prefix:?}...");
+    let result = cracker.vanity_search(&search, count, |solution| {
+        clear_status();
+        println!("  {solution}  md5 = {:x}", md5::compute(solution));
+    })?;
+    clear_status();
+    let found = result.found;
+    if found < count {
+        match result.outcome {
+            ScanOutcome::TimedOut => println!("✗ Runtime limit reached after {found} match(es)"),
+            _ => println!("✗ Keyspace exhausted after {found} match(es)"),
+        }
+    }
+    print_profile(&cracker);
+    Ok(())
+}
+
+/// Print the per-stage timing of `cracker`'s batches to stderr, if it was
+/// profiling
+fn print_profile(cracker: &GpuCracker) {
+    if let Some(report) = cracker.profile_report() {
+        eprintln!();
+        eprint!("{report}");
+    }
+}
+
+/// Measure the hash rate of `mode`, or of every mode the benchmark has,
+/// over `duration` each, and print them as a table or as JSON
+fn run_benchmark(
+    mode: Option<&str>,
+    gpu_options: GpuOptions,
+    duration: Duration,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let modes = match mode {
+        None => [&BENCHMARK_MODES[..], &CPU_BENCHMARK_MODES].concat(),
+        Some("netntlm") => vec!["netntlmv1", "netntlmv2"],
+        Some(mode) if BENCHMARK_MODES.contains(&mode) || CPU_BENCHMARK_MODES.contains(&mode) => {
+            vec![mode]
+        }
+        Some(mode) => {
+            eprintln!("Error: no benchmark for mode '{mode}'");
+            std::process::exit(1);
+        }
+    };
+
+    // Only the results go to stdout, so the JSON can be piped on. The GPU
+    // is left alone when only CPU modes are measured.
+    let mut cracker = None;
+    if modes.iter().any(|mode| BENCHMARK_MODES.contains(mode)) {
+        eprintln!("Initializing GPU...");
+        cracker = Some(pollster::block_on(GpuCracker::with_options(gpu_options))?);
+    }
+    let mut cpu = CpuCracker::new();
+    if !json {
+        println!(
+            "{:<10} {:>8} {:>14} {:>14}",
+            "mode", "batch", "rate", "GPU rate"
+        );
+    }
+    let mut results = Vec::new();
+    for mode in modes {
+        eprintln!("Benchmarking {mode}...");
+        let result = match &mut cracker {
+            Some(cracker) if BENCHMARK_MODES.contains(&mode) => {
+                cracker.benchmark(mode, duration)?
+            }
+            _ => cpu.benchmark(mode, duration)?,
+        };
+        if !json {
+            let gpu_rate = result
+                .gpu_hashes_per_sec()
+                .map_or_else(|| "-".to_string(), format_rate);
+            println!(
+                "{:<10} {:>8} {:>14} {:>14}",
+                result.mode,
+                result.batch_size,
+                format_rate(result.hashes_per_sec),
+                gpu_rate
+            );
+        }
+        results.push(result);
+    }
+    let adapter = cracker.as_ref().map(GpuCracker::adapter_info);
+    if json {
+        print!("{}", benchmark_json(adapter, &results));
+    }
+    if let Some(cracker) = &cracker {
+        print_profile(cracker);
+    }
+    Ok(())
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command-line arguments
     let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Usage: {} <wordlist_file> <md5_hash>", args[0]);
-        eprintln!(
-            "Example: {} wordlist.txt 5f4dcc3b5aa765d61d8327deb882cf99",
-            args[0]
+    let mut mode_name = None;
+    let mut all_matches = false;
+    let mut count = 1;
+    let mut counter = false;
+    let mut gpu_options = GpuOptions::default();
+    let mut log_level = log::LevelFilter::Info;
+    let mut runtime = None;
+    let mut json = false;
+    let mut shader = None;
+    let mut entry_point = "md5_crack";
+    let mut positional = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-m" | "--mode" => match iter.next() {
+                Some(mode) => mode_name = Some(mode.clone()),
+                None => usage(&args[0]),
+            },
+            "--all" => all_matches = true,
+            "--count" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => count = n,
+                None => usage(&args[0]),
+            },
+            "--counter" => counter = true,
+            "--batch-size" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => gpu_options.batch_size = Some(n),
+                None => usage(&args[0]),
+            },
+            "--autotune" => gpu_options.autotune = true,
+            "--tune-cache" => match iter.next() {
+                Some(path) => gpu_options.tune_cache = Some(path.into()),
+                None => usage(&args[0]),
+            },
+            "--dispatch-target" => match iter.next().and_then(|ms| ms.parse().ok()) {
+                Some(ms) => gpu_options.dispatch_target = Duration::from_millis(ms),
+                None => usage(&args[0]),
+            },
+            "--runtime" => match iter.next().and_then(|s| s.parse().ok()) {
+                Some(seconds) => runtime = Some(Duration::from_secs(seconds)),
+                None => usage(&args[0]),
+            },
+            "--profile" => gpu_options.profile = true,
+            "--shader" => match iter.next() {
+                Some(path) => shader = Some(path),
+                None => usage(&args[0]),
+            },
+            "--entry-point" => match iter.next() {
+                Some(name) => entry_point = name,
+                None => usage(&args[0]),
+            },
+            "--json" => json = true,
+            "-v" | "--verbose" => log_level = log::LevelFilter::Debug,
+            "-q" | "--quiet" => log_level = log::LevelFilter::Error,
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(log_level);
+    }
+    if let Some(path) = shader {
+        gpu_options.shader = Some(CustomShader::load(path, entry_point)?);
+    }
+    if positional.first() == Some(&"benchmark") {
+        if positional.len() != 1 {
+            usage(&args[0]);
+        }
+        return run_benchmark(
+            mode_name.as_deref(),
+            gpu_options,
+            runtime.unwrap_or(BENCHMARK_DURATION),
+            json,
         );
-        std::process::exit(1);
+    }
+    if log_level >= log::LevelFilter::Info && std::io::stderr().is_terminal() {
+        gpu_options.on_event = Some(status_line());
+    }
+    if positional.first() == Some(&"vanity") {
+        if positional.len() != 4 {
+            usage(&args[0]);
+        }
+        return run_vanity(
+            positional[1],
+            positional[2],
+            positional[3],
+            counter,
+            count,
+            gpu_options,
+            runtime,
+        );
+    }
+    if positional.len() != 2 {
+        usage(&args[0]);
     }
 
-    let wordlist_path = &args[1];
-    let target_hash_str = &args[2];
+    let mode_name = mode_name.as_deref().unwrap_or("md5");
+    let wordlist_path = positional[0];
+    let target_str = positional[1];
 
-    // Decode target hash
-    let target_hash_vec = hex::decode(target_hash_str)?;
-    if target_hash_vec.len() != 16 {
-        eprintln!("Error: MD5 hash must be 32 hex characters (16 bytes)");
-        std::process::exit(1);
-    }
-    let mut target_hash = [0u8; 16];
-    target_hash.copy_from_slice(&target_hash_vec);
+    let targets = match parse_targets(mode_name, target_str) {
+        Ok(targets) => targets,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+    };
 
     // Read wordlist
     println!("Loading wordlist from {wordlist_path}...");
@@ -35,22 +506,82 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let wordlist: Vec<&str> = wordlist_data.lines().collect();
     println!("Loaded {} passwords", wordlist.len());
 
-    // Initialize GPU cracker
-    println!("Initializing GPU...");
-    let mut cracker = pollster::block_on(GpuCracker::new())?;
+    // Initialize the GPU cracker unless every target runs on the CPU
+    let mut gpu = None;
+    let mut cpu = CpuCracker::new();
+    if targets.iter().any(|t| !cpu.supports(t)) {
+        println!("Initializing GPU...");
+        gpu = Some(pollster::block_on(GpuCracker::with_options(gpu_options))?);
+    }
+    let deadline = runtime.map(|runtime| Instant::now() + runtime);
+    cpu.set_deadline(deadline);
+    if let Some(cracker) = &mut gpu {
+        cracker.set_deadline(deadline);
+    }
+    if gpu.is_none() {
+        println!("Using {} CPU threads", cpu.threads());
+    }
+
+    for (i, target) in targets.iter().enumerate() {
+        let label = match target {
+            Target::Md5(_)
+            | Target::PartialMd5(_)
+            | Target::OldOffice(_)
+            | Target::Lm(_)
+            | Target::Ntlm(_)
+            | Target::Zip(_) => target_str.to_string(),
+            Target::Postgres(pg) => pg.to_string(),
+            Target::Bcrypt(bcrypt) => bcrypt.to_string(),
+            Target::Pbkdf2(pbkdf2) => pbkdf2.to_string(),
+            Target::Mysql(my) => my.to_string(),
+            Target::Pwdump(entry) => entry.username.clone(),
+            Target::NetNtlm(net) => format!("{}\\{}", net.domain, net.username),
+            Target::Wpa(wpa) => format!("{} ({})", wpa.essid_lossy(), hex::encode(wpa.mac_ap)),
+        };
+
+        // Attempt to crack the hash
+        println!("Cracking {mode_name} target {label}...");
+        let ScanResult { found, outcome } = match (target, gpu.as_mut()) {
+            (Target::PartialMd5(partial), Some(cracker)) => {
+                cracker.crack_md5_partial(partial, &wordlist, all_matches)?
+            }
+            (target, gpu) => {
+                let cracker: &mut dyn Cracker = match gpu {
+                    Some(gpu) if gpu.supports(target) => gpu,
+                    _ => &mut cpu,
+                };
+                let result = cracker.crack_target(target, &wordlist)?;
+                ScanResult {
+                    found: result.found.into_iter().collect(),
+                    outcome: result.outcome,
+                }
+            }
+        };
+        clear_status();
 
-    // Attempt to crack the hash
-    println!("Cracking hash {target_hash_str}...");
-    match cracker.crack(&target_hash, &wordlist) {
-        Some(password) => {
+        let timed_out = outcome == ScanOutcome::TimedOut;
+        if !found.is_empty() {
             println!("✓ Hash cracked!");
+        } else if timed_out {
+            println!("✗ Runtime limit reached before the hash was found");
+        } else {
+            println!("✗ Hash not found in wordlist");
+        }
+        for password in &found {
             println!("  Password: {password}");
-            println!("  md5({password}) = {target_hash_str}");
+            if let Target::Md5(_) | Target::PartialMd5(_) = target {
+                println!("  md5({password}) = {:x}", md5::compute(password));
+            }
         }
-        None => {
-            println!("✗ Hash not found in wordlist");
+
+        if timed_out && i + 1 < targets.len() {
+            println!("Skipping the {} remaining target(s)", targets.len() - i - 1);
+            break;
         }
     }
+    if let Some(cracker) = &gpu {
+        print_profile(cracker);
+    }
 
     Ok(())
 }
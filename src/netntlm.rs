@@ -0,0 +1,200 @@
+//! NetNTLMv1/v2 challenge-response hashes
+//!
+//! These are the `user::domain:...` lines captured by Responder and similar
+//! tools, in hashcat's 5500 (v1) and 5600 (v2) formats. Both start from the
+//! NT hash of the candidate:
+//!
+//! * v1: the NT hash, zero-padded to 21 bytes, is used as three DES keys to
+//!   encrypt the 8-byte challenge. With extended session security the
+//!   challenge is first mixed with the client challenge from the LM field.
+//! * v2: `NTProofStr = HMAC-MD5(HMAC-MD5(NT, UPPER(user) || domain),
+//!   server_challenge || blob)`, all strings in UTF-16LE.
+
+use crate::windows::des_ip;
+use crate::{
+    append_hmac_md5_blocks_for, CrackerError, GpuCracker, Kernel, ScanResult, TARGET_BUFFER_SIZE,
+};
+use std::error::Error;
+
+/// The response part of a captured NetNTLM exchange
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetNtlmResponse {
+    V1 {
+        /// LM response field; carries the client challenge under ESS
+        lm_response: Vec<u8>,
+        nt_response: [u8; 24],
+        server_challenge: [u8; 8],
+    },
+    V2 {
+        server_challenge: [u8; 8],
+        nt_proof: [u8; 16],
+        /// The rest of the NTLMv2 response (timestamp, client challenge, AV pairs)
+        blob: Vec<u8>,
+    },
+}
+
+/// A captured NetNTLMv1 or NetNTLMv2 response
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetNtlmTarget {
+    pub username: String,
+    pub domain: String,
+    pub response: NetNtlmResponse,
+}
+
+impl NetNtlmTarget {
+    /// Parse a `user::domain:challenge:ntproofstr:blob` (v2) or
+    /// `user::domain:lmresp:ntresp:challenge` (v1) line
+    ///
+    /// Responder console output is accepted as well; anything up to the
+    /// last ` : ` (e.g. `[SMB] NTLMv2-SSP Hash     : `) is ignored.
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let line = line
+            .rsplit_once(" : ")
+            .map_or(line, |(_, hash)| hash)
+            .trim();
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() != 6 || !fields[1].is_empty() {
+            return Err("expected user::domain:... NetNTLM response".into());
+        }
+
+        let response = if fields[3].len() == 16 && fields[4].len() == 32 {
+            NetNtlmResponse::V2 {
+                server_challenge: decode_array(fields[3])?,
+                nt_proof: decode_array(fields[4])?,
+                blob: hex::decode(fields[5])?,
+            }
+        } else if fields[4].len() == 48 && fields[5].len() == 16 {
+            NetNtlmResponse::V1 {
+                lm_response: hex::decode(fields[3])?,
+                nt_response: decode_array(fields[4])?,
+                server_challenge: decode_array(fields[5])?,
+            }
+        } else {
+            return Err("unrecognised NetNTLM response lengths".into());
+        };
+
+        let target = Self {
+            username: fields[0].to_string(),
+            domain: fields[2].to_string(),
+            response,
+        };
+        if target.params().len() * 4 > TARGET_BUFFER_SIZE as usize {
+            return Err("NetNTLMv2 blob too long for the GPU kernel".into());
+        }
+        Ok(target)
+    }
+
+    /// Parse every response of a capture log, skipping lines that don't match
+    pub fn parse_dump(text: &str) -> Vec<Self> {
+        text.lines().filter_map(|l| Self::parse(l).ok()).collect()
+    }
+
+    /// The challenge the DES stage of NetNTLMv1 actually encrypts
+    ///
+    /// With extended session security the LM field holds an 8-byte client
+    /// challenge followed by zeros, and the challenge becomes
+    /// `MD5(server_challenge || client_challenge)[..8]`.
+    fn v1_challenge(lm_response: &[u8], server_challenge: &[u8; 8]) -> [u8; 8] {
+        if lm_response.len() == 24 && lm_response[8..].iter().all(|&b| b == 0) {
+            let mut data = server_challenge.to_vec();
+            data.extend_from_slice(&lm_response[..8]);
+            md5::compute(data).0[..8].try_into().unwrap()
+        } else {
+            *server_challenge
+        }
+    }
+
+    /// Kernel parameters, as the words `netntlmv1_crack`/`netntlmv2_crack`
+    /// read from the target buffer
+    fn params(&self) -> Vec<u32> {
+        match &self.response {
+            NetNtlmResponse::V1 {
+                lm_response,
+                nt_response,
+                server_challenge,
+            } => {
+                let mut params =
+                    des_ip(&Self::v1_challenge(lm_response, server_challenge)).to_vec();
+                for block in nt_response.chunks_exact(8) {
+                    params.extend_from_slice(&des_ip(block));
+                }
+                params
+            }
+            NetNtlmResponse::V2 {
+                server_challenge,
+                nt_proof,
+                blob,
+            } => {
+                let identity: Vec<u8> = (self.username.to_uppercase() + &self.domain)
+                    .encode_utf16()
+                    .flat_map(u16::to_le_bytes)
+                    .collect();
+                let mut data = server_challenge.to_vec();
+                data.extend_from_slice(blob);
+
+                let mut params: Vec<u32> = nt_proof
+                    .chunks_exact(4)
+                    .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+                    .collect();
+                params.resize(8, 0);
+                let identity_blocks = append_hmac_md5_blocks_for(&identity, &mut params);
+                let data_blocks = append_hmac_md5_blocks_for(&data, &mut params);
+                params[4] = identity_blocks;
+                params[5] = data_blocks;
+                params
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for NetNtlmTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}:", self.username, self.domain)?;
+        match &self.response {
+            NetNtlmResponse::V1 {
+                lm_response,
+                nt_response,
+                server_challenge,
+            } => write!(
+                f,
+                "{}:{}:{}",
+                hex::encode(lm_response),
+                hex::encode(nt_response),
+                hex::encode(server_challenge)
+            ),
+            NetNtlmResponse::V2 {
+                server_challenge,
+                nt_proof,
+                blob,
+            } => write!(
+                f,
+                "{}:{}:{}",
+                hex::encode(server_challenge),
+                hex::encode(nt_proof),
+                hex::encode(blob)
+            ),
+        }
+    }
+}
+
+impl GpuCracker {
+    /// Crack a captured NetNTLMv1 or NetNTLMv2 response
+    pub fn crack_netntlm(
+        &mut self,
+        target: &NetNtlmTarget,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let kernel = match target.response {
+            NetNtlmResponse::V1 { .. } => Kernel::NetNtlmV1,
+            NetNtlmResponse::V2 { .. } => Kernel::NetNtlmV2,
+        };
+        let params = target.params();
+        self.crack_with(kernel, bytemuck::cast_slice(&params), &[], wordlist)
+    }
+}
+
+fn decode_array<const N: usize>(field: &str) -> Result<[u8; N], Box<dyn Error>> {
+    hex::decode(field)?
+        .try_into()
+        .map_err(|_| format!("expected {} hex characters", N * 2).into())
+}
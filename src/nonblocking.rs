@@ -0,0 +1,401 @@
+//! Async versions of the cracking calls, for programs running an async
+//! executor such as tokio
+//!
+//! The blocking calls wait for GPU readbacks by polling the device on the
+//! calling thread, which would stall every other task on an executor
+//! thread. These calls instead hand the polling to a thread of their own
+//! per device: a future waiting for a readback registers its waker there,
+//! and the thread polls the device until the readback's submission has
+//! finished, which runs the `map_async` callbacks, then wakes it.
+//!
+//! Candidates are packed on the task itself between readbacks. The first
+//! dispatch of each scan is still timed synchronously, which waits for a
+//! few thousand candidates at most.
+
+use crate::{
+    events, CrackerError, CrackerEvent, Encoding, GpuCracker, Kernel, MapStatus, PackedBatch,
+    PartialMd5Target, ScanOutcome, ScanResult, TARGET_BUFFER_SIZE,
+};
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Instant;
+
+/// Thread polling one device for the futures waiting on it
+///
+/// It sleeps while nobody waits. The thread stops once the poller is
+/// dropped, which happens when the device is replaced or the cracker is.
+pub(crate) struct DevicePoller {
+    shared: Arc<PollerShared>,
+}
+
+struct PollerShared {
+    state: Mutex<PollerState>,
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct PollerState {
+    // Futures to wake after the next poll, and the submission the first of
+    // them waits for
+    wakers: Vec<Waker>,
+    submission: Option<wgpu::SubmissionIndex>,
+    stopped: bool,
+}
+
+impl DevicePoller {
+    pub(crate) fn new(device: wgpu::Device, lost: Arc<AtomicBool>) -> Result<Self, CrackerError> {
+        let shared = Arc::new(PollerShared {
+            state: Mutex::new(PollerState::default()),
+            wake: Condvar::new(),
+        });
+        let polling = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("rustcracker-poller".into())
+            .spawn(move || polling.run(&device, &lost))
+            .map_err(CrackerError::PollerThread)?;
+        Ok(Self { shared })
+    }
+
+    /// Wake `waker` once the device has been polled up to `submission`
+    fn register(&self, waker: &Waker, submission: Option<wgpu::SubmissionIndex>) {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.wakers.is_empty() {
+            state.submission = submission;
+        }
+        state.wakers.push(waker.clone());
+        self.shared.wake.notify_one();
+    }
+}
+
+impl Drop for DevicePoller {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stopped = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+impl PollerShared {
+    fn run(&self, device: &wgpu::Device, lost: &AtomicBool) {
+        loop {
+            let (wakers, submission) = {
+                let mut state = self.state.lock().unwrap();
+                while state.wakers.is_empty() && !state.stopped {
+                    state = self.wake.wait(state).unwrap();
+                }
+                if state.stopped {
+                    return;
+                }
+                (std::mem::take(&mut state.wakers), state.submission.take())
+            };
+
+            // A lost device is left alone, as in GpuCracker::poll; the
+            // woken futures find out from the flag the device lost
+            // callback set
+            if !lost.load(Ordering::Acquire) {
+                let polled = device.poll(wgpu::PollType::Wait {
+                    submission_index: submission,
+                    timeout: None,
+                });
+                if let Err(e) = polled {
+                    log::warn!("Polling the GPU failed: {e}");
+                }
+            }
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A scan running as a future, as [`HitStream`] drives it
+type Scan<'a> = Pin<Box<dyn Future<Output = Result<ScanOutcome, CrackerError>> + Send + 'a>>;
+
+/// Every match of a partial MD5 target, as [`GpuCracker::crack_stream`]
+/// finds them
+///
+/// The scan only runs while the stream is polled. It ends after the last
+/// batch, or after the first error.
+pub struct HitStream<'a> {
+    scan: Option<Scan<'a>>,
+    hits: Arc<Mutex<VecDeque<String>>>,
+    outcome: Option<ScanOutcome>,
+}
+
+impl HitStream<'_> {
+    /// Why the scan ended, once the stream has, unless it ended on an error
+    pub fn outcome(&self) -> Option<ScanOutcome> {
+        self.outcome
+    }
+}
+
+impl Stream for HitStream<'_> {
+    type Item = Result<String, CrackerError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(hit) = self.hits.lock().unwrap().pop_front() {
+                return Poll::Ready(Some(Ok(hit)));
+            }
+            let Some(scan) = self.scan.as_mut() else {
+                return Poll::Ready(None);
+            };
+            match scan.as_mut().poll(cx) {
+                // Hand over whatever the last batches found first
+                Poll::Ready(Ok(outcome)) => {
+                    self.scan = None;
+                    self.outcome = Some(outcome);
+                }
+                Poll::Ready(Err(e)) => {
+                    self.scan = None;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Pending => {
+                    return match self.hits.lock().unwrap().pop_front() {
+                        Some(hit) => Poll::Ready(Some(Ok(hit))),
+                        None => Poll::Pending,
+                    };
+                }
+            }
+        }
+    }
+}
+
+impl GpuCracker {
+    /// Like [`process_batch`](Self::process_batch), but waiting for the
+    /// result without blocking the calling thread
+    pub async fn process_batch_async(
+        &mut self,
+        messages: &[&str],
+        target_hash: &[u8; 16],
+    ) -> Result<Option<usize>, CrackerError> {
+        self.check_batch(messages)?;
+        let mut batch = std::mem::take(&mut self.batch);
+        batch.pack(messages, Encoding::Utf8, &[]);
+
+        let params = PartialMd5Target::full(*target_hash).params();
+        let target = bytemuck::cast_slice(&params);
+        let result = async {
+            self.ensure_device()?;
+            loop {
+                self.ensure_poller()?;
+                self.submit_packed(Kernel::Md5, target, 0, &batch, 0);
+                match self.read_result_async(0).await {
+                    Ok(slots) => return Ok((slots[0] >= 0).then_some(slots[0] as usize)),
+                    Err(CrackerError::DeviceLost) => self.recover_device()?,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        .await;
+        self.batch = batch;
+        result
+    }
+
+    /// Like [`crack`](Self::crack), but waiting for each batch without
+    /// blocking the calling thread
+    pub async fn crack_async(
+        &mut self,
+        target_hash: &[u8; 16],
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let params = PartialMd5Target::full(*target_hash).params();
+        let mut found = None;
+        let events = self.events.clone();
+        let outcome = self
+            .scan_async(bytemuck::cast_slice(&params), wordlist, |batch, slots| {
+                if slots[0] < 0 {
+                    return false;
+                }
+                let candidate = batch[slots[0] as usize];
+                events::emit(&events, || CrackerEvent::HitFound {
+                    candidate: candidate.to_string(),
+                });
+                found = Some(candidate.to_string());
+                true
+            })
+            .await?;
+        Ok(ScanResult { found, outcome })
+    }
+
+    /// Stream every candidate whose MD5 matches the known bits of
+    /// `target`, in wordlist order, as soon as its batch is read back
+    ///
+    /// Meant for long jobs where waiting for
+    /// [`crack_md5_partial`](Self::crack_md5_partial) to return them all at
+    /// once is not an option. Dropping the stream stops the scan; so do the
+    /// [`CancelToken`](crate::CancelToken) and the deadline, after the
+    /// batches in flight.
+    pub fn crack_stream<'a>(
+        &'a mut self,
+        target: &'a PartialMd5Target,
+        wordlist: &'a [&'a str],
+    ) -> HitStream<'a> {
+        let hits = Arc::new(Mutex::new(VecDeque::new()));
+        let found = Arc::clone(&hits);
+        let events = self.events.clone();
+        let params = target.params();
+        let scan = async move {
+            self.scan_async(bytemuck::cast_slice(&params), wordlist, |batch, slots| {
+                let mut found = found.lock().unwrap();
+                for i in target.batch_hits(batch, slots) {
+                    events::emit(&events, || CrackerEvent::HitFound {
+                        candidate: batch[i].to_string(),
+                    });
+                    found.push_back(batch[i].to_string());
+                }
+                false
+            })
+            .await
+        };
+        HitStream {
+            scan: Some(Box::pin(scan)),
+            hits,
+            outcome: None,
+        }
+    }
+
+    /// Run `md5_crack` over the wordlist like
+    /// [`scan_with`](Self::scan_with), awaiting each readback instead of
+    /// blocking on it
+    async fn scan_async(
+        &mut self,
+        target: &[u8],
+        wordlist: &[&str],
+        mut on_result: impl FnMut(&[&str], &[i32]) -> bool,
+    ) -> Result<ScanOutcome, CrackerError> {
+        let kernel = Kernel::Md5;
+        if target.len() as u64 > TARGET_BUFFER_SIZE {
+            return Err(CrackerError::InvalidTarget(format!(
+                "{} bytes of parameters, more than the {TARGET_BUFFER_SIZE} the kernels read",
+                target.len()
+            )));
+        }
+
+        let chunks: Vec<&[&str]> = wordlist.chunks(self.batch_size_for(kernel)).collect();
+        self.begin_scan(kernel, Some(wordlist.len()))?;
+        self.ensure_poller()?;
+
+        let depth = self.buffer_sets.len();
+        let mut spare: Vec<PackedBatch> = Vec::with_capacity(depth);
+        let mut in_flight: VecDeque<(usize, usize, PackedBatch)> = VecDeque::with_capacity(depth);
+        let mut next = 0;
+        loop {
+            // Keep every buffer set of the ring busy
+            while in_flight.len() < depth && next < chunks.len() {
+                if let Some(outcome) = self.stop_requested() {
+                    self.scan_outcome = outcome;
+                    next = chunks.len();
+                    break;
+                }
+                let mut batch = spare.pop().unwrap_or_default();
+                batch.pack(chunks[next], kernel.encoding(), &[]);
+                let slot = next % depth;
+                self.submit_packed(kernel, target, slot, &batch, 0);
+                in_flight.push_back((slot, next, batch));
+                next += 1;
+            }
+
+            let Some(&(slot, chunk, _)) = in_flight.front() else {
+                return Ok(self.scan_outcome);
+            };
+            let started = Instant::now();
+            let slots = match self.read_result_async(slot).await {
+                Ok(slots) => slots,
+                Err(CrackerError::DeviceLost) => {
+                    self.recover_device()?;
+                    self.ensure_poller()?;
+                    for (slot, _, batch) in &in_flight {
+                        self.submit_packed(kernel, target, *slot, batch, 0);
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    in_flight.pop_front();
+                    self.discard_in_flight_async(&mut in_flight).await;
+                    return Err(e);
+                }
+            };
+
+            self.batch_done(slot, started.elapsed());
+            let (_, _, batch) = in_flight.pop_front().expect("checked above");
+            if on_result(&batch.messages(chunks[chunk]), &slots) {
+                self.scan_outcome = ScanOutcome::Finished;
+                self.discard_in_flight_async(&mut in_flight).await;
+                return Ok(self.scan_outcome);
+            }
+            spare.push(batch);
+        }
+    }
+
+    /// Start polling the current device on a thread of its own, unless
+    /// that already happens
+    fn ensure_poller(&mut self) -> Result<(), CrackerError> {
+        if self.poller.is_none() {
+            self.poller = Some(DevicePoller::new(
+                self.device.clone(),
+                Arc::clone(&self.device_lost),
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Wait until `status` is in without blocking, then check it and the
+    /// device as [`wait_for_map`](Self::wait_for_map) does
+    fn map_ready<'a>(
+        &'a self,
+        status: &'a MapStatus,
+        submission: Option<wgpu::SubmissionIndex>,
+    ) -> impl Future<Output = Result<(), CrackerError>> + 'a {
+        future::poll_fn(move |cx| {
+            if let Some(mapped) = status.get() {
+                return Poll::Ready(
+                    self.check_device()
+                        .and(mapped.map_err(CrackerError::MapFailed)),
+                );
+            }
+            if let Err(e) = self.check_device() {
+                return Poll::Ready(Err(e));
+            }
+            let poller = self.poller.as_ref().expect("ensure_poller ran first");
+            poller.register(cx.waker(), submission.clone());
+            Poll::Pending
+        })
+    }
+
+    /// Wait until the batch in `slot` and its timestamps, if it was timed,
+    /// are read back, so nothing after this blocks
+    async fn readback_done(&self, slot: usize) -> Result<(), CrackerError> {
+        let buffer_set = &self.buffer_sets[slot];
+        let submission = buffer_set.submission.clone();
+        self.map_ready(&buffer_set.readback, submission.clone())
+            .await?;
+        if buffer_set.timed_passes > 0 {
+            self.map_ready(&buffer_set.timer_readback, submission)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Read the result slots of the batch in `slot` once they are in
+    async fn read_result_async(&self, slot: usize) -> Result<Vec<i32>, CrackerError> {
+        self.readback_done(slot).await?;
+        self.read_result(slot)
+    }
+
+    /// Release the readbacks of every batch still in flight, once each is
+    /// in
+    async fn discard_in_flight_async<P>(&self, in_flight: &mut VecDeque<(usize, usize, P)>) {
+        for (slot, _, _) in in_flight.drain(..) {
+            // Whatever went wrong is up to the next batch read to report
+            if self.readback_done(slot).await.is_ok() {
+                self.discard_readback(slot);
+            }
+        }
+    }
+}
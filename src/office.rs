@@ -0,0 +1,434 @@
+//! MS Office 97-2003 binary document password recovery
+//!
+//! Covers `.doc` and `.xls` files protected with "Office binary document RC4
+//! encryption" (RC4 keyed from an MD5-derived 40-bit key). The salt, encrypted
+//! verifier and encrypted verifier hash are pulled out of the OLE compound
+//! file and candidates are checked on the GPU by `office_rc4_crack`.
+
+use crate::{CrackerError, GpuCracker, Kernel, ScanResult};
+use std::error::Error;
+use std::path::Path;
+
+const CFB_MAGIC: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const END_OF_CHAIN: u32 = 0xFFFF_FFFE;
+const FREE_SECTOR: u32 = 0xFFFF_FFFF;
+const NO_STREAM: u32 = 0xFFFF_FFFF;
+
+/// Verifier material for an RC4-encrypted Office 97-2003 document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OfficeRc4Target {
+    pub salt: [u8; 16],
+    pub encrypted_verifier: [u8; 16],
+    pub encrypted_verifier_hash: [u8; 16],
+}
+
+impl OfficeRc4Target {
+    /// Parse a hashcat-style `$oldoffice$0*salt*verifier*verifierhash` line
+    /// (types 0 and 1 are both MD5 + RC4)
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let rest = line
+            .trim()
+            .strip_prefix("$oldoffice$")
+            .ok_or("expected a $oldoffice$ hash")?;
+        let fields: Vec<&str> = rest.split('*').collect();
+        if fields.len() != 4 {
+            return Err("expected $oldoffice$<type>*<salt>*<verifier>*<verifier hash>".into());
+        }
+        if fields[0] != "0" && fields[0] != "1" {
+            return Err(format!(
+                "$oldoffice$ type {} uses CryptoAPI (SHA1) and is not supported",
+                fields[0]
+            )
+            .into());
+        }
+
+        Ok(Self {
+            salt: decode_16(fields[1])?,
+            encrypted_verifier: decode_16(fields[2])?,
+            encrypted_verifier_hash: decode_16(fields[3])?,
+        })
+    }
+
+    /// Extract the verifier from a `.doc` or `.xls` file on disk
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Extract the verifier from the raw bytes of an OLE compound file
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let cfb = CompoundFile::parse(data)?;
+
+        if let Some(word) = cfb.stream("WordDocument")? {
+            return Self::from_word(&cfb, &word);
+        }
+        for name in ["Workbook", "Book"] {
+            if let Some(workbook) = cfb.stream(name)? {
+                return Self::from_workbook(&workbook);
+            }
+        }
+
+        Err("no WordDocument or Workbook stream found".into())
+    }
+
+    /// Word: the FIB says whether the file is encrypted and which table
+    /// stream holds the encryption header
+    fn from_word(cfb: &CompoundFile, word: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if word.len() < 0x12 || read_u16(word, 0) != 0xA5EC {
+            return Err("WordDocument stream has no valid FIB".into());
+        }
+        let flags = read_u16(word, 0x0A);
+        if flags & 0x0100 == 0 {
+            return Err("document is not encrypted".into());
+        }
+        if flags & 0x8000 != 0 {
+            return Err("document uses XOR obfuscation, not RC4".into());
+        }
+
+        let table_name = if flags & 0x0200 != 0 {
+            "1Table"
+        } else {
+            "0Table"
+        };
+        let table = cfb
+            .stream(table_name)?
+            .ok_or_else(|| format!("missing {table_name} stream"))?;
+        Self::from_encryption_header(&table)
+    }
+
+    /// Excel: look for the FILEPASS record in the BIFF8 workbook globals
+    fn from_workbook(workbook: &[u8]) -> Result<Self, Box<dyn Error>> {
+        const FILEPASS: u16 = 0x002F;
+        const EOF: u16 = 0x000A;
+
+        let mut pos = 0;
+        while pos + 4 <= workbook.len() {
+            let record_type = read_u16(workbook, pos);
+            let len = read_u16(workbook, pos + 2) as usize;
+            let body = workbook
+                .get(pos + 4..pos + 4 + len)
+                .ok_or("truncated BIFF record")?;
+
+            match record_type {
+                FILEPASS => {
+                    if body.len() < 2 || read_u16(body, 0) != 1 {
+                        return Err("workbook uses XOR obfuscation, not RC4".into());
+                    }
+                    return Self::from_encryption_header(&body[2..]);
+                }
+                EOF => break,
+                _ => pos += 4 + len,
+            }
+        }
+
+        Err("workbook is not encrypted".into())
+    }
+
+    /// Parse an RC4 EncryptionHeader: version 1.1 followed by the salt,
+    /// encrypted verifier and encrypted verifier hash
+    fn from_encryption_header(header: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if header.len() < 52 {
+            return Err("encryption header is truncated".into());
+        }
+        let major = read_u16(header, 0);
+        let minor = read_u16(header, 2);
+        if (major, minor) != (1, 1) {
+            return Err(
+                format!("encryption version {major}.{minor} (CryptoAPI) is not supported").into(),
+            );
+        }
+
+        Ok(Self {
+            salt: header[4..20].try_into().unwrap(),
+            encrypted_verifier: header[20..36].try_into().unwrap(),
+            encrypted_verifier_hash: header[36..52].try_into().unwrap(),
+        })
+    }
+
+    /// Check a password on the CPU
+    pub fn verify(&self, password: &str) -> bool {
+        let utf16: Vec<u8> = password
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        let h0 = md5::compute(&utf16);
+
+        let mut buf = Vec::with_capacity(336);
+        for _ in 0..16 {
+            buf.extend_from_slice(&h0[..5]);
+            buf.extend_from_slice(&self.salt);
+        }
+        let h1 = md5::compute(&buf);
+
+        // Key for block 0
+        let mut key_input = [0u8; 9];
+        key_input[..5].copy_from_slice(&h1[..5]);
+        let key = md5::compute(key_input);
+
+        let mut data = [0u8; 32];
+        data[..16].copy_from_slice(&self.encrypted_verifier);
+        data[16..].copy_from_slice(&self.encrypted_verifier_hash);
+        rc4_apply(&key.0, &mut data);
+
+        md5::compute(&data[..16]).0 == data[16..]
+    }
+
+    /// Target buffer layout expected by `office_rc4_crack`
+    fn params(&self) -> [u8; 48] {
+        let mut params = [0u8; 48];
+        params[..16].copy_from_slice(&self.salt);
+        params[16..32].copy_from_slice(&self.encrypted_verifier);
+        params[32..].copy_from_slice(&self.encrypted_verifier_hash);
+        params
+    }
+}
+
+impl std::fmt::Display for OfficeRc4Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "$oldoffice$1*{}*{}*{}",
+            hex::encode(self.salt),
+            hex::encode(self.encrypted_verifier),
+            hex::encode(self.encrypted_verifier_hash)
+        )
+    }
+}
+
+impl GpuCracker {
+    /// Recover the password of an RC4-encrypted Office 97-2003 document
+    pub fn crack_office_rc4(
+        &mut self,
+        target: &OfficeRc4Target,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        self.crack_with(Kernel::OfficeRc4, &target.params(), &[], wordlist)
+    }
+}
+
+fn decode_16(field: &str) -> Result<[u8; 16], Box<dyn Error>> {
+    let bytes = hex::decode(field)?;
+    bytes
+        .try_into()
+        .map_err(|_| "expected 32 hex characters".into())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn rc4_apply(key: &[u8], data: &mut [u8]) {
+    let mut s: [u8; 256] = std::array::from_fn(|i| i as u8);
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+        s.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+    for byte in data {
+        i = i.wrapping_add(1);
+        j = j.wrapping_add(s[i as usize]);
+        s.swap(i as usize, j as usize);
+        *byte ^= s[s[i as usize].wrapping_add(s[j as usize]) as usize];
+    }
+}
+
+struct DirEntry {
+    name: String,
+    start_sector: u32,
+    size: u64,
+}
+
+/// Minimal read-only OLE compound file (CFB) reader
+struct CompoundFile<'a> {
+    data: &'a [u8],
+    sector_size: usize,
+    mini_sector_size: usize,
+    mini_stream_cutoff: u64,
+    fat: Vec<u32>,
+    mini_fat: Vec<u32>,
+    entries: Vec<DirEntry>,
+    mini_stream: Vec<u8>,
+}
+
+impl<'a> CompoundFile<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, Box<dyn Error>> {
+        if data.len() < 512 || data[..8] != CFB_MAGIC {
+            return Err("not an OLE compound file".into());
+        }
+
+        let sector_shift = read_u16(data, 0x1E);
+        let mini_sector_shift = read_u16(data, 0x20);
+        if sector_shift != 9 && sector_shift != 12 {
+            return Err(format!("unsupported sector shift {sector_shift}").into());
+        }
+        if mini_sector_shift != 6 {
+            return Err(format!("unsupported mini sector shift {mini_sector_shift}").into());
+        }
+        let sector_size = 1usize << sector_shift;
+        let mini_sector_size = 1usize << mini_sector_shift;
+
+        let num_fat_sectors = read_u32(data, 0x2C) as usize;
+        let first_dir_sector = read_u32(data, 0x30);
+        let mini_stream_cutoff = read_u32(data, 0x38) as u64;
+        let first_mini_fat_sector = read_u32(data, 0x3C);
+        let mut difat_sector = read_u32(data, 0x44);
+
+        // The header holds the first 109 FAT sector numbers, the rest are
+        // chained through DIFAT sectors
+        let mut fat_sectors: Vec<u32> = (0..109)
+            .map(|i| read_u32(data, 0x4C + i * 4))
+            .take_while(|&s| s != FREE_SECTOR)
+            .collect();
+        let per_difat = sector_size / 4 - 1;
+        let mut guard = 0;
+        while fat_sectors.len() < num_fat_sectors && difat_sector < END_OF_CHAIN {
+            let sector = sector_slice(data, sector_size, difat_sector)?;
+            for i in 0..per_difat {
+                let s = read_u32(sector, i * 4);
+                if s != FREE_SECTOR {
+                    fat_sectors.push(s);
+                }
+            }
+            difat_sector = read_u32(sector, per_difat * 4);
+            guard += 1;
+            if guard > data.len() / sector_size {
+                return Err("DIFAT chain loops".into());
+            }
+        }
+        fat_sectors.truncate(num_fat_sectors);
+
+        let mut fat = Vec::with_capacity(fat_sectors.len() * sector_size / 4);
+        for &s in &fat_sectors {
+            let sector = sector_slice(data, sector_size, s)?;
+            fat.extend((0..sector_size / 4).map(|i| read_u32(sector, i * 4)));
+        }
+
+        let mut cfb = Self {
+            data,
+            sector_size,
+            mini_sector_size,
+            mini_stream_cutoff,
+            fat,
+            mini_fat: Vec::new(),
+            entries: Vec::new(),
+            mini_stream: Vec::new(),
+        };
+
+        let dir = cfb.read_chain(first_dir_sector, None)?;
+        for entry in dir.chunks_exact(128) {
+            let name_len = (read_u16(entry, 64) as usize).min(64);
+            let units: Vec<u16> = entry[..name_len.saturating_sub(2)]
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            let object_type = entry[66];
+            let size = if sector_shift == 9 {
+                read_u32(entry, 120) as u64
+            } else {
+                u64::from_le_bytes(entry[120..128].try_into().unwrap())
+            };
+            cfb.entries.push(DirEntry {
+                name: if object_type == 0 {
+                    String::new()
+                } else {
+                    String::from_utf16_lossy(&units)
+                },
+                start_sector: read_u32(entry, 116),
+                size,
+            });
+        }
+
+        let root = cfb
+            .entries
+            .first()
+            .ok_or("compound file has no root entry")?;
+        let (root_start, root_size) = (root.start_sector, root.size);
+        if root_start < END_OF_CHAIN {
+            cfb.mini_stream = cfb.read_chain(root_start, Some(root_size))?;
+            let mini_fat_bytes = cfb.read_chain(first_mini_fat_sector, None)?;
+            cfb.mini_fat = (0..mini_fat_bytes.len() / 4)
+                .map(|i| read_u32(&mini_fat_bytes, i * 4))
+                .collect();
+        }
+
+        Ok(cfb)
+    }
+
+    /// Read a stream by name, if present
+    fn stream(&self, name: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let Some(entry) = self.entries.iter().skip(1).find(|e| e.name == name) else {
+            return Ok(None);
+        };
+        if entry.start_sector == NO_STREAM || entry.size == 0 {
+            return Ok(Some(Vec::new()));
+        }
+
+        let data = if entry.size < self.mini_stream_cutoff {
+            self.read_mini_chain(entry.start_sector, entry.size)?
+        } else {
+            self.read_chain(entry.start_sector, Some(entry.size))?
+        };
+        Ok(Some(data))
+    }
+
+    fn read_chain(&self, start: u32, size: Option<u64>) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut out = Vec::new();
+        let mut sector = start;
+        while sector < END_OF_CHAIN {
+            out.extend_from_slice(sector_slice(self.data, self.sector_size, sector)?);
+            if out.len() > self.data.len() {
+                return Err("sector chain loops".into());
+            }
+            sector = *self
+                .fat
+                .get(sector as usize)
+                .ok_or("sector chain points outside the FAT")?;
+        }
+        if let Some(size) = size {
+            if (out.len() as u64) < size {
+                return Err("stream is shorter than its directory entry".into());
+            }
+            out.truncate(size as usize);
+        }
+        Ok(out)
+    }
+
+    fn read_mini_chain(&self, start: u32, size: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut out = Vec::new();
+        let mut sector = start;
+        while sector < END_OF_CHAIN && (out.len() as u64) < size {
+            let offset = sector as usize * self.mini_sector_size;
+            let chunk = self
+                .mini_stream
+                .get(offset..offset + self.mini_sector_size)
+                .ok_or("mini sector outside the mini stream")?;
+            out.extend_from_slice(chunk);
+            sector = *self
+                .mini_fat
+                .get(sector as usize)
+                .ok_or("mini sector chain points outside the mini FAT")?;
+        }
+        if (out.len() as u64) < size {
+            return Err("stream is shorter than its directory entry".into());
+        }
+        out.truncate(size as usize);
+        Ok(out)
+    }
+}
+
+fn sector_slice(data: &[u8], sector_size: usize, sector: u32) -> Result<&[u8], Box<dyn Error>> {
+    let offset = (sector as usize + 1) * sector_size;
+    data.get(offset..offset + sector_size)
+        .ok_or_else(|| format!("sector {sector} is outside the file").into())
+}
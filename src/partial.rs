@@ -0,0 +1,179 @@
+//! Truncated and partial MD5 digests
+//!
+//! Some systems keep only the first few hex characters of an MD5, and logs
+//! sometimes leak just part of one. A [`PartialMd5Target`] pairs a digest
+//! with a mask of the bits that are known; `md5_crack` compares only those
+//! bits and records every candidate that matches, since with a short
+//! prefix more than one usually does.
+
+use crate::{
+    events, CrackerError, CrackerEvent, GpuCracker, Kernel, ScanResult, MD5_IV, RESULT_SLOTS,
+};
+use std::error::Error;
+
+/// An MD5 digest of which only the bits set in `mask` are known
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PartialMd5Target {
+    pub digest: [u8; 16],
+    pub mask: [u8; 16],
+}
+
+impl PartialMd5Target {
+    /// A complete digest
+    pub fn full(digest: [u8; 16]) -> Self {
+        Self {
+            digest,
+            mask: [0xff; 16],
+        }
+    }
+
+    /// Only the bits of `digest` set in `mask`
+    pub fn with_mask(digest: [u8; 16], mask: [u8; 16]) -> Self {
+        let mut digest = digest;
+        for (d, m) in digest.iter_mut().zip(mask) {
+            *d &= m;
+        }
+        Self { digest, mask }
+    }
+
+    /// Parse a hex prefix of 1 to 32 characters (odd lengths keep the high
+    /// nibble of the last byte), or `<32 hex digest>/<32 hex mask>`
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let text = text.trim();
+        if let Some((digest, mask)) = text.split_once('/') {
+            return Ok(Self::with_mask(decode_hex16(digest)?, decode_hex16(mask)?));
+        }
+
+        if text.is_empty() || text.len() > 32 {
+            return Err("MD5 prefix must be 1 to 32 hex characters".into());
+        }
+        let mut digest = [0u8; 16];
+        for (i, c) in text.chars().enumerate() {
+            let nibble = c.to_digit(16).ok_or("MD5 prefix must be hex")? as u8;
+            digest[i / 2] |= nibble << if i.is_multiple_of(2) { 4 } else { 0 };
+        }
+        Ok(Self {
+            digest,
+            mask: prefix_mask(text.len()),
+        })
+    }
+
+    /// Whether every digest bit is known
+    pub fn is_full(&self) -> bool {
+        self.mask == [0xff; 16]
+    }
+
+    /// Number of digest bits compared
+    pub fn known_bits(&self) -> u32 {
+        self.mask.iter().map(|m| m.count_ones()).sum()
+    }
+
+    /// Check a digest against the known bits
+    pub fn matches(&self, digest: &[u8; 16]) -> bool {
+        digest
+            .iter()
+            .zip(self.digest.iter().zip(self.mask))
+            .all(|(d, (t, m))| (d ^ t) & m == 0)
+    }
+
+    /// Target buffer layout expected by `md5_crack` and `md5_crack_short`:
+    /// the digest, the mask, then the digest minus the MD5 initial state
+    pub(crate) fn params(&self) -> [u32; 12] {
+        let mut params = [0u32; 12];
+        for (i, (d, m)) in self
+            .digest
+            .chunks_exact(4)
+            .zip(self.mask.chunks_exact(4))
+            .enumerate()
+        {
+            params[i] = u32::from_le_bytes(d.try_into().unwrap());
+            params[4 + i] = u32::from_le_bytes(m.try_into().unwrap());
+            params[8 + i] = params[i].wrapping_sub(MD5_IV[i]);
+        }
+        params
+    }
+
+    /// Positions in `batch` of the candidates matching, in order, from the
+    /// result slots the kernel filled
+    pub(crate) fn batch_hits(&self, batch: &[&str], slots: &[i32]) -> Vec<usize> {
+        let mut hits: Vec<usize> = if slots.len() >= RESULT_SLOTS {
+            // Every slot is taken, so the GPU may have dropped hits;
+            // recheck the whole batch on the CPU instead
+            (0..batch.len())
+                .filter(|&i| self.matches(&md5::compute(batch[i]).0))
+                .collect()
+        } else {
+            slots
+                .iter()
+                .take_while(|&&hit| hit >= 0)
+                .map(|&hit| hit as usize)
+                .collect()
+        };
+        hits.sort_unstable();
+        hits
+    }
+}
+
+impl std::fmt::Display for PartialMd5Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Print prefixes back as prefixes, anything else as digest/mask
+        let nibbles = (self.known_bits() / 4) as usize;
+        if nibbles > 0 && self.mask == prefix_mask(nibbles) {
+            write!(f, "{}", &hex::encode(self.digest)[..nibbles])
+        } else {
+            write!(f, "{}/{}", hex::encode(self.digest), hex::encode(self.mask))
+        }
+    }
+}
+
+impl GpuCracker {
+    /// Find the candidates whose MD5 matches the known bits of `target`
+    ///
+    /// Matches come back in wordlist order. With `all` unset the scan stops
+    /// at the first batch that has one and only the first is returned.
+    pub fn crack_md5_partial(
+        &mut self,
+        target: &PartialMd5Target,
+        wordlist: &[&str],
+        all: bool,
+    ) -> Result<ScanResult<Vec<String>>, CrackerError> {
+        let params = target.params();
+        let mut found = Vec::new();
+        let events = self.events.clone();
+        let outcome = self.scan_with(
+            Kernel::Md5,
+            bytemuck::cast_slice(&params),
+            &[],
+            wordlist,
+            |batch, slots| {
+                let mut hits = target.batch_hits(batch, slots);
+                if !all {
+                    hits.truncate(1);
+                }
+                for i in hits {
+                    events::emit(&events, || CrackerEvent::HitFound {
+                        candidate: batch[i].to_string(),
+                    });
+                    found.push(batch[i].to_string());
+                }
+                !all && !found.is_empty()
+            },
+        )?;
+        Ok(ScanResult { found, outcome })
+    }
+}
+
+/// Mask of the first `nibbles` hex digits of a digest
+fn prefix_mask(nibbles: usize) -> [u8; 16] {
+    let mut mask = [0u8; 16];
+    for i in 0..nibbles {
+        mask[i / 2] |= 0xf << if i.is_multiple_of(2) { 4 } else { 0 };
+    }
+    mask
+}
+
+fn decode_hex16(text: &str) -> Result<[u8; 16], Box<dyn Error>> {
+    hex::decode(text)?
+        .try_into()
+        .map_err(|_| "digest and mask must be 32 hex characters".into())
+}
@@ -0,0 +1,229 @@
+//! Generic PBKDF2 password hashes
+//!
+//! Supports HMAC-SHA1 and HMAC-SHA256 as the PRF, in Django's
+//! `pbkdf2_sha256$iterations$salt$hash` format and passlib's
+//! `$pbkdf2-sha256$rounds$salt$hash` (plus the `pbkdf2_sha1` and `$pbkdf2$`
+//! SHA1 variants of both). `pbkdf2_crack` runs the iterations in windows of
+//! [`ITERATIONS_PER_DISPATCH`](crate::ITERATIONS_PER_DISPATCH), so even
+//! hundreds of thousands of rounds never make one long dispatch.
+//!
+//! Only the first PRF output block is derived and compared; the rest of a
+//! longer derived key would just repeat the work with another block index.
+
+use crate::{
+    append_hmac_sha1_blocks_for, events, CrackerError, CrackerEvent, GpuCracker, Kernel, ScanResult,
+};
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use std::error::Error;
+
+/// Longest salt that fits the target buffer
+const MAX_SALT_LEN: usize = 1024;
+/// Most iterations accepted: past any hash in use, and well short of
+/// where the iteration windows' ends would overflow a `u32`
+const MAX_ITERATIONS: u32 = 100_000_000;
+/// Shortest derived key accepted, so a match still means something
+const MIN_HASH_LEN: usize = 8;
+
+/// Pseudo-random function PBKDF2 is built on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pbkdf2Prf {
+    HmacSha1,
+    HmacSha256,
+}
+
+impl Pbkdf2Prf {
+    /// Output size of one PRF block in bytes
+    pub fn digest_len(self) -> usize {
+        match self {
+            Pbkdf2Prf::HmacSha1 => 20,
+            Pbkdf2Prf::HmacSha256 => 32,
+        }
+    }
+}
+
+/// How a PBKDF2 hash is written out
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pbkdf2Format {
+    /// `pbkdf2_sha256$iterations$salt$base64`, with the salt as plain text
+    Django,
+    /// `$pbkdf2-sha256$rounds$salt$hash`, both in passlib's adapted base64
+    Passlib,
+}
+
+/// A PBKDF2 password hash
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pbkdf2Target {
+    pub format: Pbkdf2Format,
+    pub prf: Pbkdf2Prf,
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    /// The derived key
+    pub hash: Vec<u8>,
+}
+
+impl Pbkdf2Target {
+    /// Parse a Django or passlib PBKDF2 hash, optionally preceded by a
+    /// username and `:`
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let start = line
+            .find("$pbkdf2")
+            .or_else(|| line.find("pbkdf2_"))
+            .ok_or("no PBKDF2 hash found")?;
+        let hash = line[start..]
+            .split(|c: char| c == ':' || c.is_whitespace())
+            .next()
+            .unwrap();
+
+        let (format, fields) = match hash.strip_prefix('$') {
+            Some(rest) => (Pbkdf2Format::Passlib, rest.split('$').collect::<Vec<_>>()),
+            None => (Pbkdf2Format::Django, hash.split('$').collect()),
+        };
+        if fields.len() != 4 {
+            return Err("expected algorithm, iterations, salt and hash fields".into());
+        }
+
+        let prf = match (format, fields[0]) {
+            (Pbkdf2Format::Django, "pbkdf2_sha1") | (Pbkdf2Format::Passlib, "pbkdf2") => {
+                Pbkdf2Prf::HmacSha1
+            }
+            (Pbkdf2Format::Django, "pbkdf2_sha256") | (Pbkdf2Format::Passlib, "pbkdf2-sha256") => {
+                Pbkdf2Prf::HmacSha256
+            }
+            (_, other) => return Err(format!("unsupported PBKDF2 variant '{other}'").into()),
+        };
+        let iterations: u32 = fields[1].parse()?;
+        if iterations == 0 {
+            return Err("PBKDF2 needs at least one iteration".into());
+        }
+        if iterations > MAX_ITERATIONS {
+            return Err(format!("more than {MAX_ITERATIONS} iterations are not supported").into());
+        }
+
+        let (salt, hash) = match format {
+            Pbkdf2Format::Django => (fields[2].as_bytes().to_vec(), STANDARD.decode(fields[3])?),
+            Pbkdf2Format::Passlib => (decode_ab64(fields[2])?, decode_ab64(fields[3])?),
+        };
+        if salt.len() > MAX_SALT_LEN {
+            return Err(format!("salts longer than {MAX_SALT_LEN} bytes are not supported").into());
+        }
+        if hash.len() < MIN_HASH_LEN {
+            return Err("derived key is too short".into());
+        }
+
+        Ok(Self {
+            format,
+            prf,
+            iterations,
+            salt,
+            hash,
+        })
+    }
+
+    /// Parse every hash of a file, skipping lines that don't match
+    pub fn parse_dump(text: &str) -> Vec<Self> {
+        text.lines().filter_map(|l| Self::parse(l).ok()).collect()
+    }
+
+    /// Target buffer layout expected by `pbkdf2_crack`
+    fn params(&self) -> Vec<u32> {
+        let compared = &self.hash[..self.hash.len().min(self.prf.digest_len())];
+        let mut params = vec![0u32; 16];
+        params[0] = match self.prf {
+            Pbkdf2Prf::HmacSha1 => 0,
+            Pbkdf2Prf::HmacSha256 => 1,
+        };
+        params[1] = self.iterations;
+        params[2] = (compared.len() / 4) as u32;
+        for (i, word) in compared.chunks_exact(4).enumerate() {
+            params[8 + i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+
+        // First block: U1 = PRF(password, salt || INT(1))
+        let mut salt = self.salt.clone();
+        salt.extend_from_slice(&1u32.to_be_bytes());
+        let salt_blocks = append_hmac_sha1_blocks_for(&salt, &mut params);
+        params[3] = salt_blocks;
+        params
+    }
+}
+
+impl std::fmt::Display for Pbkdf2Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.format {
+            Pbkdf2Format::Django => {
+                let name = match self.prf {
+                    Pbkdf2Prf::HmacSha1 => "pbkdf2_sha1",
+                    Pbkdf2Prf::HmacSha256 => "pbkdf2_sha256",
+                };
+                write!(
+                    f,
+                    "{name}${}${}${}",
+                    self.iterations,
+                    String::from_utf8_lossy(&self.salt),
+                    STANDARD.encode(&self.hash)
+                )
+            }
+            Pbkdf2Format::Passlib => {
+                let name = match self.prf {
+                    Pbkdf2Prf::HmacSha1 => "pbkdf2",
+                    Pbkdf2Prf::HmacSha256 => "pbkdf2-sha256",
+                };
+                write!(
+                    f,
+                    "${name}${}${}${}",
+                    self.iterations,
+                    encode_ab64(&self.salt),
+                    encode_ab64(&self.hash)
+                )
+            }
+        }
+    }
+}
+
+impl GpuCracker {
+    /// Crack a PBKDF2-HMAC-SHA1 or PBKDF2-HMAC-SHA256 hash
+    ///
+    /// Candidates longer than 64 bytes are skipped.
+    pub fn crack_pbkdf2(
+        &mut self,
+        target: &Pbkdf2Target,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        if target.iterations > MAX_ITERATIONS {
+            return Err(CrackerError::InvalidTarget(format!(
+                "more than {MAX_ITERATIONS} iterations are not supported"
+            )));
+        }
+        let params = target.params();
+        let mut found = None;
+        let events = self.events.clone();
+        let outcome = self.scan_iterated(
+            Kernel::Pbkdf2,
+            bytemuck::cast_slice(&params),
+            &[],
+            target.iterations,
+            wordlist,
+            |batch, slots| {
+                if slots[0] >= 0 {
+                    let candidate = batch[slots[0] as usize];
+                    events::emit(&events, || CrackerEvent::HitFound {
+                        candidate: candidate.to_string(),
+                    });
+                    found = Some(candidate.to_string());
+                }
+                found.is_some()
+            },
+        )?;
+        Ok(ScanResult { found, outcome })
+    }
+}
+
+/// passlib's "adapted base64": `.` instead of `+` and no padding
+fn decode_ab64(text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(STANDARD_NO_PAD.decode(text.replace('.', "+"))?)
+}
+
+fn encode_ab64(bytes: &[u8]) -> String {
+    STANDARD_NO_PAD.encode(bytes).replace('+', ".")
+}
@@ -0,0 +1,142 @@
+//! Where the time of the cracking pipeline goes
+//!
+//! With profiling on ([`GpuOptions::profile`](crate::GpuOptions::profile)
+//! or [`GpuCracker::set_profiling`](crate::GpuCracker::set_profiling)),
+//! every dispatch of the normal pipeline gets a pair of timestamp queries
+//! from the query set its buffer set owns and reuses. Every batch read back
+//! is then added to its kernel's totals, split into three stages:
+//!
+//! - upload: host time writing the batch's candidates to the GPU
+//! - kernel: GPU time of its dispatches, from the timestamps
+//! - readback: host time waiting for its results and copying them out,
+//!   which overlaps with later batches running on the GPU
+
+use std::fmt;
+use std::time::Duration;
+
+/// Per-kernel totals of every batch profiled so far, from
+/// [`GpuCracker::profile_report`](crate::GpuCracker::profile_report)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProfileReport {
+    /// One entry per kernel, in the order they first ran
+    pub kernels: Vec<KernelProfile>,
+}
+
+/// Time one kernel's batches spent in each stage of the pipeline
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct KernelProfile {
+    /// Shader entry point
+    pub kernel: &'static str,
+    pub batches: usize,
+    pub dispatches: usize,
+    pub candidates: usize,
+    pub upload: Duration,
+    /// GPU time of the timed dispatches; `None` on devices without
+    /// timestamp queries
+    pub kernel_time: Option<Duration>,
+    /// Dispatches whose timestamps are in `kernel_time`; a batch split
+    /// into more dispatches than its buffer set has queries for leaves the
+    /// rest untimed
+    pub timed_dispatches: usize,
+    pub readback: Duration,
+}
+
+impl KernelProfile {
+    /// Candidates per second of GPU time, when every dispatch was timed
+    pub fn gpu_hashes_per_sec(&self) -> Option<f64> {
+        let kernel_time = self.kernel_time.filter(|time| !time.is_zero())?;
+        (self.timed_dispatches == self.dispatches)
+            .then(|| self.candidates as f64 / kernel_time.as_secs_f64())
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>8} {:>10} {:>12} {:>12} {:>12} {:>12} {:>14}",
+            "kernel",
+            "batches",
+            "dispatches",
+            "candidates",
+            "upload",
+            "kernel",
+            "readback",
+            "GPU H/s"
+        )?;
+        for profile in &self.kernels {
+            let kernel_time = profile
+                .kernel_time
+                .map_or_else(|| "-".to_string(), |time| format!("{time:.2?}"));
+            let rate = profile
+                .gpu_hashes_per_sec()
+                .map_or_else(|| "-".to_string(), |rate| format!("{rate:.0}"));
+            writeln!(
+                f,
+                "{:<24} {:>8} {:>10} {:>12} {:>12} {:>12} {:>12} {:>14}",
+                profile.kernel,
+                profile.batches,
+                profile.dispatches,
+                profile.candidates,
+                format!("{:.2?}", profile.upload),
+                kernel_time,
+                format!("{:.2?}", profile.readback),
+                rate
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Timing of one batch, as read back
+pub(crate) struct BatchTiming {
+    pub candidates: usize,
+    pub dispatches: usize,
+    pub upload: Duration,
+    /// GPU time and number of the dispatches that were timed
+    pub kernel: Option<(Duration, usize)>,
+    pub readback: Duration,
+}
+
+/// Running totals behind a [`ProfileReport`]
+#[derive(Default)]
+pub(crate) struct Profiler {
+    report: ProfileReport,
+}
+
+impl Profiler {
+    pub fn record(&mut self, kernel: &'static str, batch: BatchTiming) {
+        let kernels = &mut self.report.kernels;
+        let profile = match kernels.iter().position(|profile| profile.kernel == kernel) {
+            Some(i) => &mut kernels[i],
+            None => {
+                kernels.push(KernelProfile {
+                    kernel,
+                    batches: 0,
+                    dispatches: 0,
+                    candidates: 0,
+                    upload: Duration::ZERO,
+                    kernel_time: None,
+                    timed_dispatches: 0,
+                    readback: Duration::ZERO,
+                });
+                kernels.last_mut().expect("just pushed")
+            }
+        };
+
+        profile.batches += 1;
+        profile.dispatches += batch.dispatches;
+        profile.candidates += batch.candidates;
+        profile.upload += batch.upload;
+        profile.readback += batch.readback;
+        if let Some((time, dispatches)) = batch.kernel {
+            *profile.kernel_time.get_or_insert(Duration::ZERO) += time;
+            profile.timed_dispatches += dispatches;
+        }
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        self.report.clone()
+    }
+}
@@ -0,0 +1,192 @@
+//! Kernels loaded from SPIR-V at runtime
+//!
+//! The shader crate is compiled into the binary, so trying out a change to
+//! a kernel normally means rebuilding the host as well. A [`CustomShader`]
+//! is a SPIR-V module read at runtime instead, handed to the cracker with
+//! [`GpuOptions::shader`](crate::GpuOptions::shader) or
+//! [`GpuCracker::with_shader`](crate::GpuCracker::with_shader). Its named
+//! entry point runs in place of `md5_crack`; every other kernel the module
+//! exports under its usual name (a rebuilt shader crate exports them all)
+//! replaces the built-in one, and the rest still come from the built-in
+//! shader.
+//!
+//! A module is checked when it is loaded, after naga's SPIR-V frontend has
+//! parsed it as wgpu will: the entry point must be a compute shader with 64
+//! invocations per workgroup, and every resource the module declares must
+//! match the cracker's bind group layout:
+//!
+//! | binding | buffer          | type            |
+//! |---------|-----------------|-----------------|
+//! | 0       | packed messages | storage         |
+//! | 1       | target          | storage         |
+//! | 2       | results         | storage         |
+//! | 3       | dispatch        | uniform (uvec4) |
+//! | 4       | message offsets | storage         |
+//! | 5       | per-candidate   | storage         |
+//!
+//! all in descriptor set 0. The device validates the rest (read-only
+//! bindings written to, say) when the pipeline is created.
+
+use crate::{CrackerError, DEFAULT_WORKGROUP_SIZE};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use wgpu::naga::{self, front::spv};
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// Binding of the dispatch uniform; every other binding is a storage buffer
+const UNIFORM_BINDING: u32 = 3;
+/// Bindings in the cracker's bind group layout
+const BINDINGS: u32 = 6;
+
+/// A SPIR-V module whose kernels run in place of the built-in ones
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomShader {
+    words: Arc<[u32]>,
+    entry_point: String,
+    // Compute entry points of the module, with their workgroup size
+    entry_points: HashMap<String, [u32; 3]>,
+}
+
+impl CustomShader {
+    /// Check a SPIR-V module and pick the entry point to run as the MD5
+    /// kernel
+    pub fn from_spirv(bytes: &[u8], entry_point: &str) -> Result<Self, CrackerError> {
+        if !bytes.len().is_multiple_of(4) {
+            return Err(CrackerError::InvalidShader(format!(
+                "{} bytes is not a whole number of SPIR-V words",
+                bytes.len()
+            )));
+        }
+        let mut words: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        match words.first() {
+            Some(&SPIRV_MAGIC) => {}
+            // Written on a big-endian machine
+            Some(&magic) if magic.swap_bytes() == SPIRV_MAGIC => {
+                words.iter_mut().for_each(|word| *word = word.swap_bytes());
+            }
+            _ => {
+                return Err(CrackerError::InvalidShader(
+                    "not a SPIR-V module (bad magic number)".into(),
+                ))
+            }
+        }
+
+        // Parsed as wgpu parses it when the shader module is created
+        let options = spv::Options {
+            adjust_coordinate_space: false,
+            strict_capabilities: true,
+            block_ctx_dump_prefix: None,
+        };
+        let module = spv::Frontend::new(words.iter().copied(), &options)
+            .parse()
+            .map_err(|e| CrackerError::InvalidShader(format!("invalid SPIR-V: {e}")))?;
+        check_bindings(&module).map_err(CrackerError::InvalidShader)?;
+        let entry_points = module
+            .entry_points
+            .iter()
+            .filter(|entry_point| entry_point.stage == naga::ShaderStage::Compute)
+            .map(|entry_point| (entry_point.name.clone(), entry_point.workgroup_size))
+            .collect::<HashMap<_, _>>();
+        match entry_points.get(entry_point) {
+            None => {
+                return Err(CrackerError::InvalidShader(format!(
+                    "the module has no compute entry point '{entry_point}'"
+                )))
+            }
+            Some(&[x, y, z]) if [x, y, z] != [DEFAULT_WORKGROUP_SIZE, 1, 1] => {
+                return Err(CrackerError::InvalidShader(format!(
+                    "entry point '{entry_point}' has a workgroup size of {x}x{y}x{z}, \
+                     not {DEFAULT_WORKGROUP_SIZE}"
+                )))
+            }
+            Some(_) => {}
+        }
+
+        Ok(Self {
+            words: words.into(),
+            entry_point: entry_point.to_string(),
+            entry_points,
+        })
+    }
+
+    /// Read a `.spv` file, as [`from_spirv`](Self::from_spirv)
+    pub fn load(path: impl AsRef<Path>, entry_point: &str) -> Result<Self, CrackerError> {
+        let bytes = fs::read(path).map_err(CrackerError::ShaderFile)?;
+        Self::from_spirv(&bytes, entry_point)
+    }
+
+    /// Entry point run as the MD5 kernel
+    pub fn entry_point(&self) -> &str {
+        &self.entry_point
+    }
+
+    /// Whether the module has a compute entry point `name` with
+    /// `workgroup_size` invocations per workgroup
+    pub(crate) fn exports(&self, name: &str, workgroup_size: u32) -> bool {
+        self.entry_points.get(name) == Some(&[workgroup_size, 1, 1])
+    }
+
+    pub(crate) fn words(&self) -> &[u32] {
+        &self.words
+    }
+
+    /// FNV-1a hash of the module and entry point, which tells autotuned
+    /// sizes measured with one kernel from those of another
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let bytes = self
+            .words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .chain(self.entry_point.bytes());
+        bytes.fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+/// Check every resource the module declares against the bind group layout
+fn check_bindings(module: &naga::Module) -> Result<(), String> {
+    for (_, variable) in module.global_variables.iter() {
+        // Private, workgroup and push constant variables are not bound
+        if !matches!(
+            variable.space,
+            naga::AddressSpace::Uniform
+                | naga::AddressSpace::Storage { .. }
+                | naga::AddressSpace::Handle
+        ) {
+            continue;
+        }
+        let name = variable.name.as_deref().unwrap_or("(unnamed)");
+        let Some(naga::ResourceBinding { group, binding }) = variable.binding else {
+            return Err(format!("resource {name} lacks a descriptor set or binding"));
+        };
+        if group != 0 || binding >= BINDINGS {
+            return Err(format!(
+                "resource at set {group}, binding {binding} is not in the cracker's layout \
+                 (set 0, bindings 0-{})",
+                BINDINGS - 1
+            ));
+        }
+
+        let uniform = match variable.space {
+            naga::AddressSpace::Uniform => true,
+            naga::AddressSpace::Storage { .. } => false,
+            _ => return Err(format!("binding {binding} is not a buffer")),
+        };
+        if uniform != (binding == UNIFORM_BINDING) {
+            let expected = if binding == UNIFORM_BINDING {
+                "a uniform"
+            } else {
+                "a storage"
+            };
+            return Err(format!("binding {binding} must be {expected} buffer"));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+//! Vanity / proof-of-work search
+//!
+//! Instead of reading a wordlist, `md5_vanity` builds candidates on the GPU
+//! from a fixed prefix and a [`CandidateGenerator`], and keeps those whose
+//! MD5 matches the known bits of a [`PartialMd5Target`]: "find a string
+//! starting with `pow-` whose MD5 begins with `000000`". Solutions are
+//! streamed back in keyspace order until the requested number is found.
+
+use crate::{
+    events, CrackerError, CrackerEvent, GpuCracker, Kernel, PartialMd5Target, ScanOutcome,
+    ScanResult, RESULT_SLOTS,
+};
+use std::error::Error;
+
+/// Candidates must fit one MD5 block
+const MAX_CANDIDATE_LEN: usize = 55;
+/// Mask positions the kernel can enumerate
+const MAX_POSITIONS: usize = 32;
+/// Bytes of charset table after word 128 of the target buffer
+const CHARSET_TABLE_LEN: usize = 1536;
+
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SPECIAL: &[u8] = b" !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// How the part after the prefix is enumerated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CandidateGenerator {
+    /// One charset per position, as from a hashcat-style mask
+    Mask(Vec<Vec<u8>>),
+    /// Every string over `charset`, shortest first, up to `max_len`
+    /// characters
+    Counter { charset: Vec<u8>, max_len: usize },
+}
+
+impl CandidateGenerator {
+    /// Parse a hashcat-style mask: `?l` `?u` `?d` `?s` `?a` `?h` `?H` for
+    /// the usual classes, `??` for a literal `?`, anything else literally
+    pub fn parse_mask(mask: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(CandidateGenerator::Mask(mask_positions(mask)?))
+    }
+
+    /// Expand `?`-classes in a charset description (`?d`, `?l?d`, `abc`)
+    /// into the characters themselves
+    pub fn parse_charset(spec: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut charset: Vec<u8> = Vec::new();
+        for byte in mask_positions(spec)?.into_iter().flatten() {
+            if !charset.contains(&byte) {
+                charset.push(byte);
+            }
+        }
+        Ok(charset)
+    }
+
+    /// Number of candidates the generator runs through, if that fits in a
+    /// `usize`
+    pub fn keyspace(&self) -> Option<usize> {
+        self.masks()
+            .iter()
+            .try_fold(0usize, |total, mask| total.checked_add(mask_size(mask)?))
+    }
+
+    /// The charsets of each mask the generator runs through, in order
+    fn masks(&self) -> Vec<Vec<&[u8]>> {
+        match self {
+            CandidateGenerator::Mask(positions) => {
+                vec![positions.iter().map(Vec::as_slice).collect()]
+            }
+            CandidateGenerator::Counter { charset, max_len } => (1..=*max_len)
+                .map(|len| vec![charset.as_slice(); len])
+                .collect(),
+        }
+    }
+}
+
+/// The charset of each position of a hashcat-style mask
+fn mask_positions(mask: &str) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    let mut positions = Vec::new();
+    let mut chars = mask.chars();
+    while let Some(c) = chars.next() {
+        if c != '?' {
+            let mut literal = [0u8; 4];
+            positions.push(c.encode_utf8(&mut literal).as_bytes().to_vec());
+            continue;
+        }
+        let class = chars.next().ok_or("mask ends in '?'")?;
+        positions.push(charset_class(class)?);
+    }
+    if positions.is_empty() {
+        return Err("mask is empty".into());
+    }
+    Ok(positions)
+}
+
+/// A prefix, a candidate generator and the digest bits to match
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VanitySearch {
+    prefix: String,
+    generator: CandidateGenerator,
+    target: PartialMd5Target,
+}
+
+impl VanitySearch {
+    pub fn new(
+        prefix: &str,
+        generator: CandidateGenerator,
+        target: PartialMd5Target,
+    ) -> Result<Self, Box<dyn Error>> {
+        let masks = generator.masks();
+        let longest = masks.iter().map(Vec::len).max().unwrap_or(0);
+        if longest == 0 {
+            return Err("the generator produces no characters".into());
+        }
+        if longest > MAX_POSITIONS {
+            return Err(format!("at most {MAX_POSITIONS} generated characters").into());
+        }
+        if prefix.len() + longest > MAX_CANDIDATE_LEN {
+            return Err(format!("candidates are limited to {MAX_CANDIDATE_LEN} bytes").into());
+        }
+        for charset in masks.iter().flatten() {
+            if charset.is_empty() || charset.len() > 256 || !charset.is_ascii() {
+                return Err("charsets must hold 1 to 256 ASCII characters".into());
+            }
+        }
+        for mask in &masks {
+            charset_table(mask)?;
+        }
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            generator,
+            target,
+        })
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn generator(&self) -> &CandidateGenerator {
+        &self.generator
+    }
+
+    pub fn target(&self) -> &PartialMd5Target {
+        &self.target
+    }
+
+    /// Target buffer layout expected by `md5_vanity`, starting at `digits`
+    fn params(&self, mask: &[&[u8]], digits: &[u32]) -> Vec<u32> {
+        let (table, offsets) = charset_table(mask).expect("checked in new");
+        let mut bytes = vec![0u8; 512 + CHARSET_TABLE_LEN];
+        bytes[64..64 + self.prefix.len()].copy_from_slice(self.prefix.as_bytes());
+        bytes[512..512 + table.len()].copy_from_slice(&table);
+
+        let mut params: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+            .collect();
+        params[..8].copy_from_slice(&self.target.params()[..8]);
+        params[8] = self.prefix.len() as u32;
+        params[9] = mask.len() as u32;
+        for (i, &charset) in mask.iter().enumerate() {
+            params[32 + i] = digits[i];
+            params[64 + i] = charset.len() as u32;
+            params[96 + i] = offsets[i];
+        }
+        params
+    }
+
+    /// The candidate `index` steps after `digits` in `mask`, if it is still
+    /// inside the keyspace
+    fn candidate(&self, mask: &[&[u8]], digits: &[u32], index: usize) -> Option<String> {
+        let mut digits = digits.to_vec();
+        if !advance(&mut digits, mask, index) {
+            return None;
+        }
+        let mut candidate = self.prefix.clone();
+        for (&digit, charset) in digits.iter().zip(mask) {
+            candidate.push(charset[digit as usize] as char);
+        }
+        Some(candidate)
+    }
+}
+
+impl GpuCracker {
+    /// Search for candidates matching `search`, handing each solution to
+    /// `on_solution` in keyspace order until `count` have been found or the
+    /// keyspace runs out
+    ///
+    /// Returns the number of solutions found, and why the search ended.
+    pub fn vanity_search(
+        &mut self,
+        search: &VanitySearch,
+        count: usize,
+        mut on_solution: impl FnMut(&str),
+    ) -> Result<ScanResult<usize>, CrackerError> {
+        if count == 0 {
+            return Ok(ScanResult {
+                found: 0,
+                outcome: ScanOutcome::Finished,
+            });
+        }
+
+        let batch_size = self.batch_size_for(Kernel::Md5Vanity);
+        let masks = search.generator.masks();
+        let mut mask_index = 0;
+        let mut digits = vec![0u32; masks[0].len()];
+        let next_batch = || {
+            let mask = masks.get(mask_index)?;
+            let params = search.params(mask, &digits);
+            // The last batch of a mask only runs what is left of it, so
+            // progress counts real candidates
+            let mut candidates = batch_size;
+            if !advance(&mut digits, mask, batch_size) {
+                let left = mask_size(mask).map_or(batch_size, |size| size % batch_size);
+                candidates = if left == 0 { batch_size } else { left };
+                mask_index += 1;
+                digits = vec![0; masks.get(mask_index).map_or(0, Vec::len)];
+            }
+            Some((params, candidates))
+        };
+
+        let mut found = 0;
+        let events = self.events.clone();
+        let keyspace = search.generator.keyspace();
+        let outcome =
+            self.generate_with(Kernel::Md5Vanity, keyspace, next_batch, |params, slots| {
+                // Each mask has a different length, so the batch's is enough
+                // to tell which one it came from
+                let positions = params[9] as usize;
+                let mask = masks
+                    .iter()
+                    .find(|m| m.len() == positions)
+                    .expect("batch parameters come from one of the masks");
+                let start = &params[32..32 + positions];
+
+                let mut hits: Vec<usize> = if slots.len() >= RESULT_SLOTS {
+                    // Every slot is taken, so the GPU may have dropped hits;
+                    // recheck the whole batch on the CPU instead
+                    (0..batch_size).collect()
+                } else {
+                    slots
+                        .iter()
+                        .take_while(|&&hit| hit >= 0)
+                        .map(|&hit| hit as usize)
+                        .collect()
+                };
+                hits.sort_unstable();
+
+                for index in hits {
+                    let Some(candidate) = search.candidate(mask, start, index) else {
+                        continue;
+                    };
+                    if search.target.matches(&md5::compute(&candidate).0) {
+                        events::emit(&events, || CrackerEvent::HitFound {
+                            candidate: candidate.clone(),
+                        });
+                        on_solution(&candidate);
+                        found += 1;
+                        if found == count {
+                            return true;
+                        }
+                    }
+                }
+                false
+            })?;
+        Ok(ScanResult { found, outcome })
+    }
+}
+
+/// Number of candidates in `mask`, if that fits in a `usize`
+fn mask_size(mask: &[&[u8]]) -> Option<usize> {
+    mask.iter()
+        .try_fold(1usize, |size, charset| size.checked_mul(charset.len()))
+}
+
+/// Move `digits` forward by `steps` in the mixed-radix keyspace of `mask`,
+/// returning `false` if that runs past the end
+fn advance(digits: &mut [u32], mask: &[&[u8]], steps: usize) -> bool {
+    let mut carry = steps;
+    for (digit, charset) in digits.iter_mut().zip(mask).rev() {
+        if carry == 0 {
+            break;
+        }
+        let value = *digit as usize + carry;
+        *digit = (value % charset.len()) as u32;
+        carry = value / charset.len();
+    }
+    carry == 0
+}
+
+/// The charsets of a mask as one table with repeats stored once, and the
+/// offset of each position's charset in it
+fn charset_table(mask: &[&[u8]]) -> Result<(Vec<u8>, Vec<u32>), Box<dyn Error>> {
+    let mut table = Vec::new();
+    let mut offsets = Vec::with_capacity(mask.len());
+    let mut stored: Vec<(&[u8], u32)> = Vec::new();
+    for &charset in mask {
+        let offset = match stored.iter().find(|(c, _)| *c == charset) {
+            Some(&(_, offset)) => offset,
+            None => {
+                let offset = table.len() as u32;
+                table.extend_from_slice(charset);
+                stored.push((charset, offset));
+                offset
+            }
+        };
+        offsets.push(offset);
+    }
+    if table.len() > CHARSET_TABLE_LEN {
+        return Err(format!("charsets exceed {CHARSET_TABLE_LEN} bytes in total").into());
+    }
+    Ok((table, offsets))
+}
+
+fn charset_class(class: char) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(match class {
+        'l' => LOWER.to_vec(),
+        'u' => UPPER.to_vec(),
+        'd' => DIGITS.to_vec(),
+        's' => SPECIAL.to_vec(),
+        'a' => [LOWER, UPPER, DIGITS, SPECIAL].concat(),
+        'h' => b"0123456789abcdef".to_vec(),
+        'H' => b"0123456789ABCDEF".to_vec(),
+        '?' => b"?".to_vec(),
+        other => return Err(format!("unknown mask class '?{other}'").into()),
+    })
+}
@@ -0,0 +1,219 @@
+//! Windows password hashes: LM, NTLM and pwdump-style dumps
+//!
+//! LM uppercases the password, splits it into two 7-character halves and
+//! uses each half as a DES key to encrypt `KGS!@#$%`. Both halves are cracked
+//! independently on the GPU, which is cheap, and the uppercase result is then
+//! case-toggled against the NTLM hash (MD4 of the UTF-16LE password) to get
+//! the real password.
+
+use crate::{CrackerError, CrackerEvent, GpuCracker, Kernel, ScanOutcome, ScanResult};
+use std::error::Error;
+
+/// LM hash of an empty 7-character half
+pub const EMPTY_LM_HALF: [u8; 8] = [0xAA, 0xD3, 0xB4, 0x35, 0xB5, 0x14, 0x04, 0xEE];
+
+const LM_PLAINTEXT: &[u8; 8] = b"KGS!@#$%";
+
+// DES initial permutation (1-based bit positions)
+const DES_IP: [u32; 64] = [
+    58, 50, 42, 34, 26, 18, 10, 2, 60, 52, 44, 36, 28, 20, 12, 4, 62, 54, 46, 38, 30, 22, 14, 6,
+    64, 56, 48, 40, 32, 24, 16, 8, 57, 49, 41, 33, 25, 17, 9, 1, 59, 51, 43, 35, 27, 19, 11, 3, 61,
+    53, 45, 37, 29, 21, 13, 5, 63, 55, 47, 39, 31, 23, 15, 7,
+];
+
+/// One account from a pwdump/secretsdump file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PwdumpEntry {
+    pub username: String,
+    pub rid: Option<u32>,
+    /// `None` when no LM hash is stored (empty, disabled or blank)
+    pub lm: Option<[u8; 16]>,
+    pub nt: [u8; 16],
+}
+
+impl PwdumpEntry {
+    /// Parse a `user:rid:lmhash:nthash:::` line
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let fields: Vec<&str> = line.trim().split(':').collect();
+        if fields.len() < 4 {
+            return Err("expected user:rid:lmhash:nthash".into());
+        }
+
+        let lm = if fields[2].starts_with("NO PASSWORD") || fields[2].starts_with('*') {
+            None
+        } else {
+            Some(decode_16(fields[2])?)
+        };
+        // An LM hash of two empty halves means LM storage was disabled
+        let lm = lm.filter(|h| h[..8] != EMPTY_LM_HALF || h[8..] != EMPTY_LM_HALF);
+
+        Ok(Self {
+            username: fields[0].to_string(),
+            rid: fields[1].parse().ok(),
+            lm,
+            nt: decode_16(fields[3])?,
+        })
+    }
+
+    /// Parse every account of a dump, skipping lines that don't match
+    pub fn parse_dump(text: &str) -> Vec<Self> {
+        text.lines().filter_map(|l| Self::parse(l).ok()).collect()
+    }
+}
+
+/// Most ASCII letters [`toggle_case_variants`] toggles, as many as an LM
+/// password (14 characters) can have; 2^14 variants
+pub const MAX_TOGGLE_LETTERS: usize = 14;
+
+/// Every upper/lower case combination of the ASCII letters in `password`,
+/// which may have at most [`MAX_TOGGLE_LETTERS`] of them
+pub fn toggle_case_variants(password: &str) -> Result<Vec<String>, CrackerError> {
+    let letters: Vec<usize> = password
+        .char_indices()
+        .filter(|(_, c)| c.is_ascii_alphabetic())
+        .map(|(i, _)| i)
+        .collect();
+    if letters.len() > MAX_TOGGLE_LETTERS {
+        return Err(CrackerError::InvalidTarget(format!(
+            "{} letters to toggle, more than the {MAX_TOGGLE_LETTERS} an LM password has",
+            letters.len()
+        )));
+    }
+
+    let mut variants = Vec::with_capacity(1 << letters.len());
+    for mask in 0u32..(1 << letters.len()) {
+        let mut bytes = password.as_bytes().to_vec();
+        for (bit, &pos) in letters.iter().enumerate() {
+            bytes[pos] = if mask & (1 << bit) != 0 {
+                bytes[pos].to_ascii_lowercase()
+            } else {
+                bytes[pos].to_ascii_uppercase()
+            };
+        }
+        // Only ASCII bytes were changed, so this stays valid UTF-8
+        variants.push(String::from_utf8(bytes).unwrap());
+    }
+    Ok(variants)
+}
+
+impl GpuCracker {
+    /// Crack an NTLM hash
+    pub fn crack_ntlm(
+        &mut self,
+        nt_hash: &[u8; 16],
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        self.crack_with(Kernel::Ntlm, nt_hash, &[], wordlist)
+    }
+
+    /// Crack an LM hash, returning the uppercased password
+    ///
+    /// Both halves are searched for in the same pass, so a wordlist entry
+    /// can supply either half of the password.
+    pub fn crack_lm(
+        &mut self,
+        lm_hash: &[u8; 16],
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let mut found: [Option<String>; 2] = [None, None];
+        for (half, slot) in found.iter_mut().enumerate() {
+            if lm_hash[half * 8..half * 8 + 8] == EMPTY_LM_HALF {
+                *slot = Some(String::new());
+            }
+        }
+
+        // Halves of the empty password need no scan
+        let mut outcome = ScanOutcome::Finished;
+        if found.iter().any(Option::is_none) {
+            let mut params = [0u32; 6];
+            params[..2].copy_from_slice(&des_ip(&lm_hash[..8]));
+            params[2..4].copy_from_slice(&des_ip(&lm_hash[8..]));
+            params[4..].copy_from_slice(&des_ip(LM_PLAINTEXT));
+
+            outcome = self.scan_with(
+                Kernel::Lm,
+                bytemuck::cast_slice(&params),
+                &[],
+                wordlist,
+                |batch, slots| {
+                    for (slot, &hit) in found.iter_mut().zip(slots.iter()) {
+                        if slot.is_none() && hit >= 0 {
+                            let word = batch[hit as usize / 2];
+                            *slot = Some(lm_half(word, hit as usize % 2));
+                        }
+                    }
+                    found.iter().all(Option::is_some)
+                },
+            )?;
+        }
+
+        let password = match found {
+            [Some(first), Some(second)] => first + &second,
+            _ => {
+                return Ok(ScanResult {
+                    found: None,
+                    outcome,
+                })
+            }
+        };
+        self.emit(|| CrackerEvent::HitFound {
+            candidate: password.clone(),
+        });
+        Ok(ScanResult {
+            found: Some(password),
+            outcome,
+        })
+    }
+
+    /// Crack a pwdump entry: LM first when present, then toggle the case of
+    /// the result against the NT hash. Falls back to NTLM with the wordlist.
+    ///
+    /// The outcome is that of the last scan run; a cancelled token or a
+    /// passed deadline stops every later one before its first batch.
+    pub fn crack_pwdump(
+        &mut self,
+        entry: &PwdumpEntry,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        if let Some(lm) = &entry.lm {
+            // LM passwords are at most 14 characters, so every one the
+            // kernel finds has few enough letters to toggle
+            if let Some(upper) = self.crack_lm(lm, wordlist)?.found {
+                let variants = toggle_case_variants(&upper)?;
+                let refs: Vec<&str> = variants.iter().map(String::as_str).collect();
+                let result = self.crack_ntlm(&entry.nt, &refs)?;
+                if result.found.is_some() {
+                    return Ok(result);
+                }
+            }
+        }
+
+        self.crack_ntlm(&entry.nt, wordlist)
+    }
+}
+
+/// The uppercased 7-character `half` of a candidate, as the LM kernel saw it
+fn lm_half(word: &str, half: usize) -> String {
+    let upper = word.to_ascii_uppercase();
+    let bytes = upper.as_bytes();
+    let start = (half * 7).min(bytes.len());
+    let end = (half * 7 + 7).min(bytes.len());
+    String::from_utf8_lossy(&bytes[start..end]).into_owned()
+}
+
+/// Apply the DES initial permutation to a 64-bit block, as (high, low) words
+pub(crate) fn des_ip(block: &[u8]) -> [u32; 2] {
+    let x = u64::from_be_bytes(block.try_into().unwrap());
+    let mut out = 0u64;
+    for (j, &p) in DES_IP.iter().enumerate() {
+        out |= ((x >> (64 - p)) & 1) << (63 - j);
+    }
+    [(out >> 32) as u32, out as u32]
+}
+
+fn decode_16(field: &str) -> Result<[u8; 16], Box<dyn Error>> {
+    let bytes = hex::decode(field)?;
+    bytes
+        .try_into()
+        .map_err(|_| "expected 32 hex characters".into())
+}
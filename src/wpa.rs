@@ -0,0 +1,213 @@
+//! WPA/WPA2-PSK PMKID and EAPOL handshake hashes (hashcat 22000)
+//!
+//! Both kinds start from `PMK = PBKDF2-HMAC-SHA1(passphrase, ESSID, 4096, 32)`:
+//!
+//! * PMKID: `HMAC-SHA1(PMK, "PMK Name" || AP MAC || client MAC)[..16]`
+//! * EAPOL: the KCK (first 16 bytes of the PTK expanded from the PMK, both
+//!   MACs and both nonces) authenticates the client's EAPOL-Key frame with
+//!   HMAC-MD5 (WPA1) or HMAC-SHA1 (WPA2)
+//!
+//! Handshakes using AES-CMAC (key version 3, 802.11w) are not supported,
+//! and no nonce error correction is attempted.
+
+use crate::{
+    append_hmac_md5_blocks_for, append_hmac_sha1_blocks_for, CrackerError, GpuCracker, Kernel,
+    ScanResult,
+};
+use std::error::Error;
+
+/// Longest EAPOL frame accepted, as in hashcat
+const MAX_EAPOL_LEN: usize = 256;
+/// Offsets into the EAPOL-Key frame
+const KEY_INFO_OFFSET: usize = 5;
+const NONCE_OFFSET: usize = 17;
+const MIC_OFFSET: usize = 81;
+
+/// What a WPA target line captured
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WpaCapture {
+    /// PMKID from the first message of a handshake (or an association)
+    Pmkid,
+    /// Second message of a 4-way handshake plus the AP nonce
+    Eapol { anonce: [u8; 32], eapol: Vec<u8> },
+}
+
+/// One hc22000 line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WpaTarget {
+    /// PMKID or EAPOL MIC
+    pub hash: [u8; 16],
+    pub mac_ap: [u8; 6],
+    pub mac_client: [u8; 6],
+    pub essid: Vec<u8>,
+    pub capture: WpaCapture,
+    /// hashcat's message pair byte, kept for round-tripping
+    pub message_pair: Option<u8>,
+}
+
+impl WpaTarget {
+    /// Parse a `WPA*01*pmkid*mac_ap*mac_client*essid***` or
+    /// `WPA*02*mic*mac_ap*mac_client*essid*anonce*eapol*messagepair` line
+    pub fn parse(line: &str) -> Result<Self, Box<dyn Error>> {
+        let fields: Vec<&str> = line.trim().split('*').collect();
+        if fields.len() != 9 || fields[0] != "WPA" {
+            return Err("expected a WPA*01* or WPA*02* line".into());
+        }
+
+        let essid = hex::decode(fields[5])?;
+        if essid.is_empty() || essid.len() > 32 {
+            return Err("ESSID must be 1 to 32 bytes".into());
+        }
+
+        let capture = match fields[1] {
+            "01" => WpaCapture::Pmkid,
+            "02" => {
+                let eapol = hex::decode(fields[7])?;
+                if eapol.len() < MIC_OFFSET + 16 || eapol.len() > MAX_EAPOL_LEN {
+                    return Err("EAPOL frame has an invalid length".into());
+                }
+                WpaCapture::Eapol {
+                    anonce: decode_array(fields[6])?,
+                    eapol,
+                }
+            }
+            other => return Err(format!("unknown WPA hash type {other}").into()),
+        };
+
+        let message_pair = if fields[8].is_empty() {
+            None
+        } else {
+            Some(u8::from_str_radix(fields[8], 16)?)
+        };
+
+        let target = Self {
+            hash: decode_array(fields[2])?,
+            mac_ap: decode_array(fields[3])?,
+            mac_client: decode_array(fields[4])?,
+            essid,
+            capture,
+            message_pair,
+        };
+        if let Some(version) = target.key_version() {
+            if version != 1 && version != 2 {
+                return Err(format!("EAPOL key version {version} is not supported").into());
+            }
+        }
+        Ok(target)
+    }
+
+    /// Parse every line of an hc22000 file, skipping lines that don't match
+    pub fn parse_dump(text: &str) -> Vec<Self> {
+        text.lines().filter_map(|l| Self::parse(l).ok()).collect()
+    }
+
+    /// The network name, for display
+    pub fn essid_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.essid).into_owned()
+    }
+
+    /// EAPOL key descriptor version (1 = HMAC-MD5, 2 = HMAC-SHA1 MIC)
+    fn key_version(&self) -> Option<u8> {
+        match &self.capture {
+            WpaCapture::Pmkid => None,
+            WpaCapture::Eapol { eapol, .. } => Some(eapol[KEY_INFO_OFFSET + 1] & 7),
+        }
+    }
+
+    /// Kernel parameters, laid out as `wpa_crack` expects
+    fn params(&self) -> Vec<u32> {
+        let mut params: Vec<u32> = if self.key_version() == Some(1) {
+            self.hash
+                .chunks_exact(4)
+                .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+                .collect()
+        } else {
+            self.hash
+                .chunks_exact(4)
+                .map(|w| u32::from_be_bytes(w.try_into().unwrap()))
+                .collect()
+        };
+        params.resize(8, 0);
+
+        // PBKDF2 salt blocks for the two SHA1-sized output blocks
+        for i in 1u32..=2 {
+            let mut salt = self.essid.clone();
+            salt.extend_from_slice(&i.to_be_bytes());
+            append_hmac_sha1_blocks_for(&salt, &mut params);
+        }
+
+        match &self.capture {
+            WpaCapture::Pmkid => {
+                let mut message = b"PMK Name".to_vec();
+                message.extend_from_slice(&self.mac_ap);
+                message.extend_from_slice(&self.mac_client);
+                let message_blocks = append_hmac_sha1_blocks_for(&message, &mut params);
+                params[5] = message_blocks;
+            }
+            WpaCapture::Eapol { anonce, eapol } => {
+                let snonce = &eapol[NONCE_OFFSET..NONCE_OFFSET + 32];
+                let mut message = b"Pairwise key expansion\0".to_vec();
+                message.extend_from_slice(self.mac_ap.min(self.mac_client).as_slice());
+                message.extend_from_slice(self.mac_ap.max(self.mac_client).as_slice());
+                message.extend_from_slice(anonce.as_slice().min(snonce));
+                message.extend_from_slice(anonce.as_slice().max(snonce));
+                message.push(0);
+                let message_blocks = append_hmac_sha1_blocks_for(&message, &mut params);
+                params[5] = message_blocks;
+
+                // The MIC is computed with its own field zeroed
+                let mut frame = eapol.clone();
+                frame[MIC_OFFSET..MIC_OFFSET + 16].fill(0);
+                let (check, frame_blocks) = if self.key_version() == Some(1) {
+                    (1, append_hmac_md5_blocks_for(&frame, &mut params))
+                } else {
+                    (2, append_hmac_sha1_blocks_for(&frame, &mut params))
+                };
+                params[4] = check;
+                params[6] = frame_blocks;
+            }
+        }
+        params
+    }
+}
+
+impl std::fmt::Display for WpaTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (kind, anonce, eapol) = match &self.capture {
+            WpaCapture::Pmkid => ("01", String::new(), String::new()),
+            WpaCapture::Eapol { anonce, eapol } => ("02", hex::encode(anonce), hex::encode(eapol)),
+        };
+        let message_pair = self
+            .message_pair
+            .map(|mp| format!("{mp:02x}"))
+            .unwrap_or_default();
+        write!(
+            f,
+            "WPA*{kind}*{}*{}*{}*{}*{anonce}*{eapol}*{message_pair}",
+            hex::encode(self.hash),
+            hex::encode(self.mac_ap),
+            hex::encode(self.mac_client),
+            hex::encode(&self.essid)
+        )
+    }
+}
+
+impl GpuCracker {
+    /// Crack a WPA PMKID or EAPOL handshake
+    ///
+    /// Candidates outside the 8 to 63 character passphrase range are skipped.
+    pub fn crack_wpa(
+        &mut self,
+        target: &WpaTarget,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let params = target.params();
+        self.crack_with(Kernel::Wpa, bytemuck::cast_slice(&params), &[], wordlist)
+    }
+}
+
+fn decode_array<const N: usize>(field: &str) -> Result<[u8; N], Box<dyn Error>> {
+    hex::decode(field)?
+        .try_into()
+        .map_err(|_| format!("expected {} hex characters", N * 2).into())
+}
@@ -0,0 +1,333 @@
+//! Legacy PKZIP (ZipCrypto) archive password recovery
+//!
+//! Traditional PKWARE encryption prefixes every file's data with a 12-byte
+//! encryption header whose last byte, once decrypted, must equal a check
+//! byte (the high byte of the CRC, or of the modification time when the
+//! sizes live in a data descriptor). `zipcrypto_crack` runs the key
+//! schedule and that check on the GPU for up to [`MAX_GPU_ENTRIES`] files.
+//! With a single file one wrong password in 256 still passes, so every GPU
+//! hit is confirmed on the CPU by decrypting (and inflating) a whole file
+//! and comparing its CRC.
+
+use crate::{events, CrackerError, CrackerEvent, GpuCracker, Kernel, ScanResult, RESULT_SLOTS};
+use std::error::Error;
+use std::io::Read;
+use std::path::Path;
+
+const LOCAL_HEADER_SIG: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIG: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIR_SIG: u32 = 0x0605_4b50;
+
+const FLAG_ENCRYPTED: u16 = 1 << 0;
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+const FLAG_STRONG_ENCRYPTION: u16 = 1 << 6;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+const METHOD_AES: u16 = 99;
+
+const ENCRYPTION_HEADER_LEN: usize = 12;
+
+/// Number of encryption headers the GPU kernel checks per candidate
+pub const MAX_GPU_ENTRIES: usize = 8;
+
+/// One ZipCrypto-encrypted file of an archive
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    /// Compression method (0 = stored, 8 = deflated)
+    pub method: u16,
+    pub crc32: u32,
+    /// Expected last byte of the decrypted encryption header
+    pub check_byte: u8,
+    /// Encrypted file data, starting with the 12-byte encryption header
+    pub data: Vec<u8>,
+}
+
+/// The ZipCrypto-encrypted files of an archive
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZipCryptoTarget {
+    pub entries: Vec<ZipEntry>,
+}
+
+impl ZipCryptoTarget {
+    /// Read the encrypted entries of a ZIP file on disk
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Read the encrypted entries from the raw bytes of a ZIP file
+    ///
+    /// Sizes and flags come from the central directory, since local headers
+    /// leave them zero when a data descriptor follows the file data.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let eocd = find_end_of_central_dir(data).ok_or("not a ZIP file")?;
+        let entry_count = read_u16(data, eocd + 10) as usize;
+        let mut pos = read_u32(data, eocd + 16) as usize;
+
+        let mut entries = Vec::new();
+        let mut skipped_aes = false;
+        for _ in 0..entry_count {
+            if pos + 46 > data.len() || read_u32(data, pos) != CENTRAL_HEADER_SIG {
+                return Err("central directory is truncated".into());
+            }
+            let flags = read_u16(data, pos + 8);
+            let method = read_u16(data, pos + 10);
+            let mod_time = read_u16(data, pos + 12);
+            let crc32 = read_u32(data, pos + 16);
+            let compressed_size = read_u32(data, pos + 20) as usize;
+            let name_len = read_u16(data, pos + 28) as usize;
+            let extra_len = read_u16(data, pos + 30) as usize;
+            let comment_len = read_u16(data, pos + 32) as usize;
+            let local_offset = read_u32(data, pos + 42) as usize;
+            let name_end = (pos + 46 + name_len).min(data.len());
+            let name = String::from_utf8_lossy(&data[pos + 46..name_end]).into_owned();
+            pos += 46 + name_len + extra_len + comment_len;
+
+            if flags & FLAG_ENCRYPTED == 0 {
+                continue;
+            }
+            if method == METHOD_AES || flags & FLAG_STRONG_ENCRYPTION != 0 {
+                skipped_aes = true;
+                continue;
+            }
+            if compressed_size == 0xFFFF_FFFF || local_offset == 0xFFFF_FFFF {
+                return Err("ZIP64 archives are not supported".into());
+            }
+
+            if local_offset + 30 > data.len() || read_u32(data, local_offset) != LOCAL_HEADER_SIG {
+                return Err(format!("local header of {name} is missing").into());
+            }
+            let start = local_offset
+                + 30
+                + read_u16(data, local_offset + 26) as usize
+                + read_u16(data, local_offset + 28) as usize;
+            if compressed_size < ENCRYPTION_HEADER_LEN || start + compressed_size > data.len() {
+                return Err(format!("data of {name} is truncated").into());
+            }
+
+            let check_byte = if flags & FLAG_DATA_DESCRIPTOR != 0 {
+                (mod_time >> 8) as u8
+            } else {
+                (crc32 >> 24) as u8
+            };
+            entries.push(ZipEntry {
+                name,
+                method,
+                crc32,
+                check_byte,
+                data: data[start..start + compressed_size].to_vec(),
+            });
+        }
+
+        if entries.is_empty() {
+            return Err(if skipped_aes {
+                "archive uses AES or strong encryption, not ZipCrypto".into()
+            } else {
+                "archive has no encrypted files".into()
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Check a password on the CPU by decrypting the smallest stored or
+    /// deflated file in full and comparing its CRC
+    pub fn verify(&self, password: &str) -> bool {
+        let keys = ZipKeys::new(password.as_bytes());
+        if !self.entries.iter().all(|e| e.check(&keys)) {
+            return false;
+        }
+
+        match self
+            .entries
+            .iter()
+            .filter(|e| e.method == METHOD_STORED || e.method == METHOD_DEFLATED)
+            .min_by_key(|e| e.data.len())
+        {
+            Some(entry) => entry
+                .decrypt(&keys)
+                .is_some_and(|p| crc32(&p) == entry.crc32),
+            // Nothing we can decompress; the header checks are all we have
+            None => true,
+        }
+    }
+
+    /// Positions in `batch` of the candidates to verify, from the result
+    /// slots the kernel filled
+    fn batch_hits(&self, batch: &[&str], slots: &[i32]) -> Vec<usize> {
+        if slots.len() >= RESULT_SLOTS {
+            // Every slot is taken, so the GPU may have dropped the password;
+            // verify the whole batch on the CPU instead
+            (0..batch.len()).collect()
+        } else {
+            slots
+                .iter()
+                .take_while(|&&hit| hit >= 0)
+                .map(|&hit| hit as usize)
+                .collect()
+        }
+    }
+
+    /// Target buffer layout expected by `zipcrypto_crack`
+    fn params(&self) -> Vec<u32> {
+        let gpu_entries = &self.entries[..self.entries.len().min(MAX_GPU_ENTRIES)];
+        let mut params = vec![0u32; 4];
+        params[0] = gpu_entries.len() as u32;
+        for entry in gpu_entries {
+            params.extend(
+                entry.data[..ENCRYPTION_HEADER_LEN]
+                    .chunks_exact(4)
+                    .map(|w| u32::from_le_bytes(w.try_into().unwrap())),
+            );
+            params.push(entry.check_byte as u32);
+        }
+        params
+    }
+}
+
+impl std::fmt::Display for ZipCryptoTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.entries.iter().map(|e| e.name.as_str()).collect();
+        write!(f, "ZipCrypto archive ({})", names.join(", "))
+    }
+}
+
+impl ZipEntry {
+    /// Whether the decrypted encryption header ends in the check byte
+    fn check(&self, keys: &ZipKeys) -> bool {
+        let mut keys = keys.clone();
+        let mut header = [0u8; ENCRYPTION_HEADER_LEN];
+        header.copy_from_slice(&self.data[..ENCRYPTION_HEADER_LEN]);
+        keys.decrypt(&mut header);
+        header[ENCRYPTION_HEADER_LEN - 1] == self.check_byte
+    }
+
+    /// Decrypt and decompress the file, or `None` if inflating fails
+    fn decrypt(&self, keys: &ZipKeys) -> Option<Vec<u8>> {
+        let mut keys = keys.clone();
+        let mut data = self.data.clone();
+        keys.decrypt(&mut data);
+        let body = &data[ENCRYPTION_HEADER_LEN..];
+
+        match self.method {
+            METHOD_STORED => Some(body.to_vec()),
+            METHOD_DEFLATED => {
+                let mut plain = Vec::new();
+                flate2::read::DeflateDecoder::new(body)
+                    .read_to_end(&mut plain)
+                    .ok()?;
+                Some(plain)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The three ZipCrypto cipher keys
+#[derive(Clone)]
+struct ZipKeys([u32; 3]);
+
+impl ZipKeys {
+    fn new(password: &[u8]) -> Self {
+        let mut keys = ZipKeys([0x1234_5678, 0x2345_6789, 0x3456_7890]);
+        for &b in password {
+            keys.update(b);
+        }
+        keys
+    }
+
+    fn update(&mut self, byte: u8) {
+        let k = &mut self.0;
+        k[0] = crc32_update(k[0], byte);
+        k[1] = k[1]
+            .wrapping_add(k[0] & 0xff)
+            .wrapping_mul(134775813)
+            .wrapping_add(1);
+        k[2] = crc32_update(k[2], (k[1] >> 24) as u8);
+    }
+
+    fn decrypt(&mut self, data: &mut [u8]) {
+        for b in data {
+            let temp = self.0[2] | 2;
+            *b ^= (temp.wrapping_mul(temp ^ 1) >> 8) as u8;
+            self.update(*b);
+        }
+    }
+}
+
+impl GpuCracker {
+    /// Recover the password of a ZipCrypto-encrypted archive
+    pub fn crack_zip(
+        &mut self,
+        target: &ZipCryptoTarget,
+        wordlist: &[&str],
+    ) -> Result<ScanResult, CrackerError> {
+        let params = target.params();
+        let mut found = None;
+        let events = self.events.clone();
+        let outcome = self.scan_with(
+            Kernel::ZipCrypto,
+            bytemuck::cast_slice(&params),
+            &[],
+            wordlist,
+            |batch, slots| {
+                found = target
+                    .batch_hits(batch, slots)
+                    .into_iter()
+                    .map(|hit| batch[hit])
+                    .find(|password| target.verify(password))
+                    .map(str::to_string);
+                if let Some(password) = &found {
+                    events::emit(&events, || CrackerEvent::HitFound {
+                        candidate: password.clone(),
+                    });
+                }
+                found.is_some()
+            },
+        )?;
+        Ok(ScanResult { found, outcome })
+    }
+}
+
+/// CRC-32 (IEEE) as used by ZIP
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &b| crc32_update(crc, b))
+}
+
+fn crc32_update(crc: u32, byte: u8) -> u32 {
+    let mut c = (crc ^ byte as u32) & 0xff;
+    for _ in 0..8 {
+        c = if c & 1 != 0 {
+            0xedb8_8320 ^ (c >> 1)
+        } else {
+            c >> 1
+        };
+    }
+    (crc >> 8) ^ c
+}
+
+/// Offset of the end of central directory record, searched from the end
+/// (it may be followed by a comment of up to 64 KiB)
+fn find_end_of_central_dir(data: &[u8]) -> Option<usize> {
+    if data.len() < 22 {
+        return None;
+    }
+    let earliest = data.len().saturating_sub(22 + 0xFFFF);
+    (earliest..=data.len() - 22)
+        .rev()
+        .find(|&pos| read_u32(data, pos) == END_OF_CENTRAL_DIR_SIG)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
@@ -0,0 +1,198 @@
+use rustcracker::*;
+
+// bcrypt("password", cost 4) and bcrypt("letmein", cost 6), same salt
+const BCRYPT_PASSWORD: &str = "$2b$04$alTxbELwWULpXVGra0Dqb.u4JtpltcZ9l1RyZl2SrF0gik3Ba7Bdy";
+const BCRYPT_LETMEIN: &str = "$2y$06$alTxbELwWULpXVGra0Dqb.znTj937Sy700jrRJ8aQYc8KsQMtlbK.";
+// OpenBSD test vector for "U*U"
+const BCRYPT_OPENBSD: &str = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+
+/// A cheap stand-in for a slow hash that counts how often it was checked
+struct CountingHash {
+    password: &'static str,
+    checked: std::sync::atomic::AtomicUsize,
+}
+
+impl SlowHash for CountingHash {
+    fn verify(&self, password: &str) -> bool {
+        self.checked
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        password == self.password
+    }
+
+    fn cost(&self) -> u32 {
+        0
+    }
+}
+
+#[test]
+fn test_parse_bcrypt() {
+    let target = BcryptTarget::parse(BCRYPT_PASSWORD).unwrap();
+    assert_eq!(target.variant, 'b');
+    assert_eq!(target.cost, 4);
+    assert_eq!(&target.salt, b"rustcracker-salt");
+    assert_eq!(target.to_string(), BCRYPT_PASSWORD);
+
+    // htpasswd and shadow style lines
+    let htpasswd = BcryptTarget::parse(&format!("alice:{BCRYPT_LETMEIN}")).unwrap();
+    assert_eq!(htpasswd.cost, 6);
+    assert_eq!(htpasswd.to_string(), BCRYPT_LETMEIN);
+    let shadow = format!("bob:{BCRYPT_OPENBSD}:19000:0:99999:7:::");
+    assert_eq!(
+        BcryptTarget::parse(&shadow).unwrap().to_string(),
+        BCRYPT_OPENBSD
+    );
+
+    assert!(
+        BcryptTarget::parse("$2x$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW")
+            .is_err()
+    );
+    assert!(BcryptTarget::parse("$2b$04$tooshort").is_err());
+    assert!(BcryptTarget::parse("5f4dcc3b5aa765d61d8327deb882cf99").is_err());
+}
+
+#[test]
+fn test_verify_bcrypt() {
+    let target = BcryptTarget::parse(BCRYPT_PASSWORD).unwrap();
+    assert!(target.verify("password"));
+    assert!(!target.verify("Password"));
+    assert!(BcryptTarget::parse(BCRYPT_OPENBSD).unwrap().verify("U*U"));
+}
+
+#[test]
+fn test_crack_bcrypt_on_worker_pool() {
+    let mut cracker = CpuCracker::with_threads(4);
+    assert_eq!(cracker.threads(), 4);
+
+    let dump = format!("alice:{BCRYPT_PASSWORD}\nbob:{BCRYPT_LETMEIN}\n# no hash here\n");
+    let targets = BcryptTarget::parse_dump(&dump);
+    assert_eq!(targets.len(), 2);
+
+    let wordlist = vec![
+        "123456", "qwerty", "letmein", "dragon", "password", "monkey",
+    ];
+    assert_eq!(
+        cracker.crack_bcrypt(&targets[0], &wordlist).found,
+        Some("password".to_string())
+    );
+    assert_eq!(
+        cracker.crack_bcrypt(&targets[1], &wordlist).found,
+        Some("letmein".to_string())
+    );
+    assert_eq!(
+        cracker.crack_bcrypt(&targets[0], &wordlist[..3]).found,
+        None
+    );
+}
+
+#[test]
+fn test_cpu_cracker_trait() {
+    let mut cracker = CpuCracker::with_threads(2);
+    let target = Target::Bcrypt(BcryptTarget::parse(BCRYPT_PASSWORD).unwrap());
+    assert!(cracker.supports(&target));
+
+    let wordlist = vec!["123456", "password"];
+    assert_eq!(
+        cracker.crack_target(&target, &wordlist).unwrap(),
+        ScanResult {
+            found: Some("password".to_string()),
+            outcome: ScanOutcome::Finished,
+        }
+    );
+    assert_eq!(
+        cracker.crack_target(&target, &wordlist[..1]).unwrap(),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::Exhausted,
+        }
+    );
+
+    // GPU modes are left to the GPU cracker
+    let md5 = Target::Md5(md5::compute(b"password").0);
+    assert!(!cracker.supports(&md5));
+    assert!(matches!(
+        cracker.crack_target(&md5, &wordlist),
+        Err(CrackerError::InvalidTarget(_))
+    ));
+}
+
+#[test]
+fn test_worker_pool_checks_every_candidate_once() {
+    let words: Vec<String> = (0..10_000).map(|i| format!("word{i}")).collect();
+    let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    let target = CountingHash {
+        password: "not in the list",
+        checked: Default::default(),
+    };
+    let mut cracker = CpuCracker::with_threads(3);
+    assert_eq!(cracker.crack_slow(&target, &wordlist).found, None);
+    assert_eq!(target.checked.into_inner(), wordlist.len());
+
+    let target = CountingHash {
+        password: "word9999",
+        checked: Default::default(),
+    };
+    assert_eq!(
+        cracker.crack_slow(&target, &wordlist).found,
+        Some("word9999".to_string())
+    );
+    assert!(cracker.crack_slow(&target, &[]).found.is_none());
+}
+
+#[test]
+fn test_cpu_cracker_stops_early() {
+    let words: Vec<String> = (0..10_000).map(|i| format!("word{i}")).collect();
+    let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    let target = CountingHash {
+        password: "word9999",
+        checked: Default::default(),
+    };
+    let mut cracker = CpuCracker::with_threads(3);
+
+    // A passed deadline stops the workers before their first candidate
+    cracker.set_deadline(Some(std::time::Instant::now()));
+    assert_eq!(
+        cracker.crack_slow(&target, &wordlist),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::TimedOut,
+        }
+    );
+    assert_eq!(target.checked.load(std::sync::atomic::Ordering::Relaxed), 0);
+    cracker.set_deadline(None);
+
+    let token = CancelToken::new();
+    cracker.set_cancel_token(Some(token.clone()));
+    token.cancel();
+    let bcrypt = Target::Bcrypt(BcryptTarget::parse(BCRYPT_PASSWORD).unwrap());
+    assert_eq!(
+        cracker.crack_target(&bcrypt, &["password"]).unwrap(),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::Cancelled,
+        }
+    );
+
+    cracker.set_cancel_token(None);
+    assert_eq!(
+        cracker.crack_slow(&target, &wordlist).found,
+        Some("word9999".to_string())
+    );
+}
+
+#[test]
+fn test_cpu_benchmark() {
+    let mut cpu = CpuCracker::with_threads(2);
+    let duration = std::time::Duration::from_millis(50);
+    assert!(matches!(
+        cpu.benchmark("md5", duration),
+        Err(CrackerError::InvalidOption(_))
+    ));
+
+    let result = cpu.benchmark("bcrypt", duration).unwrap();
+    assert_eq!(result.mode, "bcrypt");
+    assert_eq!(result.batch_size % 2, 0);
+    assert!(result.hashes >= result.batch_size);
+    assert!(result.hashes_per_sec > 0.0);
+    assert_eq!(result.gpu_time, None);
+}
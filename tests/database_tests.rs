@@ -0,0 +1,96 @@
+use rustcracker::*;
+
+// md5("password" || "postgres")
+const PG_POSTGRES_HASH: &str = "md532e12f215ba27cb750c9e093ce4b5127";
+
+#[test]
+fn test_parse_pg_shadow_lines() {
+    let target = PostgresMd5Target::parse(&format!("postgres:{PG_POSTGRES_HASH}")).unwrap();
+    assert_eq!(target.username, "postgres");
+    assert!(target.verify("password"));
+    assert!(!target.verify("Password"));
+
+    // psql table row and hashcat's hash:salt layout
+    let row = format!(" postgres | 10 | t | t | t | t | {PG_POSTGRES_HASH} | | ");
+    assert_eq!(PostgresMd5Target::parse(&row).unwrap(), target);
+    let hashcat = format!("{}:postgres", &PG_POSTGRES_HASH[3..]);
+    assert_eq!(PostgresMd5Target::parse(&hashcat).unwrap(), target);
+
+    assert!(PostgresMd5Target::parse(PG_POSTGRES_HASH).is_err());
+}
+
+#[test]
+fn test_parse_pg_dump_skips_other_rows() {
+    let dump = format!(
+        " usename  | passwd\n----------+---------\n postgres | {PG_POSTGRES_HASH}\n scram    | SCRAM-SHA-256$4096:abc\n(2 rows)\n"
+    );
+    let targets = PostgresMd5Target::parse_dump(&dump);
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].username, "postgres");
+}
+
+#[test]
+fn test_parse_mysql_lines() {
+    let native =
+        MysqlTarget::parse("localhost | root | *2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19").unwrap();
+    assert_eq!(native.username.as_deref(), Some("root"));
+    assert_eq!(
+        native.to_string(),
+        "root:*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"
+    );
+
+    let old = MysqlTarget::parse("admin:5d2e19393cc5ef67").unwrap();
+    assert_eq!(old.hash, MysqlHash::Old323([0x5d2e1939, 0x3cc5ef67]));
+    assert_eq!(old.username.as_deref(), Some("admin"));
+
+    let bare = MysqlTarget::parse("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19").unwrap();
+    assert_eq!(bare.username, None);
+
+    assert!(MysqlTarget::parse("root localhost").is_err());
+}
+
+#[test]
+fn test_mysql323_reference() {
+    assert_eq!(database::mysql323(b"password"), [0x5d2e1939, 0x3cc5ef67]);
+    // Whitespace is ignored by OLD_PASSWORD()
+    assert_eq!(
+        database::mysql323(b"pass word"),
+        database::mysql323(b"password")
+    );
+}
+
+#[tokio::test]
+async fn test_crack_postgres_md5() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let target = PostgresMd5Target::parse(&format!("postgres:{PG_POSTGRES_HASH}")).unwrap();
+    let wordlist = vec!["postgres", "admin", "password", "letmein"];
+    let result = cracker
+        .crack_postgres_md5(&target, &wordlist)
+        .unwrap()
+        .found;
+
+    assert_eq!(result, Some("password".to_string()));
+}
+
+#[tokio::test]
+async fn test_crack_mysql() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let wordlist = vec!["root", "mysql", "password", "letmein"];
+
+    let native = MysqlTarget::parse("root:*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19").unwrap();
+    assert_eq!(
+        cracker.crack_mysql(&native, &wordlist).unwrap().found,
+        Some("password".to_string())
+    );
+
+    let old = MysqlTarget::parse("root:5d2e19393cc5ef67").unwrap();
+    assert_eq!(
+        cracker.crack_mysql(&old, &wordlist).unwrap().found,
+        Some("password".to_string())
+    );
+
+    let wordlist = vec!["root", "mysql"];
+    assert_eq!(cracker.crack_mysql(&native, &wordlist).unwrap().found, None);
+}
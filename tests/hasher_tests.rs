@@ -0,0 +1,42 @@
+use rustcracker::*;
+
+#[tokio::test]
+async fn test_md5_digests() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    // Empty, one-block, multi-block and binary inputs
+    let inputs: Vec<Vec<u8>> = vec![
+        Vec::new(),
+        b"password".to_vec(),
+        vec![b'x'; 55],
+        vec![b'y'; 56],
+        vec![0xa5; 1000],
+        (0..=255).collect(),
+    ];
+    let expected: Vec<[u8; 16]> = inputs.iter().map(|i| md5::compute(i).0).collect();
+    assert_eq!(cracker.md5_digests(&inputs).unwrap(), expected);
+    assert_eq!(
+        cracker.md5_digest(b"password").unwrap(),
+        md5::compute(b"password").0
+    );
+    assert!(cracker.md5_digests::<&[u8]>(&[]).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_md5_digests_many_batches() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    // More records than one batch holds, in input order
+    let records: Vec<String> = (0..(BATCH_SIZE * 2 + 123))
+        .map(|i| format!("record-{i}"))
+        .collect();
+    let digests = cracker.md5_digests(&records).unwrap();
+    assert_eq!(digests.len(), records.len());
+    for (record, digest) in records.iter().zip(&digests).step_by(997) {
+        assert_eq!(*digest, md5::compute(record).0, "{record}");
+    }
+    assert_eq!(
+        *digests.last().unwrap(),
+        md5::compute(records.last().unwrap()).0
+    );
+}
@@ -77,7 +77,7 @@ async fn test_simple_crack() {
     ];
 
     let wordlist = vec!["wrong1", "wrong2", "password", "wrong3"];
-    let result = cracker.crack(&target_hash, &wordlist);
+    let result = cracker.crack(&target_hash, &wordlist).unwrap().found;
 
     assert_eq!(result, Some("password".to_string()));
 }
@@ -94,7 +94,7 @@ async fn test_crack_not_found() {
     ];
 
     let wordlist = vec!["wrong1", "wrong2", "wrong3", "wrong4"];
-    let result = cracker.crack(&target_hash, &wordlist);
+    let result = cracker.crack(&target_hash, &wordlist).unwrap().found;
 
     assert_eq!(result, None);
 }
@@ -117,7 +117,7 @@ async fn test_multiple_known_hashes() {
         target_hash.copy_from_slice(&hash_bytes);
 
         let wordlist = vec!["wrong1", "wrong2", expected_password, "wrong3"];
-        let result = cracker.crack(&target_hash, &wordlist);
+        let result = cracker.crack(&target_hash, &wordlist).unwrap().found;
 
         assert_eq!(
             result,
@@ -144,7 +144,7 @@ async fn test_large_batch() {
     wordlist.push(target_password.to_string());
     let wordlist_refs: Vec<&str> = wordlist.iter().map(|s| s.as_str()).collect();
 
-    let result = cracker.crack(&target_hash, &wordlist_refs);
+    let result = cracker.crack(&target_hash, &wordlist_refs).unwrap().found;
 
     assert_eq!(result, Some(target_password.to_string()));
 }
@@ -161,7 +161,7 @@ async fn test_empty_password() {
         .unwrap();
 
     let wordlist = vec!["", "test", "password"];
-    let result = cracker.crack(&target_hash, &wordlist);
+    let result = cracker.crack(&target_hash, &wordlist).unwrap().found;
 
     assert_eq!(result, Some("".to_string()));
 }
@@ -178,7 +178,765 @@ async fn test_long_password() {
     target_hash.copy_from_slice(&hash_bytes);
 
     let wordlist = vec!["short", "medium_length", target_password, "another"];
-    let result = cracker.crack(&target_hash, &wordlist);
+    let result = cracker.crack(&target_hash, &wordlist).unwrap().found;
 
     assert_eq!(result, Some(target_password.to_string()));
 }
+
+#[tokio::test]
+async fn test_candidate_too_long() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let too_long = "x".repeat(MAX_MSG_SIZE + 1);
+    let wordlist = vec![too_long.as_str(), "short"];
+    let target_hash = md5::compute(b"short").0;
+
+    // A single batch has to fit as a whole
+    assert!(matches!(
+        cracker.process_batch(&wordlist, &target_hash),
+        Err(CrackerError::CandidateTooLong { index: 0, len, max: MAX_MSG_SIZE })
+            if len == MAX_MSG_SIZE + 1
+    ));
+
+    // ... while a scan skips the candidate, and the hits after it still
+    // point at the right words
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some("short".to_string())
+    );
+    let long_hash = md5::compute(too_long.as_bytes()).0;
+    assert_eq!(cracker.crack(&long_hash, &wordlist).unwrap().found, None);
+
+    // UTF-16 modes count the encoded length
+    let wide = "x".repeat(MAX_MSG_SIZE / 2 + 1);
+    assert_eq!(
+        cracker
+            .crack_ntlm(&[0; 16], &[wide.as_str()])
+            .unwrap()
+            .found,
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_single_block_boundary() {
+    // 55 bytes is the longest candidate the single-block fast path takes;
+    // a batch holding a 56-byte one must fall back to the general kernel
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let fits = "a".repeat(55);
+    let spills = "b".repeat(56);
+    for password in [&fits, &spills] {
+        let target_hash = md5::compute(password.as_bytes()).0;
+        let mut wordlist: Vec<&str> = vec!["short"; BATCH_SIZE];
+        wordlist.push(&fits);
+        wordlist.push(&spills);
+
+        let result = cracker.crack(&target_hash, &wordlist).unwrap().found;
+        assert_eq!(result.as_deref(), Some(password.as_str()));
+    }
+
+    // One candidate per batch, so the 55-byte hit sits in a batch of its
+    // own and is found by the single-block kernel
+    let mut cracker = GpuCracker::with_options(GpuOptions {
+        batch_size: Some(1),
+        profile: true,
+        ..GpuOptions::default()
+    })
+    .await
+    .expect("Failed to initialize GPU");
+    let target_hash = md5::compute(fits.as_bytes()).0;
+    let result = cracker
+        .crack(&target_hash, &[spills.as_str(), &fits])
+        .unwrap()
+        .found;
+    assert_eq!(result.as_deref(), Some(fits.as_str()));
+    let report = cracker.profile_report().unwrap();
+    let kernels: Vec<&str> = report.kernels.iter().map(|k| k.kernel).collect();
+    assert_eq!(kernels, ["md5_crack", "md5_crack_short"]);
+}
+
+#[tokio::test]
+async fn test_zero_pipeline_depth_rejected() {
+    assert!(matches!(
+        GpuCracker::with_pipeline_depth(0).await,
+        Err(CrackerError::InvalidOption(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_pipeline_depths() {
+    // The match sits in the last of several batches, so every set of the
+    // ring is reused before it is found
+    let target = "deep_in_the_list";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let words: Vec<String> = (0..BATCH_SIZE * 5).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push(target);
+
+    for depth in [1, 2, 4] {
+        let mut cracker = GpuCracker::with_pipeline_depth(depth)
+            .await
+            .expect("Failed to initialize GPU");
+        assert_eq!(cracker.pipeline_depth(), depth);
+        assert_eq!(
+            cracker.crack(&target_hash, &wordlist).unwrap().found,
+            Some(target.to_string())
+        );
+        // An early stop must leave the ring ready for the next scan
+        assert_eq!(
+            cracker
+                .crack(&md5::compute(b"word3").0, &wordlist)
+                .unwrap()
+                .found,
+            Some("word3".to_string())
+        );
+        assert_eq!(
+            cracker.crack(&target_hash, &wordlist).unwrap().found,
+            Some(target.to_string())
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_zero_batch_size_rejected() {
+    let options = GpuOptions {
+        batch_size: Some(0),
+        ..GpuOptions::default()
+    };
+    assert!(matches!(
+        GpuCracker::with_options(options).await,
+        Err(CrackerError::InvalidOption(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_batch_sizes() {
+    let target = "past_a_few_batches";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let words: Vec<String> = (0..10_000).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push(target);
+
+    let options = GpuOptions {
+        batch_size: Some(1000),
+        ..GpuOptions::default()
+    };
+    let mut cracker = GpuCracker::with_options(options)
+        .await
+        .expect("Failed to initialize GPU");
+    assert_eq!(cracker.batch_size(), 1000);
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+
+    // Growing the batches reallocates the buffer sets
+    let largest = cracker.max_batch_size();
+    assert!(largest >= BATCH_SIZE);
+    cracker.set_batch_size(largest).unwrap();
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+    assert!(cracker.set_batch_size(largest + 1).is_err());
+    assert!(cracker.set_batch_size(0).is_err());
+}
+
+#[tokio::test]
+async fn test_autotune() {
+    // Another adapter's results, which tuning this one must keep
+    let other = "adapter Other GPU [1234:5678] other-driver 1.0\nmd5_crack 4096 64 1000\n";
+    let cache = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(
+        cache.path(),
+        format!("# rustcracker autotune cache\n{other}"),
+    )
+    .unwrap();
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let tuned = cracker.autotune_cached(cache.path()).unwrap();
+    assert!(tuned.iter().any(|result| result.kernel == "md5_crack"));
+    let text = std::fs::read_to_string(cache.path()).unwrap();
+    assert!(text.contains(other), "{text}");
+    assert_eq!(text.matches("adapter ").count(), 2);
+    for result in &tuned {
+        assert!(result.batch_size <= cracker.max_batch_size());
+        assert!([64, 128, 256].contains(&result.workgroup_size));
+        assert!(result.hashes_per_sec > 0.0);
+    }
+
+    // Tuned sizes still find the password, across batches of any size
+    let target_hash = md5::compute(b"word123456").0;
+    let words: Vec<String> = (0..MAX_BATCH_SIZE + 10)
+        .map(|i| format!("word{i}"))
+        .collect();
+    let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some("word123456".to_string())
+    );
+
+    // A second cracker on the same adapter reads the cache back
+    let options = GpuOptions {
+        tune_cache: Some(cache.path().to_path_buf()),
+        ..GpuOptions::default()
+    };
+    let mut cached = GpuCracker::with_options(options)
+        .await
+        .expect("Failed to initialize GPU");
+    let reread = cached.autotune_cached(cache.path()).unwrap();
+    assert_eq!(reread.len(), tuned.len());
+    for (a, b) in reread.iter().zip(&tuned) {
+        assert_eq!(
+            (a.kernel, a.batch_size, a.workgroup_size),
+            (b.kernel, b.batch_size, b.workgroup_size)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_zero_dispatch_target_rejected() {
+    let options = GpuOptions {
+        dispatch_target: std::time::Duration::ZERO,
+        ..GpuOptions::default()
+    };
+    assert!(matches!(
+        GpuCracker::with_options(options).await,
+        Err(CrackerError::InvalidOption(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_split_dispatches() {
+    let target = "split_over_dispatches";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let words: Vec<String> = (0..20_000).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push(target);
+
+    // A target this short leaves every dispatch a single workgroup
+    let options = GpuOptions {
+        dispatch_target: std::time::Duration::from_nanos(1),
+        ..GpuOptions::default()
+    };
+    let mut cracker = GpuCracker::with_options(options)
+        .await
+        .expect("Failed to initialize GPU");
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+
+    let digests = cracker.md5_digests(&wordlist).unwrap();
+    assert_eq!(digests.len(), wordlist.len());
+    assert_eq!(digests[wordlist.len() - 1], target_hash);
+}
+
+#[cfg(feature = "test-hooks")]
+#[tokio::test]
+async fn test_device_lost_recovery() {
+    let target = "after_the_reset";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let words: Vec<String> = (0..10_000).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push(target);
+
+    let options = GpuOptions {
+        batch_size: Some(1000),
+        ..GpuOptions::default()
+    };
+    let mut cracker = GpuCracker::with_options(options)
+        .await
+        .expect("Failed to initialize GPU");
+
+    // The device goes away with batches in flight, which are replayed
+    cracker.lose_device_after(3);
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+
+    // ... and the recreated device keeps working
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_events() {
+    let target = "reported_hit";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let words: Vec<String> = (0..2500).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push(target);
+
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let log = std::sync::Arc::clone(&events);
+    let options = GpuOptions {
+        batch_size: Some(1000),
+        on_event: Some(EventHandler::new(move |event| {
+            log.lock().unwrap().push(event.clone());
+        })),
+        ..GpuOptions::default()
+    };
+    let mut cracker = GpuCracker::with_options(options)
+        .await
+        .expect("Failed to initialize GPU");
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+
+    let events = events.lock().unwrap();
+    assert!(matches!(events[0], CrackerEvent::AdapterSelected { .. }));
+    let submitted = events
+        .iter()
+        .filter(|e| matches!(e, CrackerEvent::BatchSubmitted { .. }))
+        .count();
+    assert_eq!(submitted, 3);
+    let progress: Vec<&Progress> = events
+        .iter()
+        .filter_map(|e| match e {
+            CrackerEvent::Progress(progress) => Some(progress),
+            _ => None,
+        })
+        .collect();
+    let last = progress.last().expect("progress was reported");
+    assert_eq!(last.checked, wordlist.len());
+    assert_eq!(last.total, Some(wordlist.len()));
+    assert_eq!(last.fraction(), Some(1.0));
+    assert_eq!(last.eta(), Some(std::time::Duration::ZERO));
+    assert!(last.hashes_per_sec > 0.0);
+    assert_eq!(
+        last.gpu_hashes_per_sec.is_some(),
+        cracker.supports_timestamps()
+    );
+    assert_eq!(
+        events.last(),
+        Some(&CrackerEvent::HitFound {
+            candidate: target.to_string()
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_cancellation() {
+    let target = "cancelled_hit";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let mut words: Vec<String> = (0..5000).map(|i| format!("word{i}")).collect();
+    words[500] = target.to_string();
+    let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    let mut cracker = GpuCracker::with_options(GpuOptions {
+        batch_size: Some(1000),
+        ..GpuOptions::default()
+    })
+    .await
+    .expect("Failed to initialize GPU");
+
+    let missing = md5::compute(b"not in the wordlist").0;
+    assert_eq!(
+        cracker.crack(&missing, &wordlist).unwrap(),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::Exhausted,
+        }
+    );
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap(),
+        ScanResult {
+            found: Some(target.to_string()),
+            outcome: ScanOutcome::Finished,
+        }
+    );
+
+    // Cancelled before the first batch: nothing is checked
+    let token = CancelToken::new();
+    cracker.set_cancel_token(Some(token.clone()));
+    token.cancel();
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap(),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::Cancelled,
+        }
+    );
+
+    // Cancelled while the batch holding the hit is in flight: it is still
+    // read back
+    let token = CancelToken::new();
+    let canceller = token.clone();
+    cracker.set_cancel_token(Some(token));
+    cracker.set_event_handler(Some(EventHandler::new(move |event| {
+        if let CrackerEvent::BatchSubmitted { .. } = event {
+            canceller.cancel();
+        }
+    })));
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap(),
+        ScanResult {
+            found: Some(target.to_string()),
+            outcome: ScanOutcome::Finished,
+        }
+    );
+    assert_eq!(
+        cracker.crack(&missing, &wordlist).unwrap(),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::Cancelled,
+        }
+    );
+
+    cracker.set_cancel_token(None);
+    cracker.set_event_handler(None);
+    cracker.set_deadline(Some(std::time::Instant::now()));
+    assert_eq!(
+        cracker.crack(&missing, &wordlist).unwrap(),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::TimedOut,
+        }
+    );
+    cracker.set_deadline(None);
+    assert_eq!(
+        cracker.crack(&missing, &wordlist).unwrap(),
+        ScanResult {
+            found: None,
+            outcome: ScanOutcome::Exhausted,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_async_crack() {
+    let target = "async_hit";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let mut cracker = GpuCracker::with_options(GpuOptions {
+        batch_size: Some(1000),
+        ..GpuOptions::default()
+    })
+    .await
+    .expect("Failed to initialize GPU");
+
+    assert_eq!(
+        cracker
+            .process_batch_async(&["a", target, "b"], &target_hash)
+            .await
+            .unwrap(),
+        Some(1)
+    );
+
+    // The futures are Send, so they run on a spawned task
+    let words: Vec<String> = (0..4500)
+        .map(|i| format!("word{i}"))
+        .chain([target.to_string()])
+        .collect();
+    let (mut cracker, found) = tokio::spawn(async move {
+        let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+        let found = cracker.crack_async(&target_hash, &wordlist).await;
+        (cracker, found)
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        found.unwrap(),
+        ScanResult {
+            found: Some(target.to_string()),
+            outcome: ScanOutcome::Finished,
+        }
+    );
+
+    // Every match of a prefix comes out of the stream in wordlist order
+    let words: Vec<String> = (0..5000).map(|i| format!("word{i}")).collect();
+    let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    let prefix = PartialMd5Target::parse("a").unwrap();
+    let expected: Vec<String> = words
+        .iter()
+        .filter(|w| format!("{:x}", md5::compute(w)).starts_with('a'))
+        .cloned()
+        .collect();
+    let mut stream = cracker.crack_stream(&prefix, &wordlist);
+    let mut found = Vec::new();
+    while let Some(hit) = std::future::poll_fn(|cx| {
+        futures_core::Stream::poll_next(std::pin::Pin::new(&mut stream), cx)
+    })
+    .await
+    {
+        found.push(hit.unwrap());
+    }
+    assert_eq!(found, expected);
+    assert_eq!(stream.outcome(), Some(ScanOutcome::Exhausted));
+}
+
+#[tokio::test]
+async fn test_dropped_async_scan() {
+    let target = "after_the_drop";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let words: Vec<String> = (0..20_000).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push(target);
+    let mut cracker = GpuCracker::with_options(GpuOptions {
+        batch_size: Some(1000),
+        ..GpuOptions::default()
+    })
+    .await
+    .expect("Failed to initialize GPU");
+
+    // Dropped after its first hit, with later batches still in flight
+    let prefix = PartialMd5Target::parse("a").unwrap();
+    let mut stream = cracker.crack_stream(&prefix, &wordlist);
+    let first = std::future::poll_fn(|cx| {
+        futures_core::Stream::poll_next(std::pin::Pin::new(&mut stream), cx)
+    })
+    .await;
+    assert!(first.unwrap().is_ok());
+    drop(stream);
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+
+    // Dropped while waiting on its first readbacks
+    {
+        let mut scan = std::pin::pin!(cracker.crack_async(&target_hash, &wordlist));
+        let polled = std::future::poll_fn(|cx| {
+            std::task::Poll::Ready(std::future::Future::poll(scan.as_mut(), cx).is_pending())
+        })
+        .await;
+        assert!(polled);
+    }
+    assert_eq!(
+        cracker
+            .crack_async(&target_hash, &wordlist)
+            .await
+            .unwrap()
+            .found,
+        Some(target.to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_profiler() {
+    let target = "profiled_hit";
+    let target_hash = md5::compute(target.as_bytes()).0;
+    let words: Vec<String> = (0..4999).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push(target);
+
+    let mut cracker = GpuCracker::with_options(GpuOptions {
+        batch_size: Some(1000),
+        ..GpuOptions::default()
+    })
+    .await
+    .expect("Failed to initialize GPU");
+    assert_eq!(cracker.profile_report(), None);
+
+    // Tiny dispatches, so every batch is split and each part is timed
+    cracker.set_profiling(true);
+    cracker
+        .set_dispatch_target(std::time::Duration::from_nanos(1))
+        .unwrap();
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+
+    let report = cracker.profile_report().unwrap();
+    assert_eq!(report.kernels.len(), 1);
+    let profile = &report.kernels[0];
+    assert_eq!(profile.kernel, "md5_crack_short");
+    assert_eq!(profile.batches, 5);
+    assert_eq!(profile.candidates, 5000);
+    assert!(profile.dispatches > profile.batches);
+    if cracker.supports_timestamps() {
+        assert_eq!(profile.timed_dispatches, profile.dispatches);
+        assert!(profile.gpu_hashes_per_sec().is_some());
+    } else {
+        assert_eq!(profile.kernel_time, None);
+    }
+    assert!(report.to_string().contains("md5_crack_short"));
+
+    // The single-batch calls still time their dispatches
+    let (hit, gpu_time_ns) = cracker
+        .process_batch_with_timing(&["a", target], &target_hash)
+        .unwrap();
+    assert_eq!(hit, Some(1));
+    assert_eq!(gpu_time_ns.is_some(), cracker.supports_timestamps());
+
+    cracker.set_profiling(false);
+    assert_eq!(cracker.profile_report(), None);
+}
+
+#[tokio::test]
+async fn test_benchmark() {
+    let mut cracker = GpuCracker::with_options(GpuOptions {
+        batch_size: Some(4096),
+        ..GpuOptions::default()
+    })
+    .await
+    .expect("Failed to initialize GPU");
+
+    let duration = std::time::Duration::from_millis(200);
+    assert!(matches!(
+        cracker.benchmark("sha512", duration),
+        Err(CrackerError::InvalidOption(_))
+    ));
+    assert!(matches!(
+        cracker.benchmark("md5", std::time::Duration::ZERO),
+        Err(CrackerError::InvalidOption(_))
+    ));
+
+    for mode in BENCHMARK_MODES {
+        let result = cracker.benchmark(mode, duration).unwrap();
+        assert_eq!(result.mode, mode);
+        assert_eq!(result.batch_size, 4096);
+        assert!(result.hashes > 0, "{mode} hashed nothing");
+        assert!(result.hashes_per_sec > 0.0);
+        if !cracker.supports_timestamps() {
+            assert_eq!(result.gpu_time, None);
+        }
+    }
+
+    // The benchmark leaves profiling and the deadline as they were
+    assert_eq!(cracker.profile_report(), None);
+    let target = "after_benchmark";
+    assert_eq!(
+        cracker
+            .crack(&md5::compute(target.as_bytes()).0, &["a", target])
+            .unwrap()
+            .found,
+        Some(target.to_string())
+    );
+}
+
+#[test]
+fn test_custom_shader_validation() {
+    let shader = CustomShader::load(env!("shader.spv"), "md5_crack").unwrap();
+    assert_eq!(shader.entry_point(), "md5_crack");
+
+    // The MD5 kernel must exist and run 64 invocations per workgroup
+    assert!(matches!(
+        CustomShader::load(env!("shader.spv"), "no_such_kernel"),
+        Err(CrackerError::InvalidShader(_))
+    ));
+    assert!(matches!(
+        CustomShader::load(env!("shader.spv"), "md5_crack_wg128"),
+        Err(CrackerError::InvalidShader(_))
+    ));
+    assert!(matches!(
+        CustomShader::load("no/such/kernel.spv", "md5_crack"),
+        Err(CrackerError::ShaderFile(_))
+    ));
+    assert!(matches!(
+        CustomShader::from_spirv(b"not spirv", "md5_crack"),
+        Err(CrackerError::InvalidShader(_))
+    ));
+    assert!(matches!(
+        CustomShader::from_spirv(&[0; 20], "md5_crack"),
+        Err(CrackerError::InvalidShader(_))
+    ));
+
+    // A resource outside the bind group layout is rejected
+    let bytes = std::fs::read(env!("shader.spv")).unwrap();
+    let mut words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    // OpDecorate %id Binding 3
+    let binding = words
+        .windows(4)
+        .position(|w| w[0] == (4 << 16 | 71) && w[2] == 33 && w[3] == 3)
+        .expect("the shader declares binding 3");
+    words[binding + 3] = 7;
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    let error = CustomShader::from_spirv(&bytes, "md5_crack").unwrap_err();
+    assert!(error.to_string().contains("binding 7"), "{error}");
+}
+
+/// The shader module with its `md5_crack` entry point renamed to `name`,
+/// which must take as many words as the original
+fn rename_md5_entry_point(name: &str) -> Vec<u8> {
+    let bytes = std::fs::read(env!("shader.spv")).unwrap();
+    let mut words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let literal = |name: &str| -> Vec<u32> {
+        let mut bytes = name.as_bytes().to_vec();
+        bytes.resize((name.len() / 4 + 1) * 4, 0);
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    };
+    let (old, new) = (literal("md5_crack"), literal(name));
+    assert_eq!(old.len(), new.len(), "'{name}' must fit md5_crack's words");
+
+    // OpEntryPoint GLCompute %function "md5_crack" %interface...
+    let mut at = 5;
+    while at < words.len() {
+        let (count, opcode) = ((words[at] >> 16) as usize, words[at] & 0xffff);
+        let name_at = at + 3;
+        if opcode == 15 && words.get(name_at..name_at + old.len()) == Some(&old[..]) {
+            words[name_at..name_at + new.len()].copy_from_slice(&new);
+            return words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        }
+        at += count.max(1);
+    }
+    panic!("the shader has no md5_crack entry point");
+}
+
+#[tokio::test]
+async fn test_custom_shader_entry_point() {
+    let bytes = rename_md5_entry_point("custom_md5");
+    assert!(matches!(
+        CustomShader::from_spirv(&bytes, "md5_crack"),
+        Err(CrackerError::InvalidShader(_))
+    ));
+    let shader = CustomShader::from_spirv(&bytes, "custom_md5").unwrap();
+    let mut cracker = GpuCracker::with_shader(shader)
+        .await
+        .expect("Failed to initialize GPU");
+
+    // Candidates past one MD5 block go to the MD5 kernel rather than the
+    // module's md5_crack_short
+    let words: Vec<String> = (0..500).map(|i| format!("{i:064}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    let target = "a candidate too long for md5_crack_short to take it, run by custom_md5";
+    wordlist.push(target);
+    let target_hash = md5::compute(target.as_bytes()).0;
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some(target.to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_custom_shader() {
+    let shader = CustomShader::load(env!("shader.spv"), "md5_crack").unwrap();
+    let mut cracker = GpuCracker::with_shader(shader)
+        .await
+        .expect("Failed to initialize GPU");
+
+    let words: Vec<String> = (0..2000).map(|i| format!("word{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.push("custom_kernel_hit");
+    let target_hash = md5::compute(b"custom_kernel_hit").0;
+    assert_eq!(
+        cracker.crack(&target_hash, &wordlist).unwrap().found,
+        Some("custom_kernel_hit".to_string())
+    );
+
+    // Other modes run the module's kernels of the same name
+    wordlist.push("password");
+    let ntlm_hash: [u8; 16] = hex::decode("8846f7eaee8fb117ad06bdd830b7586c")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(
+        cracker.crack_ntlm(&ntlm_hash, &wordlist).unwrap().found,
+        Some("password".to_string())
+    );
+}
@@ -0,0 +1,72 @@
+use rustcracker::*;
+
+// hashcat example hashes for modes 5600 and 5500, password "hashcat"
+const NETNTLMV2: &str = "admin::N46iSNekpT:08ca45b7d7ea58ee:88dcbe4446168966a153a0064958dac6:5c7830315c7830310000000000000b45c67103d07d7b95acd12ffa11230e0000000052920b85f78d013c31cdb3b92f5d765c783030";
+const NETNTLMV1: &str = "u4-netntlm::kNS:338d08f8e26de93300000000000000000000000000000000:9526fb8c23a90751cdd619b6cea564742e1e4bf33006ba41:cb8086049ec4736c";
+
+#[test]
+fn test_parse_netntlmv2() {
+    let target = NetNtlmTarget::parse(NETNTLMV2).unwrap();
+    assert_eq!(target.username, "admin");
+    assert_eq!(target.domain, "N46iSNekpT");
+    match &target.response {
+        NetNtlmResponse::V2 { nt_proof, blob, .. } => {
+            assert_eq!(hex::encode(nt_proof), "88dcbe4446168966a153a0064958dac6");
+            assert_eq!(blob.len(), 53);
+        }
+        other => panic!("expected a v2 response, got {other:?}"),
+    }
+    assert_eq!(target.to_string(), NETNTLMV2);
+}
+
+#[test]
+fn test_parse_netntlmv1() {
+    let target = NetNtlmTarget::parse(NETNTLMV1).unwrap();
+    assert_eq!(target.username, "u4-netntlm");
+    assert!(matches!(target.response, NetNtlmResponse::V1 { .. }));
+    assert_eq!(target.to_string(), NETNTLMV1);
+}
+
+#[test]
+fn test_parse_responder_log() {
+    let log = format!(
+        "[*] [NBT-NS] Poisoned answer sent to 10.0.0.5\n\
+         [SMB] NTLMv2-SSP Client   : 10.0.0.5\n\
+         [SMB] NTLMv2-SSP Username : N46iSNekpT\\admin\n\
+         [SMB] NTLMv2-SSP Hash     : {NETNTLMV2}\n\
+         {NETNTLMV1}\n"
+    );
+    let targets = NetNtlmTarget::parse_dump(&log);
+    assert_eq!(targets.len(), 2);
+    assert_eq!(targets[0], NetNtlmTarget::parse(NETNTLMV2).unwrap());
+}
+
+#[test]
+fn test_parse_netntlm_rejects_bad_lines() {
+    assert!(NetNtlmTarget::parse("admin:N46iSNekpT:08ca45b7d7ea58ee").is_err());
+    assert!(NetNtlmTarget::parse(&NETNTLMV2.replace("08ca45b7", "08ca45")).is_err());
+
+    // Blobs that don't fit the GPU parameter buffer are refused up front
+    let huge = format!("{NETNTLMV2}{}", "00".repeat(4096));
+    assert!(NetNtlmTarget::parse(&huge).is_err());
+}
+
+#[tokio::test]
+async fn test_crack_netntlm() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let wordlist = vec!["password", "letmein", "hashcat", "Hashcat"];
+    for line in [NETNTLMV2, NETNTLMV1] {
+        let target = NetNtlmTarget::parse(line).unwrap();
+        assert_eq!(
+            cracker.crack_netntlm(&target, &wordlist).unwrap().found,
+            Some("hashcat".to_string())
+        );
+    }
+
+    let target = NetNtlmTarget::parse(NETNTLMV2).unwrap();
+    assert_eq!(
+        cracker.crack_netntlm(&target, &["password"]).unwrap().found,
+        None
+    );
+}
@@ -0,0 +1,148 @@
+use rustcracker::*;
+
+// hashcat example hash for mode 9700, password "hashcat"
+const OLDOFFICE_HASH: &str = "$oldoffice$1*04477077758555626246182730342136*b1b72ff351e41a7c68f6b45c4e938bd6*0d95331895e99f73ef8b6fbc4a78ac1a";
+
+/// Build a minimal 512-byte-sector compound file holding a single
+/// `Workbook` stream
+fn build_xls(workbook: &[u8]) -> Vec<u8> {
+    const END_OF_CHAIN: u32 = 0xFFFF_FFFE;
+    const FREE: u32 = 0xFFFF_FFFF;
+    assert_eq!(workbook.len() % 512, 0);
+    let stream_sectors = (workbook.len() / 512) as u32;
+
+    let mut header = vec![0u8; 512];
+    header[..8].copy_from_slice(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]);
+    header[0x18..0x1A].copy_from_slice(&0x3Eu16.to_le_bytes());
+    header[0x1A..0x1C].copy_from_slice(&3u16.to_le_bytes());
+    header[0x1C..0x1E].copy_from_slice(&0xFFFEu16.to_le_bytes());
+    header[0x1E..0x20].copy_from_slice(&9u16.to_le_bytes());
+    header[0x20..0x22].copy_from_slice(&6u16.to_le_bytes());
+    header[0x2C..0x30].copy_from_slice(&1u32.to_le_bytes()); // one FAT sector
+    header[0x30..0x34].copy_from_slice(&1u32.to_le_bytes()); // directory at sector 1
+    header[0x38..0x3C].copy_from_slice(&4096u32.to_le_bytes());
+    header[0x3C..0x40].copy_from_slice(&END_OF_CHAIN.to_le_bytes());
+    header[0x44..0x48].copy_from_slice(&END_OF_CHAIN.to_le_bytes());
+    for i in 0..109 {
+        let sector = if i == 0 { 0 } else { FREE };
+        header[0x4C + i * 4..0x50 + i * 4].copy_from_slice(&sector.to_le_bytes());
+    }
+
+    // FAT: sector 0 is the FAT itself, 1 the directory, 2.. the stream
+    let mut fat = vec![FREE; 128];
+    fat[0] = 0xFFFF_FFFD;
+    fat[1] = END_OF_CHAIN;
+    for i in 0..stream_sectors {
+        let sector = 2 + i as usize;
+        fat[sector] = if i + 1 == stream_sectors {
+            END_OF_CHAIN
+        } else {
+            sector as u32 + 1
+        };
+    }
+
+    let dir_entry = |name: &str, object_type: u8, start: u32, size: u32| {
+        let mut entry = vec![0u8; 128];
+        let units: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+        for (i, unit) in units.iter().enumerate() {
+            entry[i * 2..i * 2 + 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        entry[64..66].copy_from_slice(&((units.len() * 2) as u16).to_le_bytes());
+        entry[66] = object_type;
+        entry[68..80].copy_from_slice(&[0xFF; 12]); // no siblings or children
+        entry[116..120].copy_from_slice(&start.to_le_bytes());
+        entry[120..124].copy_from_slice(&size.to_le_bytes());
+        entry
+    };
+
+    let mut file = header;
+    file.extend(fat.iter().flat_map(|s| s.to_le_bytes()));
+    file.extend(dir_entry("Root Entry", 5, END_OF_CHAIN, 0));
+    file.extend(dir_entry("Workbook", 2, 2, workbook.len() as u32));
+    file.extend(vec![0u8; 256]);
+    file.extend_from_slice(workbook);
+    file
+}
+
+fn biff_record(record_type: u16, body: &[u8]) -> Vec<u8> {
+    let mut record = record_type.to_le_bytes().to_vec();
+    record.extend_from_slice(&(body.len() as u16).to_le_bytes());
+    record.extend_from_slice(body);
+    record
+}
+
+#[test]
+fn test_parse_oldoffice_hash() {
+    let target = OfficeRc4Target::parse(OLDOFFICE_HASH).unwrap();
+    assert_eq!(hex::encode(target.salt), "04477077758555626246182730342136");
+    assert_eq!(target.to_string(), OLDOFFICE_HASH);
+
+    // CPU reference check
+    assert!(target.verify("hashcat"));
+    assert!(!target.verify("hashcab"));
+}
+
+#[test]
+fn test_parse_oldoffice_rejects_cryptoapi() {
+    let line = OLDOFFICE_HASH.replace("$oldoffice$1", "$oldoffice$3");
+    assert!(OfficeRc4Target::parse(&line).is_err());
+    assert!(OfficeRc4Target::parse("$oldoffice$1*0011").is_err());
+}
+
+#[test]
+fn test_extract_from_xls() {
+    let expected = OfficeRc4Target::parse(OLDOFFICE_HASH).unwrap();
+
+    let mut filepass = Vec::new();
+    filepass.extend_from_slice(&1u16.to_le_bytes()); // RC4
+    filepass.extend_from_slice(&1u16.to_le_bytes()); // version 1.1
+    filepass.extend_from_slice(&1u16.to_le_bytes());
+    filepass.extend_from_slice(&expected.salt);
+    filepass.extend_from_slice(&expected.encrypted_verifier);
+    filepass.extend_from_slice(&expected.encrypted_verifier_hash);
+
+    let mut workbook = biff_record(0x0809, &[0u8; 16]); // BOF
+    workbook.extend(biff_record(0x002F, &filepass));
+    workbook.extend(biff_record(0x000A, &[])); // EOF
+    workbook.resize(4096, 0);
+
+    let target = OfficeRc4Target::from_bytes(&build_xls(&workbook)).unwrap();
+    assert_eq!(target, expected);
+}
+
+#[test]
+fn test_extract_from_unencrypted_xls() {
+    let mut workbook = biff_record(0x0809, &[0u8; 16]);
+    workbook.extend(biff_record(0x000A, &[]));
+    workbook.resize(4096, 0);
+
+    assert!(OfficeRc4Target::from_bytes(&build_xls(&workbook)).is_err());
+    assert!(OfficeRc4Target::from_bytes(b"not a compound file").is_err());
+
+    // Mini sectors are 64 bytes in every valid file; a wild shift is
+    // refused rather than overflowing
+    let mut file = build_xls(&workbook);
+    file[0x20..0x22].copy_from_slice(&64u16.to_le_bytes());
+    let error = OfficeRc4Target::from_bytes(&file).unwrap_err();
+    assert!(
+        error.to_string().contains("mini sector shift 64"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn test_crack_office_rc4() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let target = OfficeRc4Target::parse(OLDOFFICE_HASH).unwrap();
+    let wordlist = vec!["password", "letmein", "hashcat", "hashcat1"];
+    let result = cracker.crack_office_rc4(&target, &wordlist).unwrap().found;
+
+    assert_eq!(result, Some("hashcat".to_string()));
+
+    let wordlist = vec!["password", "letmein"];
+    assert_eq!(
+        cracker.crack_office_rc4(&target, &wordlist).unwrap().found,
+        None
+    );
+}
@@ -0,0 +1,108 @@
+use rustcracker::*;
+
+// md5("password") = 5f4dcc3b5aa765d61d8327deb882cf99
+const PASSWORD_MD5: &str = "5f4dcc3b5aa765d61d8327deb882cf99";
+
+fn digest(password: &str) -> [u8; 16] {
+    md5::compute(password).0
+}
+
+#[test]
+fn test_parse_prefix() {
+    let target = PartialMd5Target::parse("5f4dcc3b5aa765d6").unwrap();
+    assert_eq!(target.known_bits(), 64);
+    assert!(!target.is_full());
+    assert!(target.matches(&digest("password")));
+    assert!(!target.matches(&digest("hello")));
+    assert_eq!(target.to_string(), "5f4dcc3b5aa765d6");
+
+    // Odd lengths keep the high nibble of the last byte
+    let target = PartialMd5Target::parse("5f4dc").unwrap();
+    assert_eq!(target.known_bits(), 20);
+    assert_eq!(target.mask[..3], [0xff, 0xff, 0xf0]);
+    assert!(target.matches(&digest("password")));
+    assert_eq!(target.to_string(), "5f4dc");
+
+    let full = PartialMd5Target::parse(PASSWORD_MD5).unwrap();
+    assert!(full.is_full());
+    assert_eq!(full, PartialMd5Target::full(digest("password")));
+    assert_eq!(full.to_string(), PASSWORD_MD5);
+}
+
+#[test]
+fn test_parse_mask() {
+    // Only the last four bytes are known
+    let line = format!("{PASSWORD_MD5}/000000000000000000000000ffffffff");
+    let target = PartialMd5Target::parse(&line).unwrap();
+    assert_eq!(target.known_bits(), 32);
+    assert_eq!(target.digest[..12], [0; 12]);
+    assert!(target.matches(&digest("password")));
+    assert_eq!(
+        target.to_string(),
+        "000000000000000000000000b882cf99/000000000000000000000000ffffffff"
+    );
+
+    let mut other = digest("password");
+    other[0] ^= 0xff;
+    assert!(target.matches(&other));
+    other[15] ^= 0x01;
+    assert!(!target.matches(&other));
+}
+
+#[test]
+fn test_parse_rejects_invalid() {
+    assert!(PartialMd5Target::parse("").is_err());
+    assert!(PartialMd5Target::parse("5f4dcz").is_err());
+    assert!(PartialMd5Target::parse(&format!("{PASSWORD_MD5}00")).is_err());
+    assert!(PartialMd5Target::parse(&format!("{PASSWORD_MD5}/ffff")).is_err());
+}
+
+#[tokio::test]
+async fn test_crack_md5_partial() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let words: Vec<String> = (0..100_000).map(|i| format!("candidate{i}")).collect();
+    let mut wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    wordlist.insert(70_000, "password");
+
+    // A long prefix behaves like a full hash
+    let target = PartialMd5Target::parse("5f4dcc3b5aa765d6").unwrap();
+    assert_eq!(
+        cracker
+            .crack_md5_partial(&target, &wordlist, true)
+            .unwrap()
+            .found,
+        vec!["password".to_string()]
+    );
+
+    // One hex character matches about one candidate in 16, more than the
+    // result slots hold per batch
+    let target = PartialMd5Target::parse("5").unwrap();
+    let expected: Vec<String> = wordlist
+        .iter()
+        .filter(|w| target.matches(&digest(w)))
+        .map(|w| w.to_string())
+        .collect();
+    assert!(expected.len() > 2048);
+    assert_eq!(
+        cracker
+            .crack_md5_partial(&target, &wordlist, true)
+            .unwrap()
+            .found,
+        expected
+    );
+    assert_eq!(
+        cracker
+            .crack_md5_partial(&target, &wordlist, false)
+            .unwrap()
+            .found,
+        expected[..1]
+    );
+
+    let target = PartialMd5Target::parse("5f4dcc3b5aa765d6").unwrap();
+    assert!(cracker
+        .crack_md5_partial(&target, &wordlist[..1000], true)
+        .unwrap()
+        .found
+        .is_empty());
+}
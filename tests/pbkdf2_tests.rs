@@ -0,0 +1,86 @@
+use rustcracker::*;
+
+// hashcat example hash for mode 10000 (Django PBKDF2-SHA256), password "hashcat"
+const DJANGO: &str =
+    "pbkdf2_sha256$20000$H0dPx8NeajVu$GiC4k5kqbbR9qWBlsRgDywNqC2vd9kqfk7zdorEnNas=";
+// passlib documentation example, password "password"
+const PASSLIB: &str =
+    "$pbkdf2-sha256$6400$0ZrzXitFSGltTQnBWOsdAw$Y11AchqV4b0sUisdZd0Xr97KWoymNE0LNNrnEgY4H9M";
+// SHA1 variants, password "letmein"
+const DJANGO_SHA1: &str = "pbkdf2_sha1$1500$rustcracker$mmGhtlCMRFdyn9HTN/tNZjfOqlA=";
+const PASSLIB_SHA1: &str = "$pbkdf2$2500$cnVzdGNyYWNrZXItc2FsdA$R54gg72efC20hC01HOfNYy1fJZU";
+
+#[test]
+fn test_parse_django() {
+    let target = Pbkdf2Target::parse(DJANGO).unwrap();
+    assert_eq!(target.format, Pbkdf2Format::Django);
+    assert_eq!(target.prf, Pbkdf2Prf::HmacSha256);
+    assert_eq!(target.iterations, 20000);
+    assert_eq!(target.salt, b"H0dPx8NeajVu");
+    assert_eq!(target.hash.len(), 32);
+    assert_eq!(target.to_string(), DJANGO);
+
+    let target = Pbkdf2Target::parse(&format!("admin:{DJANGO_SHA1}")).unwrap();
+    assert_eq!(target.prf, Pbkdf2Prf::HmacSha1);
+    assert_eq!(target.hash.len(), 20);
+    assert_eq!(target.to_string(), DJANGO_SHA1);
+}
+
+#[test]
+fn test_parse_passlib() {
+    let target = Pbkdf2Target::parse(PASSLIB).unwrap();
+    assert_eq!(target.format, Pbkdf2Format::Passlib);
+    assert_eq!(target.prf, Pbkdf2Prf::HmacSha256);
+    assert_eq!(target.iterations, 6400);
+    assert_eq!(target.salt.len(), 16);
+    assert_eq!(target.to_string(), PASSLIB);
+
+    let target = Pbkdf2Target::parse(PASSLIB_SHA1).unwrap();
+    assert_eq!(target.prf, Pbkdf2Prf::HmacSha1);
+    assert_eq!(target.salt, b"rustcracker-salt");
+    assert_eq!(target.to_string(), PASSLIB_SHA1);
+}
+
+#[test]
+fn test_parse_rejects_unsupported() {
+    assert!(Pbkdf2Target::parse("$pbkdf2-sha512$25000$c2FsdA$aGFzaA").is_err());
+    assert!(Pbkdf2Target::parse(
+        "pbkdf2_sha256$0$salt$GiC4k5kqbbR9qWBlsRgDywNqC2vd9kqfk7zdorEnNas="
+    )
+    .is_err());
+    assert!(Pbkdf2Target::parse(
+        "pbkdf2_sha256$4294967295$salt$GiC4k5kqbbR9qWBlsRgDywNqC2vd9kqfk7zdorEnNas="
+    )
+    .is_err());
+    assert!(Pbkdf2Target::parse("pbkdf2_sha256$20000$salt").is_err());
+    assert!(Pbkdf2Target::parse("5f4dcc3b5aa765d61d8327deb882cf99").is_err());
+
+    let dump = format!("{DJANGO}\nnot a hash\n{PASSLIB}\n");
+    assert_eq!(Pbkdf2Target::parse_dump(&dump).len(), 2);
+}
+
+#[tokio::test]
+async fn test_crack_pbkdf2() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let wordlist = vec!["123456", "password", "hashcat", "letmein"];
+    for (line, password) in [
+        (DJANGO, "hashcat"),
+        (PASSLIB, "password"),
+        (DJANGO_SHA1, "letmein"),
+        (PASSLIB_SHA1, "letmein"),
+    ] {
+        let target = Pbkdf2Target::parse(line).unwrap();
+        assert_eq!(
+            cracker.crack_pbkdf2(&target, &wordlist).unwrap().found,
+            Some(password.to_string()),
+            "{line}"
+        );
+    }
+
+    let target = Pbkdf2Target::parse(DJANGO).unwrap();
+    assert_eq!(
+        cracker.crack_pbkdf2(&target, &["password"]).unwrap().found,
+        None
+    );
+}
@@ -0,0 +1,121 @@
+use rustcracker::*;
+
+fn md5_hex(data: &str) -> String {
+    format!("{:x}", md5::compute(data))
+}
+
+#[test]
+fn test_parse_mask() {
+    let generator = CandidateGenerator::parse_mask("ab?d??").unwrap();
+    let CandidateGenerator::Mask(positions) = &generator else {
+        panic!("expected a mask, got {generator:?}");
+    };
+    assert_eq!(positions.len(), 4);
+    assert_eq!(positions[0], b"a");
+    assert_eq!(positions[2], b"0123456789");
+    assert_eq!(positions[3], b"?");
+
+    let CandidateGenerator::Mask(positions) = CandidateGenerator::parse_mask("?a").unwrap() else {
+        unreachable!()
+    };
+    assert_eq!(positions[0].len(), 95);
+
+    assert!(CandidateGenerator::parse_mask("?x").is_err());
+    assert!(CandidateGenerator::parse_mask("abc?").is_err());
+    assert!(CandidateGenerator::parse_mask("").is_err());
+}
+
+#[test]
+fn test_parse_charset() {
+    assert_eq!(
+        CandidateGenerator::parse_charset("?dabc1").unwrap(),
+        b"0123456789abc"
+    );
+    assert_eq!(CandidateGenerator::parse_charset("?h?H").unwrap().len(), 22);
+}
+
+#[test]
+fn test_keyspace() {
+    let mask = CandidateGenerator::parse_mask("id-?l?d?d").unwrap();
+    assert_eq!(mask.keyspace(), Some(26 * 100));
+    let counter = CandidateGenerator::Counter {
+        charset: b"0123456789".to_vec(),
+        max_len: 3,
+    };
+    assert_eq!(counter.keyspace(), Some(10 + 100 + 1000));
+
+    // Too large to count
+    let huge = CandidateGenerator::parse_mask(&"?a".repeat(20)).unwrap();
+    assert_eq!(huge.keyspace(), None);
+}
+
+#[test]
+fn test_vanity_search_limits() {
+    let target = PartialMd5Target::parse("0000").unwrap();
+    let digits = || CandidateGenerator::parse_mask("?d?d?d").unwrap();
+    assert!(VanitySearch::new("pow-", digits(), target).is_ok());
+
+    // Candidates must fit one MD5 block
+    assert!(VanitySearch::new(&"x".repeat(53), digits(), target).is_err());
+    let counter = CandidateGenerator::Counter {
+        charset: b"0123456789".to_vec(),
+        max_len: 40,
+    };
+    assert!(VanitySearch::new("pow-", counter, target).is_err());
+
+    let empty = CandidateGenerator::Counter {
+        charset: Vec::new(),
+        max_len: 4,
+    };
+    assert!(VanitySearch::new("pow-", empty, target).is_err());
+    let non_ascii = CandidateGenerator::parse_mask("é").unwrap();
+    assert!(VanitySearch::new("pow-", non_ascii, target).is_err());
+}
+
+#[tokio::test]
+async fn test_vanity_search() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    // Counter mode: shortest candidates first, in keyspace order
+    let target = PartialMd5Target::parse("00").unwrap();
+    let counter = CandidateGenerator::Counter {
+        charset: b"0123456789".to_vec(),
+        max_len: 6,
+    };
+    let search = VanitySearch::new("pow-", counter, target).unwrap();
+    let mut expected = Vec::new();
+    'outer: for len in 1..=6u32 {
+        for n in 0..10u32.pow(len) {
+            let candidate = format!("pow-{n:0width$}", width = len as usize);
+            if md5_hex(&candidate).starts_with("00") {
+                expected.push(candidate);
+                if expected.len() == 5 {
+                    break 'outer;
+                }
+            }
+        }
+    }
+    let mut found = Vec::new();
+    let count = cracker
+        .vanity_search(&search, 5, |s| found.push(s.to_string()))
+        .unwrap()
+        .found;
+    assert_eq!(count, 5);
+    assert_eq!(found, expected);
+
+    // Mask mode over a keyspace smaller than one batch, finding everything
+    let target = PartialMd5Target::parse("a").unwrap();
+    let mask = CandidateGenerator::parse_mask("id-?l?d?d").unwrap();
+    let search = VanitySearch::new("", mask, target).unwrap();
+    let mut found = Vec::new();
+    let count = cracker
+        .vanity_search(&search, usize::MAX, |s| found.push(s.to_string()))
+        .unwrap()
+        .found;
+    let expected: Vec<String> = (b'a'..=b'z')
+        .flat_map(|c| (0..100).map(move |n| format!("id-{}{n:02}", c as char)))
+        .filter(|s| md5_hex(s).starts_with('a'))
+        .collect();
+    assert_eq!(count, expected.len());
+    assert_eq!(found, expected);
+}
@@ -0,0 +1,110 @@
+use rustcracker::*;
+
+// LM and NTLM hashes of "password"
+const LM_PASSWORD: &str = "e52cac67419a9a224a3b108f3fa6cb6d";
+const NT_PASSWORD: &str = "8846f7eaee8fb117ad06bdd830b7586c";
+
+fn hash16(hex_str: &str) -> [u8; 16] {
+    hex::decode(hex_str).unwrap().try_into().unwrap()
+}
+
+#[test]
+fn test_parse_pwdump_lines() {
+    let entry = PwdumpEntry::parse(&format!("alice:1001:{LM_PASSWORD}:{NT_PASSWORD}:::")).unwrap();
+    assert_eq!(entry.username, "alice");
+    assert_eq!(entry.rid, Some(1001));
+    assert_eq!(entry.lm, Some(hash16(LM_PASSWORD)));
+    assert_eq!(entry.nt, hash16(NT_PASSWORD));
+
+    // LM storage disabled
+    let entry = PwdumpEntry::parse(&format!(
+        "bob:1002:aad3b435b51404eeaad3b435b51404ee:{NT_PASSWORD}:::"
+    ))
+    .unwrap();
+    assert_eq!(entry.lm, None);
+    let entry = PwdumpEntry::parse(&format!(
+        "carol:1003:NO PASSWORD*********************:{NT_PASSWORD}:::"
+    ))
+    .unwrap();
+    assert_eq!(entry.lm, None);
+
+    assert!(PwdumpEntry::parse("alice:1001").is_err());
+    assert!(PwdumpEntry::parse("alice:1001:xyz:abc:::").is_err());
+}
+
+#[test]
+fn test_parse_pwdump_file() {
+    let dump = format!(
+        "# dumped from SAM\nAdministrator:500:{LM_PASSWORD}:{NT_PASSWORD}:::\nGuest:501:aad3b435b51404eeaad3b435b51404ee:31d6cfe0d16ae931b73c59d7e0c089c0:::\n"
+    );
+    let entries = PwdumpEntry::parse_dump(&dump);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].username, "Administrator");
+    assert_eq!(entries[1].lm, None);
+}
+
+#[test]
+fn test_toggle_case_variants() {
+    let variants = windows::toggle_case_variants("A1B").unwrap();
+    assert_eq!(variants.len(), 4);
+    for expected in ["A1B", "a1B", "A1b", "a1b"] {
+        assert!(variants.iter().any(|v| v == expected), "missing {expected}");
+    }
+    assert_eq!(
+        windows::toggle_case_variants("123").unwrap(),
+        vec!["123".to_string()]
+    );
+    assert_eq!(
+        windows::toggle_case_variants("ABCDEFGHIJKLMN")
+            .unwrap()
+            .len(),
+        1 << windows::MAX_TOGGLE_LETTERS
+    );
+    assert!(matches!(
+        windows::toggle_case_variants(&"A".repeat(40)),
+        Err(CrackerError::InvalidTarget(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_crack_lm_and_ntlm() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let wordlist = vec!["admin", "letmein", "Password", "qwerty"];
+    assert_eq!(
+        cracker
+            .crack_lm(&hash16(LM_PASSWORD), &wordlist)
+            .unwrap()
+            .found,
+        Some("PASSWORD".to_string())
+    );
+    assert_eq!(
+        cracker
+            .crack_ntlm(&hash16(NT_PASSWORD), &wordlist)
+            .unwrap()
+            .found,
+        None
+    );
+
+    let wordlist = vec!["admin", "password"];
+    assert_eq!(
+        cracker
+            .crack_ntlm(&hash16(NT_PASSWORD), &wordlist)
+            .unwrap()
+            .found,
+        Some("password".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_crack_pwdump_fixes_case() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    // Only the uppercase form is in the wordlist; NTLM recovers the real case
+    let entry = PwdumpEntry::parse(&format!("alice:1001:{LM_PASSWORD}:{NT_PASSWORD}:::")).unwrap();
+    let wordlist = vec!["admin", "PASSWORD"];
+    assert_eq!(
+        cracker.crack_pwdump(&entry, &wordlist).unwrap().found,
+        Some("password".to_string())
+    );
+}
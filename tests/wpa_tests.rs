@@ -0,0 +1,63 @@
+use rustcracker::*;
+
+// hashcat example hashes for mode 22000, passphrase "hashcat!"
+const PMKID: &str =
+    "WPA*01*4d4fe7aac3a2cecab195321ceb99a7d0*fc690c158264*f4747f87f9f4*686173686361742d6573736964***";
+const EAPOL: &str = "WPA*02*024022795224bffca545276c3762686f*6466b38ec3fc*225edc49b7aa*54502d4c494e4b5f484153484341545f54455354*10e3be3b005a629e89de088d6a2fdc489db83ad4764f2d186b9cde15446e972e*0103007502010a0000000000000000000148ce2ccba9c1fda130ff2fbbfb4fd3b063d1a93920b0f7df54a5cbf787b16171000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001630140100000fac040100000fac040100000fac028000*a2";
+// The same handshake re-signed as WPA1 (key version 1, HMAC-MD5 MIC)
+const EAPOL_WPA1: &str = "WPA*02*d28be66ce37a683ef8e6da666ee21c03*6466b38ec3fc*225edc49b7aa*54502d4c494e4b5f484153484341545f54455354*10e3be3b005a629e89de088d6a2fdc489db83ad4764f2d186b9cde15446e972e*010300750201090000000000000000000148ce2ccba9c1fda130ff2fbbfb4fd3b063d1a93920b0f7df54a5cbf787b16171000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001630140100000fac040100000fac040100000fac028000*a2";
+
+#[test]
+fn test_parse_pmkid() {
+    let target = WpaTarget::parse(PMKID).unwrap();
+    assert_eq!(target.essid_lossy(), "hashcat-essid");
+    assert_eq!(hex::encode(target.mac_ap), "fc690c158264");
+    assert_eq!(target.capture, WpaCapture::Pmkid);
+    assert_eq!(target.message_pair, None);
+    assert_eq!(target.to_string(), PMKID);
+}
+
+#[test]
+fn test_parse_eapol() {
+    let target = WpaTarget::parse(EAPOL).unwrap();
+    assert_eq!(target.essid_lossy(), "TP-LINK_HASHCAT_TEST");
+    assert_eq!(target.message_pair, Some(0xa2));
+    match &target.capture {
+        WpaCapture::Eapol { eapol, .. } => assert_eq!(eapol.len(), 121),
+        other => panic!("expected an EAPOL capture, got {other:?}"),
+    }
+    assert_eq!(target.to_string(), EAPOL);
+}
+
+#[test]
+fn test_parse_wpa_rejects_bad_lines() {
+    assert!(WpaTarget::parse("WPA*03*00*00*00*00***").is_err());
+    assert!(WpaTarget::parse(&PMKID.replace("*fc690c158264*", "*fc690c1582*")).is_err());
+    // AES-CMAC (802.11w) handshakes
+    assert!(WpaTarget::parse(&EAPOL.replace("0103007502010a", "0103007502010b")).is_err());
+
+    let file = format!("{PMKID}\n\n# comment\n{EAPOL}\n");
+    assert_eq!(WpaTarget::parse_dump(&file).len(), 2);
+}
+
+#[tokio::test]
+async fn test_crack_wpa() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    // "hashcat" is too short to be a WPA passphrase and must be skipped
+    let wordlist = vec!["hashcat", "password", "hashcat!", "letmein1"];
+    for line in [PMKID, EAPOL, EAPOL_WPA1] {
+        let target = WpaTarget::parse(line).unwrap();
+        assert_eq!(
+            cracker.crack_wpa(&target, &wordlist).unwrap().found,
+            Some("hashcat!".to_string()),
+            "{line}"
+        );
+    }
+
+    let target = WpaTarget::parse(PMKID).unwrap();
+    assert_eq!(
+        cracker.crack_wpa(&target, &["password"]).unwrap().found,
+        None
+    );
+}
@@ -0,0 +1,297 @@
+use rustcracker::*;
+use std::io::Write;
+
+struct TestFile<'a> {
+    name: &'a str,
+    contents: &'a [u8],
+    deflate: bool,
+    data_descriptor: bool,
+}
+
+/// The ZipCrypto key schedule, as PKZIP 2.0 runs it
+struct TestKeys([u32; 3]);
+
+impl TestKeys {
+    fn new(password: &[u8]) -> Self {
+        let mut keys = TestKeys([0x1234_5678, 0x2345_6789, 0x3456_7890]);
+        for &b in password {
+            keys.update(b);
+        }
+        keys
+    }
+
+    fn update(&mut self, b: u8) {
+        let crc_update = |crc: u32, b: u8| {
+            let mut c = (crc ^ b as u32) & 0xff;
+            for _ in 0..8 {
+                c = if c & 1 != 0 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            (crc >> 8) ^ c
+        };
+        let k = &mut self.0;
+        k[0] = crc_update(k[0], b);
+        k[1] = k[1]
+            .wrapping_add(k[0] & 0xff)
+            .wrapping_mul(134775813)
+            .wrapping_add(1);
+        k[2] = crc_update(k[2], (k[1] >> 24) as u8);
+    }
+
+    /// Next byte of the key stream
+    fn stream(&self) -> u8 {
+        let temp = self.0[2] | 2;
+        (temp.wrapping_mul(temp ^ 1) >> 8) as u8
+    }
+}
+
+/// Encrypt `plain` with ZipCrypto
+fn zipcrypto_encrypt(password: &[u8], plain: &[u8]) -> Vec<u8> {
+    let mut keys = TestKeys::new(password);
+    plain
+        .iter()
+        .map(|&p| {
+            let c = p ^ keys.stream();
+            keys.update(p);
+            c
+        })
+        .collect()
+}
+
+/// Whether `password` decrypts `entry`'s encryption header to end in the
+/// check byte, the test the GPU kernel runs
+fn passes_header_check(password: &str, entry: &ZipEntry) -> bool {
+    let mut keys = TestKeys::new(password.as_bytes());
+    let mut last = 0;
+    for &c in &entry.data[..12] {
+        last = c ^ keys.stream();
+        keys.update(last);
+    }
+    last == entry.check_byte
+}
+
+/// Build a ZIP archive with every file ZipCrypto-encrypted under `password`
+fn build_zip(password: &str, files: &[TestFile]) -> Vec<u8> {
+    const MOD_TIME: u16 = 0x6b3c;
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (i, file) in files.iter().enumerate() {
+        let crc = zip::crc32(file.contents);
+        let body = if file.deflate {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(file.contents).unwrap();
+            encoder.finish().unwrap()
+        } else {
+            file.contents.to_vec()
+        };
+        let check = if file.data_descriptor {
+            (MOD_TIME >> 8) as u8
+        } else {
+            (crc >> 24) as u8
+        };
+        let mut header: Vec<u8> = (0..11).map(|j| (i * 31 + j * 7) as u8).collect();
+        header.push(check);
+        header.extend_from_slice(&body);
+        let encrypted = zipcrypto_encrypt(password.as_bytes(), &header);
+
+        let flags: u16 = if file.data_descriptor { 0x9 } else { 0x1 };
+        let method: u16 = if file.deflate { 8 } else { 0 };
+        let sizes = |v: &mut Vec<u8>, zeroed: bool| {
+            let (c, s, u) = if zeroed {
+                (0, 0, 0)
+            } else {
+                (crc, encrypted.len() as u32, file.contents.len() as u32)
+            };
+            v.extend_from_slice(&c.to_le_bytes());
+            v.extend_from_slice(&s.to_le_bytes());
+            v.extend_from_slice(&u.to_le_bytes());
+        };
+
+        let local_offset = out.len() as u32;
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes());
+        out.extend_from_slice(&flags.to_le_bytes());
+        out.extend_from_slice(&method.to_le_bytes());
+        out.extend_from_slice(&MOD_TIME.to_le_bytes());
+        out.extend_from_slice(&0x5a21u16.to_le_bytes());
+        sizes(&mut out, file.data_descriptor);
+        out.extend_from_slice(&(file.name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(file.name.as_bytes());
+        out.extend_from_slice(&encrypted);
+        if file.data_descriptor {
+            out.extend_from_slice(&0x0807_4b50u32.to_le_bytes());
+            sizes(&mut out, false);
+        }
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes());
+        central.extend_from_slice(&flags.to_le_bytes());
+        central.extend_from_slice(&method.to_le_bytes());
+        central.extend_from_slice(&MOD_TIME.to_le_bytes());
+        central.extend_from_slice(&0x5a21u16.to_le_bytes());
+        sizes(&mut central, false);
+        central.extend_from_slice(&(file.name.len() as u16).to_le_bytes());
+        central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+        central.extend_from_slice(&local_offset.to_le_bytes());
+        central.extend_from_slice(file.name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out
+}
+
+const README: &[u8] = b"Quarterly numbers are in the spreadsheet.\n";
+
+fn single_file_zip(password: &str) -> Vec<u8> {
+    build_zip(
+        password,
+        &[TestFile {
+            name: "readme.txt",
+            contents: README,
+            deflate: false,
+            data_descriptor: false,
+        }],
+    )
+}
+
+#[test]
+fn test_crc32_reference() {
+    assert_eq!(zip::crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(zip::crc32(b""), 0);
+}
+
+#[test]
+fn test_parse_zip_entries() {
+    let contents = "x".repeat(500);
+    let archive = build_zip(
+        "secret",
+        &[
+            TestFile {
+                name: "readme.txt",
+                contents: README,
+                deflate: false,
+                data_descriptor: false,
+            },
+            TestFile {
+                name: "data.csv",
+                contents: contents.as_bytes(),
+                deflate: true,
+                data_descriptor: true,
+            },
+        ],
+    );
+
+    let target = ZipCryptoTarget::from_bytes(&archive).unwrap();
+    assert_eq!(target.entries.len(), 2);
+    assert_eq!(target.entries[0].name, "readme.txt");
+    assert_eq!(target.entries[0].method, 0);
+    assert_eq!(
+        target.entries[0].check_byte,
+        (zip::crc32(README) >> 24) as u8
+    );
+    assert_eq!(target.entries[1].method, 8);
+    // Data descriptor: the check byte comes from the modification time
+    assert_eq!(target.entries[1].check_byte, 0x6b);
+    assert!(target.entries[1].data.len() < contents.len());
+
+    assert!(target.verify("secret"));
+    assert!(!target.verify("Secret"));
+}
+
+#[test]
+fn test_verify_rejects_header_false_positives() {
+    let target = ZipCryptoTarget::from_bytes(&single_file_zip("secret")).unwrap();
+
+    // Roughly one password in 256 passes the check byte; the full decrypt
+    // must still reject all of them
+    let mut header_hits = 0;
+    for i in 0..5000 {
+        let password = format!("guess{i}");
+        let mut single = target.clone();
+        single.entries[0].data.truncate(12);
+        single.entries[0].method = 1; // not decompressible: header check only
+        if single.verify(&password) {
+            header_hits += 1;
+            assert!(!target.verify(&password));
+        }
+    }
+    assert!(header_hits > 0);
+}
+
+#[test]
+fn test_parse_zip_rejects_unsupported() {
+    assert!(ZipCryptoTarget::from_bytes(b"PK\x03\x04 not really").is_err());
+
+    // Clear the encryption flag in the central directory
+    let mut archive = single_file_zip("secret");
+    let central = archive
+        .windows(4)
+        .position(|w| w == [0x50, 0x4b, 0x01, 0x02])
+        .unwrap();
+    archive[central + 8] &= !1;
+    assert!(ZipCryptoTarget::from_bytes(&archive).is_err());
+
+    // AES (method 99)
+    let mut archive = single_file_zip("secret");
+    archive[central + 10] = 99;
+    assert!(ZipCryptoTarget::from_bytes(&archive).is_err());
+}
+
+#[tokio::test]
+async fn test_crack_zip() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    let target = ZipCryptoTarget::from_bytes(&single_file_zip("secret")).unwrap();
+    // Enough wrong guesses that several pass the one-byte GPU check
+    let mut words: Vec<String> = (0..20000).map(|i| format!("guess{i}")).collect();
+    words.push("secret".to_string());
+    let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    assert_eq!(
+        cracker.crack_zip(&target, &wordlist).unwrap().found,
+        Some("secret".to_string())
+    );
+    assert_eq!(
+        cracker
+            .crack_zip(&target, &wordlist[..20000])
+            .unwrap()
+            .found,
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_crack_zip_result_overflow() {
+    let mut cracker = GpuCracker::new().await.expect("Failed to initialize GPU");
+
+    // More wrong guesses passing the header check than the GPU has result
+    // slots, in one batch with the password after them
+    let target = ZipCryptoTarget::from_bytes(&single_file_zip("secret")).unwrap();
+    let mut words: Vec<String> = (0..)
+        .map(|i| format!("guess{i}"))
+        .filter(|word| passes_header_check(word, &target.entries[0]))
+        .take(1500)
+        .collect();
+    words.push("secret".to_string());
+    let wordlist: Vec<&str> = words.iter().map(String::as_str).collect();
+    assert!(wordlist.len() <= cracker.batch_size());
+
+    let result = cracker.crack_zip(&target, &wordlist).unwrap();
+    assert_eq!(result.found, Some("secret".to_string()));
+    assert_eq!(result.outcome, ScanOutcome::Finished);
+}